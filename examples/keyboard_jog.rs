@@ -0,0 +1,21 @@
+use mycobot::teleop::*;
+use mycobot::*;
+use std::env;
+use std::io::{self, BufRead};
+
+pub fn main() -> Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut mycobot = MyCobotSerialOperator::new(&args[0], 115200);
+    let mut controller = KeyboardJogController::default();
+    println!("Type keys and press enter (q/a w/s e/d r/f t/g y/h, m: mode, x: quit)");
+    for line in io::stdin().lock().lines() {
+        for key in line?.chars() {
+            match controller.handle_key(&mut mycobot, key)? {
+                KeyAction::ModeChanged(mode) => println!("Mode: {:?}", mode),
+                KeyAction::Quit => return Ok(()),
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,45 @@
+use mycobot::kinematics::KinematicModel;
+use mycobot::*;
+use std::env;
+use std::fs::File;
+use std::io::BufReader;
+
+/// Stands in for the robot when simulating; dry run answers every command.
+struct Offline;
+
+impl Connection for Offline {
+    fn write(&mut self, _command: &[u8]) -> Result<()> {
+        Err(anyhow::anyhow!("No robot connected"))
+    }
+    fn read(&mut self) -> Result<Vec<u8>> {
+        Ok(Vec::new())
+    }
+    fn write_and_read(&mut self, _command: &[u8]) -> Result<Vec<u8>> {
+        Err(anyhow::anyhow!("No robot connected"))
+    }
+}
+
+/// Usage: replay_session <log> [port]
+/// Without a port the session is only simulated and each command printed.
+pub fn main() -> Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let frames = read_audit_log(BufReader::new(File::open(&args[0])?), AuditFormat::Binary)?;
+    let options = ReplayOptions::default();
+    match args.get(1) {
+        Some(port) => {
+            let mut mycobot = MyCobotSerialOperator::new(port, 115200);
+            let count = mycobot.replay_session(&frames, &options)?;
+            println!("Replayed {} commands", count);
+        }
+        None => {
+            let mut mycobot = MyCobotOperator::from_connection(Offline);
+            mycobot.enable_dry_run(KinematicModel::mycobot_280());
+            let count = mycobot.replay_session(&frames, &options)?;
+            for entry in mycobot.dry_run().map_or(&[][..], |d| d.log()) {
+                println!("{:?}", entry);
+            }
+            println!("Simulated {} commands", count);
+        }
+    }
+    Ok(())
+}
@@ -6,9 +6,11 @@ pub fn main() -> Result<()> {
     let mut mycobot = MyCobotSerialOperator::new(&args[0], 115200);
     let target = [0.0, 0.0, 0.0, 0.0, 50.0, 0.0];
     mycobot.set_color(255, 0, 0)?;
-    mycobot.sync_send_angles(&target, 50, 10.0)?;
-    let angles = mycobot.get_angles()?;
-    println!("Angles: {:?}", angles);
+    let result = mycobot.sync_send_angles(&target, 50, 10.0)?;
+    println!(
+        "Angles: {:?} (off by {:.2} deg)",
+        result.angles.0, result.error
+    );
     mycobot.set_color(0, 255, 0)?;
     Ok(())
 }
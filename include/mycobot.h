@@ -0,0 +1,40 @@
+/* C interface of the mycobot crate, built with `--features ffi`. */
+#ifndef MYCOBOT_H
+#define MYCOBOT_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define MYCOBOT_OK 0
+#define MYCOBOT_NULL_POINTER -1
+#define MYCOBOT_INVALID_ARGUMENT -2
+#define MYCOBOT_ERROR -3
+#define MYCOBOT_NO_DATA -4
+#define MYCOBOT_PANIC -5
+
+typedef struct MycobotHandle MycobotHandle;
+
+int mycobot_open(const char *port, uint32_t baudrate, MycobotHandle **out);
+void mycobot_close(MycobotHandle *handle);
+int mycobot_last_error(char *buffer, size_t len);
+
+int mycobot_power_on(MycobotHandle *handle);
+int mycobot_power_off(MycobotHandle *handle);
+int mycobot_release_all_servos(MycobotHandle *handle);
+int mycobot_stop(MycobotHandle *handle);
+
+int mycobot_send_angles(MycobotHandle *handle, const double degrees[6], uint8_t speed);
+int mycobot_get_angles(MycobotHandle *handle, double degrees[6]);
+int mycobot_send_coords(MycobotHandle *handle, const double coords[6], uint8_t speed, int mode);
+int mycobot_get_coords(MycobotHandle *handle, double coords[6]);
+int mycobot_is_moving(MycobotHandle *handle, int *moving);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,110 @@
+//! Joint-space angle arithmetic. Interpolation between two sets of joint
+//! angles is only correct if it respects each joint's travel: a joint at 160°
+//! going to -160° near the ±168° limits has to sweep back through zero, while
+//! a joint with more than a full turn of travel can take the short way round.
+
+use super::limits::JointLimits;
+
+/// Normalizes `degree` to -180..=180, keeping 180 rather than -180.
+pub fn wrap_degrees(degree: f64) -> f64 {
+    let wrapped = (degree + 180.0).rem_euclid(360.0) - 180.0;
+    if wrapped == -180.0 && degree > 0.0 {
+        180.0
+    } else {
+        wrapped
+    }
+}
+
+/// Six joint angles in degrees, J1 first.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct JointAngles(pub [f64; 6]);
+
+impl From<[f64; 6]> for JointAngles {
+    fn from(degrees: [f64; 6]) -> JointAngles {
+        JointAngles(degrees)
+    }
+}
+
+impl From<JointAngles> for [f64; 6] {
+    fn from(angles: JointAngles) -> [f64; 6] {
+        angles.0
+    }
+}
+
+impl JointAngles {
+    pub fn new(degrees: [f64; 6]) -> JointAngles {
+        JointAngles(degrees)
+    }
+    pub fn degrees(&self) -> [f64; 6] {
+        self.0
+    }
+    /// Each angle normalized to -180..=180.
+    pub fn wrapped(&self) -> JointAngles {
+        JointAngles(self.0.map(wrap_degrees))
+    }
+    /// Straight per-joint interpolation; `t` is clamped to 0..=1.
+    pub fn lerp(&self, other: &JointAngles, t: f64) -> JointAngles {
+        let t = t.clamp(0.0, 1.0);
+        let mut out = self.0;
+        for (o, to) in out.iter_mut().zip(other.0.iter()) {
+            *o += (to - *o) * t;
+        }
+        JointAngles(out)
+    }
+    /// Per-joint change from `self` to reach `other`, taking a wrapped
+    /// equivalent of the target (±360°) when it is closer and still within
+    /// `limits`. Joints that cannot reach any equivalent within their limits
+    /// keep the direct difference.
+    pub fn shortest_delta(&self, other: &JointAngles, limits: &JointLimits) -> [f64; 6] {
+        let mut delta = [0.0; 6];
+        for (j, d) in delta.iter_mut().enumerate() {
+            let (from, to) = (self.0[j], other.0[j]);
+            let direct = to - from;
+            let wrapped = wrap_degrees(direct);
+            let mut candidates = [wrapped, wrapped - 360.0, wrapped + 360.0];
+            candidates.sort_by(|a, b| a.abs().total_cmp(&b.abs()));
+            *d = candidates
+                .iter()
+                .copied()
+                .find(|c| limits.contains(j, from + c))
+                .unwrap_or(direct);
+        }
+        delta
+    }
+    /// Interpolates along `shortest_delta`; `t` is clamped to 0..=1.
+    pub fn lerp_shortest(&self, other: &JointAngles, t: f64, limits: &JointLimits) -> JointAngles {
+        let t = t.clamp(0.0, 1.0);
+        let delta = self.shortest_delta(other, limits);
+        let mut out = self.0;
+        for (o, d) in out.iter_mut().zip(delta.iter()) {
+            *o += d * t;
+        }
+        JointAngles(out)
+    }
+    /// Euclidean distance in degrees.
+    pub fn distance(&self, other: &JointAngles) -> f64 {
+        self.weighted_distance(other, &[1.0; 6])
+    }
+    /// Euclidean distance with each joint's difference scaled by `weights`,
+    /// e.g. to count base rotation more than wrist rotation.
+    pub fn weighted_distance(&self, other: &JointAngles, weights: &[f64; 6]) -> f64 {
+        (0..6)
+            .map(|j| ((other.0[j] - self.0[j]) * weights[j]).powi(2))
+            .sum::<f64>()
+            .sqrt()
+    }
+    /// Largest single-joint travel in degrees, which bounds the move time.
+    pub fn max_distance(&self, other: &JointAngles) -> f64 {
+        (0..6)
+            .map(|j| (other.0[j] - self.0[j]).abs())
+            .fold(0.0, f64::max)
+    }
+    /// Like `max_distance`, measured along `shortest_delta`.
+    pub fn max_distance_within(&self, other: &JointAngles, limits: &JointLimits) -> f64 {
+        self.shortest_delta(other, limits)
+            .iter()
+            .map(|d| d.abs())
+            .fold(0.0, f64::max)
+    }
+}
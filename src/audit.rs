@@ -0,0 +1,237 @@
+use super::io::Connection;
+use super::operator::MyCobotOperator;
+use super::tap::{Frame, FrameDirection};
+use anyhow::Result;
+use std::convert::TryInto;
+use std::io::{BufRead, ErrorKind, Write};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// On-disk format of a session log.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuditFormat {
+    /// Records of microseconds since the epoch (u64), direction (u8, 0 sent,
+    /// 1 received), frame length (u16) and the raw frame, little-endian.
+    Binary,
+    /// One JSON object per frame with the command name and decoded values.
+    #[cfg(feature = "json")]
+    Jsonl,
+}
+
+/// Connection wrapper appending every frame sent or received to a session log.
+/// Logging errors are returned from the connection call that caused them,
+/// so a full disk does not go unnoticed.
+pub struct AuditLog<C: Connection, W: Write> {
+    inner: C,
+    writer: W,
+    format: AuditFormat,
+}
+
+impl<C: Connection, W: Write> AuditLog<C, W> {
+    /// Open `writer` in append mode to keep the log append-only across sessions.
+    pub fn new(inner: C, writer: W, format: AuditFormat) -> AuditLog<C, W> {
+        AuditLog {
+            inner,
+            writer,
+            format,
+        }
+    }
+    pub fn into_inner(self) -> (C, W) {
+        (self.inner, self.writer)
+    }
+    fn record(&mut self, direction: FrameDirection, data: &[u8]) -> Result<()> {
+        for frame in Frame::parse(direction, data) {
+            write_record(&mut self.writer, self.format, &frame)?;
+        }
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+impl<C: Connection, W: Write> Connection for AuditLog<C, W> {
+    fn write(&mut self, command: &[u8]) -> Result<()> {
+        self.record(FrameDirection::Sent, command)?;
+        self.inner.write(command)
+    }
+    fn read(&mut self) -> Result<Vec<u8>> {
+        let res = self.inner.read()?;
+        self.record(FrameDirection::Received, &res)?;
+        Ok(res)
+    }
+    fn write_and_read(&mut self, command: &[u8]) -> Result<Vec<u8>> {
+        self.record(FrameDirection::Sent, command)?;
+        let res = self.inner.write_and_read(command)?;
+        self.record(FrameDirection::Received, &res)?;
+        Ok(res)
+    }
+    fn is_healthy(&mut self) -> bool {
+        self.inner.is_healthy()
+    }
+    fn set_read_timeout(&mut self, timeout: Duration) -> Result<()> {
+        self.inner.set_read_timeout(timeout)
+    }
+    fn clear_input_buffer(&mut self) -> Result<()> {
+        self.inner.clear_input_buffer()
+    }
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+}
+
+fn micros(timestamp: SystemTime) -> u64 {
+    timestamp
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_micros() as u64)
+}
+
+fn direction_byte(direction: FrameDirection) -> u8 {
+    match direction {
+        FrameDirection::Sent => 0,
+        FrameDirection::Received => 1,
+    }
+}
+
+/// Appends one frame to `writer` in `format`.
+pub fn write_record<W: Write>(writer: &mut W, format: AuditFormat, frame: &Frame) -> Result<()> {
+    match format {
+        AuditFormat::Binary => {
+            writer.write_all(&micros(frame.timestamp).to_le_bytes())?;
+            writer.write_all(&[direction_byte(frame.direction)])?;
+            writer.write_all(&(frame.raw.len() as u16).to_le_bytes())?;
+            writer.write_all(&frame.raw)?;
+        }
+        #[cfg(feature = "json")]
+        AuditFormat::Jsonl => {
+            let raw: String = frame.raw.iter().map(|b| format!("{:02x}", b)).collect();
+            let record = serde_json::json!({
+                "timestamp_us": micros(frame.timestamp),
+                "direction": match frame.direction {
+                    FrameDirection::Sent => "sent",
+                    FrameDirection::Received => "received",
+                },
+                "genre": frame.genre,
+                "command": frame.command().map(|c| c.name()),
+                "values": frame.decode(),
+                "raw": raw,
+            });
+            writeln!(writer, "{}", record)?;
+        }
+    }
+    Ok(())
+}
+
+/// Reads every frame of a session log written in `format`.
+pub fn read_audit_log<R: BufRead>(mut reader: R, format: AuditFormat) -> Result<Vec<Frame>> {
+    let mut frames = Vec::new();
+    match format {
+        AuditFormat::Binary => loop {
+            let mut head = [0u8; 11];
+            match reader.read_exact(&mut head) {
+                Ok(()) => {}
+                Err(e) if e.kind() == ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e.into()),
+            }
+            let us = u64::from_le_bytes(head[..8].try_into()?);
+            let direction = match head[8] {
+                0 => FrameDirection::Sent,
+                1 => FrameDirection::Received,
+                d => return Err(anyhow::anyhow!("Invalid direction byte {}", d)),
+            };
+            let mut raw = vec![0u8; u16::from_le_bytes([head[9], head[10]]) as usize];
+            reader.read_exact(&mut raw)?;
+            frames.extend(parse_logged(direction, &raw, us)?);
+        },
+        #[cfg(feature = "json")]
+        AuditFormat::Jsonl => {
+            for line in reader.lines() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let value: serde_json::Value = serde_json::from_str(&line)?;
+                let field = |name: &str| {
+                    value
+                        .get(name)
+                        .ok_or_else(|| anyhow::anyhow!("Missing {} in audit record", name))
+                };
+                let us = field("timestamp_us")?
+                    .as_u64()
+                    .ok_or_else(|| anyhow::anyhow!("Invalid timestamp in audit record"))?;
+                let direction = match field("direction")?.as_str() {
+                    Some("sent") => FrameDirection::Sent,
+                    Some("received") => FrameDirection::Received,
+                    _ => return Err(anyhow::anyhow!("Invalid direction in audit record")),
+                };
+                let hex = field("raw")?
+                    .as_str()
+                    .ok_or_else(|| anyhow::anyhow!("Invalid raw frame in audit record"))?;
+                let raw = (0..hex.len())
+                    .step_by(2)
+                    .map(|i| u8::from_str_radix(hex.get(i..i + 2).unwrap_or("zz"), 16))
+                    .collect::<std::result::Result<Vec<u8>, _>>()?;
+                frames.extend(parse_logged(direction, &raw, us)?);
+            }
+        }
+    }
+    Ok(frames)
+}
+
+fn parse_logged(direction: FrameDirection, raw: &[u8], us: u64) -> Result<Vec<Frame>> {
+    let mut parsed = Frame::parse(direction, raw);
+    if parsed.is_empty() {
+        return Err(anyhow::anyhow!("Corrupt frame in audit log"));
+    }
+    for frame in parsed.iter_mut() {
+        frame.timestamp = UNIX_EPOCH + Duration::from_micros(us);
+    }
+    Ok(parsed)
+}
+
+#[derive(Clone, Debug)]
+pub struct ReplayOptions {
+    /// Reproduce the original gaps between commands instead of sending back-to-back.
+    pub realtime: bool,
+    /// Upper bound on any one reproduced gap.
+    pub max_gap: Duration,
+}
+
+impl Default for ReplayOptions {
+    fn default() -> ReplayOptions {
+        ReplayOptions {
+            realtime: true,
+            max_gap: Duration::from_secs(5),
+        }
+    }
+}
+
+impl<T: Connection> MyCobotOperator<T> {
+    /// Re-sends the sent frames of a logged session. Enable dry run first to
+    /// simulate the session instead of moving the robot. Received frames and
+    /// unknown genres are skipped. Returns the number of commands replayed.
+    pub fn replay_session(&mut self, frames: &[Frame], options: &ReplayOptions) -> Result<usize> {
+        let mut count = 0;
+        let mut previous: Option<SystemTime> = None;
+        for frame in frames {
+            if frame.direction != FrameDirection::Sent {
+                continue;
+            }
+            let command = match frame.command() {
+                Some(command) => command,
+                None => continue,
+            };
+            if options.realtime {
+                if let Some(gap) = previous.and_then(|p| frame.timestamp.duration_since(p).ok()) {
+                    thread::sleep(gap.min(options.max_gap));
+                }
+            }
+            previous = Some(frame.timestamp);
+            if command.response().is_empty() {
+                self.write_command(command, frame.data())?;
+            } else {
+                self.write_command_and_receive(command, frame.data())?;
+            }
+            count += 1;
+        }
+        Ok(count)
+    }
+}
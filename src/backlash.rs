@@ -0,0 +1,171 @@
+//! Gear backlash: each joint settles short of a target by a different amount
+//! depending on the side it approaches from. `estimate_backlash` measures
+//! the gap per joint, and once compensation is set, angle commands are
+//! offset by half of it in the direction of travel. After any other motion
+//! (coords, jog, raw angles, releasing servos) the direction is taken from
+//! the joint's current angle rather than the last commanded one.
+
+use super::common::Command;
+use super::conversion::int_vec_to_angles;
+use super::io::Connection;
+use super::operator::MyCobotOperator;
+use anyhow::Result;
+use std::thread;
+use std::time::Duration;
+
+/// Direction-dependent correction applied by `send_angle(s)`.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct BacklashCompensation {
+    backlash: [f64; 6],
+    last: [Option<f64>; 6],
+    direction: [f64; 6],
+}
+
+impl BacklashCompensation {
+    fn new(backlash: [f64; 6]) -> BacklashCompensation {
+        BacklashCompensation {
+            backlash,
+            last: [None; 6],
+            direction: [0.0; 6],
+        }
+    }
+    /// The angle to send for joint `index` (0-based) to end at `target`. A
+    /// joint keeps its last direction while its target doesn't change.
+    pub(crate) fn apply(&mut self, index: usize, target: f64) -> f64 {
+        if let Some(last) = self.last[index] {
+            let delta = target - last;
+            if delta.abs() > 1e-6 {
+                self.direction[index] = delta.signum();
+            }
+        }
+        self.last[index] = Some(target);
+        target + self.direction[index] * self.backlash[index] / 2.0
+    }
+    /// Forgets the last targets, after the arm moved in a way `apply` didn't see.
+    pub(crate) fn invalidate(&mut self) {
+        self.last = [None; 6];
+        self.direction = [0.0; 6];
+    }
+    fn seed(&mut self, angles: &[f64; 6]) {
+        for (last, angle) in self.last.iter_mut().zip(angles) {
+            last.get_or_insert(*angle);
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BacklashOptions {
+    /// Distance in degrees each approach starts from.
+    pub approach_deg: f64,
+    /// Approaches from each side per joint, averaged.
+    pub repetitions: usize,
+    pub speed: u8,
+    /// Pause after arriving before reading the angle back.
+    pub settle: Duration,
+    pub timeout_secs: f64,
+}
+
+impl Default for BacklashOptions {
+    fn default() -> BacklashOptions {
+        BacklashOptions {
+            approach_deg: 5.0,
+            repetitions: 3,
+            speed: 30,
+            settle: Duration::from_millis(500),
+            timeout_secs: 10.0,
+        }
+    }
+}
+
+impl<T: Connection> MyCobotOperator<T> {
+    /// Offsets angle commands by half of `backlash` (degrees per joint) in
+    /// the direction each joint is moving; `None` sends angles unchanged.
+    pub fn set_backlash_compensation(&mut self, backlash: Option<[f64; 6]>) {
+        self.backlash = backlash.map(BacklashCompensation::new);
+    }
+    pub fn backlash_compensation(&self) -> Option<[f64; 6]> {
+        self.backlash.as_ref().map(|c| c.backlash)
+    }
+    pub(crate) fn compensate_backlash(&mut self, index: usize, degree: f64) -> f64 {
+        let unknown = self
+            .backlash
+            .as_ref()
+            .is_some_and(|c| c.last[index].is_none());
+        if unknown {
+            // Firmware angles, the same space the targets here are in. When
+            // they can't be read (e.g. while batching) the joint gets no offset.
+            if let Ok(current) = self.get_angles_raw() {
+                if let Some(compensation) = &mut self.backlash {
+                    compensation.seed(&int_vec_to_angles(&current));
+                }
+            }
+        }
+        match &mut self.backlash {
+            Some(compensation) => compensation.apply(index, degree),
+            None => degree,
+        }
+    }
+    /// Called for every written genre: motion that didn't go through
+    /// `compensate_backlash` makes the last targets stale.
+    pub(crate) fn track_backlash(&mut self, genre: Command) {
+        let untracked = match genre {
+            Command::SendAngle | Command::SendAngles => false,
+            Command::ReleaseAllServos | Command::ReleaseServo | Command::SetFreeMode => true,
+            _ => genre.starts_motion(),
+        };
+        if untracked {
+            if let Some(compensation) = &mut self.backlash {
+                compensation.invalidate();
+            }
+        }
+    }
+    /// Measures per-joint backlash in degrees around `angles`: every joint in
+    /// turn approaches its angle from below and from above, and the gap
+    /// between where it settles is averaged over the repetitions.
+    /// Compensation is suspended while measuring; the result can be passed
+    /// to `set_backlash_compensation`.
+    pub fn estimate_backlash(
+        &mut self,
+        angles: &[f64; 6],
+        options: &BacklashOptions,
+    ) -> Result<[f64; 6]> {
+        let compensation = self.backlash.take();
+        let res = self.measure_backlash(angles, options);
+        self.backlash = compensation;
+        res
+    }
+    fn measure_backlash(
+        &mut self,
+        angles: &[f64; 6],
+        options: &BacklashOptions,
+    ) -> Result<[f64; 6]> {
+        let mut backlash = [0.0; 6];
+        let repetitions = options.repetitions.max(1);
+        for (joint, b) in backlash.iter_mut().enumerate() {
+            let mut gap = 0.0;
+            for _ in 0..repetitions {
+                let from_below = self.settle_from(angles, joint, -options.approach_deg, options)?;
+                let from_above = self.settle_from(angles, joint, options.approach_deg, options)?;
+                gap += from_above - from_below;
+            }
+            *b = (gap / repetitions as f64).max(0.0);
+        }
+        self.sync_send_angles(angles, options.speed, options.timeout_secs)?;
+        Ok(backlash)
+    }
+    /// Moves `joint` to `offset` degrees from its angle and back, then reads where it settled.
+    fn settle_from(
+        &mut self,
+        angles: &[f64; 6],
+        joint: usize,
+        offset: f64,
+        options: &BacklashOptions,
+    ) -> Result<f64> {
+        let mut start = *angles;
+        start[joint] += offset;
+        self.sync_send_angles(&start, options.speed, options.timeout_secs)?;
+        self.sync_send_angles(angles, options.speed, options.timeout_secs)?;
+        thread::sleep(options.settle);
+        Ok(self.get_angles()?[joint])
+    }
+}
@@ -0,0 +1,85 @@
+use super::common::{Angle, Command, GripperState, Mode};
+use super::io::Connection;
+use super::operator::MyCobotOperator;
+use anyhow::{Error, Result};
+
+/// Write-only commands queued on an operator and sent as consecutive frames
+/// in a single serial write by `flush`. The first failing command (e.g. an
+/// out-of-range angle) is reported by `flush` and nothing is sent. Dropping
+/// the batch without flushing discards it.
+pub struct Batch<'a, T: Connection> {
+    operator: &'a mut MyCobotOperator<T>,
+    error: Option<Error>,
+}
+
+impl<T: Connection> MyCobotOperator<T> {
+    pub fn batch(&mut self) -> Batch<'_, T> {
+        self.begin_batch();
+        Batch {
+            operator: self,
+            error: None,
+        }
+    }
+}
+
+impl<'a, T: Connection> Batch<'a, T> {
+    fn queue<F>(mut self, f: F) -> Batch<'a, T>
+    where
+        F: FnOnce(&mut MyCobotOperator<T>) -> Result<()>,
+    {
+        if self.error.is_none() {
+            if let Err(e) = f(self.operator) {
+                self.error = Some(e);
+            }
+        }
+        self
+    }
+    pub fn power_on(self) -> Batch<'a, T> {
+        self.queue(|op| op.power_on())
+    }
+    pub fn set_color(self, r: u8, g: u8, b: u8) -> Batch<'a, T> {
+        self.queue(|op| op.set_color(r, g, b))
+    }
+    pub fn set_speed(self, speed: u8) -> Batch<'a, T> {
+        self.queue(|op| op.set_speed(speed))
+    }
+    pub fn send_angle(self, id: Angle, degree: f64, speed: u8) -> Batch<'a, T> {
+        self.queue(|op| op.send_angle(id, degree, speed))
+    }
+    pub fn send_angles(self, degrees: &[f64; 6], speed: u8) -> Batch<'a, T> {
+        self.queue(|op| op.send_angles(degrees, speed))
+    }
+    pub fn send_coords(self, coords: &[f64; 6], speed: u8, mode: Mode) -> Batch<'a, T> {
+        self.queue(|op| op.send_coords(coords, speed, mode))
+    }
+    pub fn set_gripper_state(self, state: GripperState, speed: u8) -> Batch<'a, T> {
+        self.queue(|op| op.set_gripper_state(state, speed))
+    }
+    pub fn set_digital_output(self, pin_no: u8, pin_signal: bool) -> Batch<'a, T> {
+        self.queue(|op| op.set_digital_output(pin_no, pin_signal))
+    }
+    pub fn set_basic_output(self, pin_no: u8, pin_signal: bool) -> Batch<'a, T> {
+        self.queue(|op| op.set_basic_output(pin_no, pin_signal))
+    }
+    /// Any write-only genre, with arguments encoded as for `call`.
+    pub fn command(self, genre: Command, args: &[i32]) -> Batch<'a, T> {
+        self.queue(|op| op.call(genre, args).map(|_| ()))
+    }
+    /// Sends every queued frame in one write.
+    pub fn flush(mut self) -> Result<()> {
+        let frames = self.operator.end_batch();
+        if let Some(e) = self.error.take() {
+            return Err(e);
+        }
+        if frames.is_empty() {
+            return Ok(());
+        }
+        self.operator.connection_mut().write(&frames)
+    }
+}
+
+impl<T: Connection> Drop for Batch<'_, T> {
+    fn drop(&mut self) {
+        self.operator.end_batch();
+    }
+}
@@ -0,0 +1,147 @@
+use super::audit::{write_record, AuditFormat};
+use super::io::Connection;
+use super::tap::{Frame, FrameDirection};
+use anyhow::Result;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Shared handle to a `BlackBox` ring buffer, cloneable into e-stop observers
+/// or other threads so they can dump it while the connection is in use.
+#[derive(Clone)]
+pub struct BlackBoxRecorder {
+    frames: Arc<Mutex<VecDeque<Frame>>>,
+    window: Duration,
+    capacity: usize,
+}
+
+impl BlackBoxRecorder {
+    fn push(&self, frame: Frame) {
+        let mut frames = match self.frames.lock() {
+            Ok(frames) => frames,
+            Err(_) => return,
+        };
+        let horizon = frame.timestamp.checked_sub(self.window);
+        frames.push_back(frame);
+        while frames.len() > self.capacity
+            || frames
+                .front()
+                .zip(horizon)
+                .is_some_and(|(f, h)| f.timestamp < h)
+        {
+            frames.pop_front();
+        }
+    }
+    /// Frames currently held, oldest first.
+    pub fn frames(&self) -> Vec<Frame> {
+        self.frames
+            .lock()
+            .map(|frames| frames.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+    pub fn clear(&self) {
+        if let Ok(mut frames) = self.frames.lock() {
+            frames.clear();
+        }
+    }
+    /// Writes the held frames as a binary session log, readable with `read_audit_log`.
+    pub fn dump<W: Write>(&self, writer: &mut W) -> Result<()> {
+        for frame in self.frames() {
+            write_record(writer, AuditFormat::Binary, &frame)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+    pub fn dump_to_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.dump(&mut writer)
+    }
+}
+
+/// Connection wrapper keeping the last `window` of frames sent and received,
+/// commands and state replies alike, for post-incident debugging of rigs
+/// nobody is watching.
+pub struct BlackBox<C: Connection> {
+    inner: C,
+    recorder: BlackBoxRecorder,
+    dump_on_error: Option<PathBuf>,
+}
+
+impl<C: Connection> BlackBox<C> {
+    /// Holds at most 100 000 frames, whatever the window.
+    pub fn new(inner: C, window: Duration) -> BlackBox<C> {
+        BlackBox {
+            inner,
+            recorder: BlackBoxRecorder {
+                frames: Arc::new(Mutex::new(VecDeque::new())),
+                window,
+                capacity: 100_000,
+            },
+            dump_on_error: None,
+        }
+    }
+    pub fn capacity(mut self, capacity: usize) -> BlackBox<C> {
+        self.recorder.capacity = capacity.max(1);
+        self
+    }
+    /// Dumps the buffer to `path` whenever the wrapped connection returns an error.
+    pub fn dump_on_error<P: Into<PathBuf>>(mut self, path: P) -> BlackBox<C> {
+        self.dump_on_error = Some(path.into());
+        self
+    }
+    pub fn recorder(&self) -> BlackBoxRecorder {
+        self.recorder.clone()
+    }
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+    fn record(&self, direction: FrameDirection, data: &[u8]) {
+        for frame in Frame::parse(direction, data) {
+            self.recorder.push(frame);
+        }
+    }
+    fn checked<R>(&self, res: Result<R>) -> Result<R> {
+        if let (Err(_), Some(path)) = (&res, &self.dump_on_error) {
+            let _ = self.recorder.dump_to_file(path);
+        }
+        res
+    }
+}
+
+impl<C: Connection> Connection for BlackBox<C> {
+    fn write(&mut self, command: &[u8]) -> Result<()> {
+        self.record(FrameDirection::Sent, command);
+        let res = self.inner.write(command);
+        self.checked(res)
+    }
+    fn read(&mut self) -> Result<Vec<u8>> {
+        let res = self.inner.read();
+        if let Ok(data) = &res {
+            self.record(FrameDirection::Received, data);
+        }
+        self.checked(res)
+    }
+    fn write_and_read(&mut self, command: &[u8]) -> Result<Vec<u8>> {
+        self.record(FrameDirection::Sent, command);
+        let res = self.inner.write_and_read(command);
+        if let Ok(data) = &res {
+            self.record(FrameDirection::Received, data);
+        }
+        self.checked(res)
+    }
+    fn is_healthy(&mut self) -> bool {
+        self.inner.is_healthy()
+    }
+    fn set_read_timeout(&mut self, timeout: Duration) -> Result<()> {
+        self.inner.set_read_timeout(timeout)
+    }
+    fn clear_input_buffer(&mut self) -> Result<()> {
+        self.inner.clear_input_buffer()
+    }
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+}
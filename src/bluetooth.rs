@@ -0,0 +1,196 @@
+use super::io::Connection;
+use anyhow::Result;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::os::unix::io::{AsRawFd, FromRawFd};
+use std::process;
+use std::thread;
+use std::time::Duration;
+
+const AF_BLUETOOTH: libc::c_int = 31;
+const BTPROTO_RFCOMM: libc::c_int = 3;
+
+#[repr(C)]
+struct SockaddrRc {
+    rc_family: libc::sa_family_t,
+    rc_bdaddr: [u8; 6],
+    rc_channel: u8,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BluetoothDevice {
+    pub address: String,
+    pub name: String,
+}
+
+fn parse_address(address: &str) -> Result<[u8; 6]> {
+    let bytes = address
+        .split(':')
+        .map(|s| u8::from_str_radix(s, 16))
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|_| anyhow::anyhow!("Invalid bluetooth address: {}", address))?;
+    if bytes.len() != 6 {
+        return Err(anyhow::anyhow!("Invalid bluetooth address: {}", address));
+    }
+    let mut bdaddr = [0u8; 6];
+    for (dst, src) in bdaddr.iter_mut().zip(bytes.iter().rev()) {
+        *dst = *src;
+    }
+    Ok(bdaddr)
+}
+
+fn set_receive_timeout(fd: libc::c_int, timeout: Duration) -> Result<()> {
+    let timeout = libc::timeval {
+        tv_sec: timeout.as_secs() as libc::time_t,
+        tv_usec: timeout.subsec_micros() as libc::suseconds_t,
+    };
+    let res = unsafe {
+        libc::setsockopt(
+            fd,
+            libc::SOL_SOCKET,
+            libc::SO_RCVTIMEO,
+            &timeout as *const libc::timeval as *const libc::c_void,
+            std::mem::size_of::<libc::timeval>() as libc::socklen_t,
+        )
+    };
+    if res < 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    Ok(())
+}
+
+/// RFCOMM (Bluetooth serial port profile) connection, Linux only.
+pub struct Bluetooth {
+    socket: File,
+    /// The peer closed the connection or the socket failed.
+    closed: bool,
+}
+
+impl Bluetooth {
+    pub fn open(address: &str, channel: u8) -> Result<Bluetooth> {
+        let addr = SockaddrRc {
+            rc_family: AF_BLUETOOTH as libc::sa_family_t,
+            rc_bdaddr: parse_address(address)?,
+            rc_channel: channel,
+        };
+        let fd = unsafe { libc::socket(AF_BLUETOOTH, libc::SOCK_STREAM, BTPROTO_RFCOMM) };
+        if fd < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        let socket = unsafe { File::from_raw_fd(fd) };
+        let res = unsafe {
+            libc::connect(
+                fd,
+                &addr as *const SockaddrRc as *const libc::sockaddr,
+                std::mem::size_of::<SockaddrRc>() as libc::socklen_t,
+            )
+        };
+        if res < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        set_receive_timeout(fd, Duration::from_millis(10))?;
+        Ok(Bluetooth {
+            socket,
+            closed: false,
+        })
+    }
+    /// Scans for nearby devices with `bluetoothctl` and returns those whose name contains `name`.
+    pub fn scan(name: &str, timeout_secs: u64) -> Result<Vec<BluetoothDevice>> {
+        process::Command::new("bluetoothctl")
+            .args(["--timeout", &timeout_secs.to_string(), "scan", "on"])
+            .output()?;
+        let output = process::Command::new("bluetoothctl")
+            .arg("devices")
+            .output()?;
+        let devices = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| {
+                let mut parts = line.splitn(3, ' ');
+                match (parts.next(), parts.next(), parts.next()) {
+                    (Some("Device"), Some(address), Some(device_name)) => Some(BluetoothDevice {
+                        address: address.to_string(),
+                        name: device_name.to_string(),
+                    }),
+                    _ => None,
+                }
+            })
+            .filter(|device| device.name.contains(name))
+            .collect();
+        Ok(devices)
+    }
+    /// Connects to the first scanned device whose name contains `name`.
+    pub fn connect_by_name(name: &str, channel: u8, timeout_secs: u64) -> Result<Bluetooth> {
+        let device = Bluetooth::scan(name, timeout_secs)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("No bluetooth device named {}", name))?;
+        Bluetooth::open(&device.address, channel)
+    }
+}
+
+impl Connection for Bluetooth {
+    fn write(&mut self, command: &[u8]) -> Result<()> {
+        self.socket.write_all(command)?;
+        thread::sleep(Duration::from_millis(5));
+        Ok(())
+    }
+    fn read(&mut self) -> Result<Vec<u8>> {
+        let mut data = Vec::<u8>::new();
+        let mut buf = [0u8; 64];
+        loop {
+            match self.socket.read(&mut buf) {
+                Ok(0) => {
+                    self.closed = true;
+                    break;
+                }
+                Ok(n) => data.extend_from_slice(&buf[..n]),
+                Err(e) => {
+                    // The receive timeout surfaces as WouldBlock; anything else is a dead link.
+                    if !matches!(
+                        e.kind(),
+                        std::io::ErrorKind::WouldBlock
+                            | std::io::ErrorKind::TimedOut
+                            | std::io::ErrorKind::Interrupted
+                    ) {
+                        self.closed = true;
+                    }
+                    break;
+                }
+            }
+        }
+        Ok(data)
+    }
+    fn write_and_read(&mut self, command: &[u8]) -> Result<Vec<u8>> {
+        self.write(command)?;
+        let res = self.read()?;
+        Ok(res)
+    }
+    fn is_healthy(&mut self) -> bool {
+        !self.closed
+    }
+    fn set_read_timeout(&mut self, timeout: Duration) -> Result<()> {
+        set_receive_timeout(self.socket.as_raw_fd(), timeout)
+    }
+    fn clear_input_buffer(&mut self) -> Result<()> {
+        let mut buf = [0u8; 64];
+        loop {
+            let n = unsafe {
+                libc::recv(
+                    self.socket.as_raw_fd(),
+                    buf.as_mut_ptr() as *mut libc::c_void,
+                    buf.len(),
+                    libc::MSG_DONTWAIT,
+                )
+            };
+            if n == 0 {
+                self.closed = true;
+            }
+            if n <= 0 {
+                return Ok(());
+            }
+        }
+    }
+    fn flush(&mut self) -> Result<()> {
+        Ok(self.socket.flush()?)
+    }
+}
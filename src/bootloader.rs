@@ -0,0 +1,211 @@
+//! Field updates of the Basic (ESP32) controller over the robot's own USB
+//! serial port, using the ESP32 ROM bootloader protocol esptool speaks.
+//! Only uncompressed writes are used, so no flasher stub is needed.
+//! The ATOM is not reachable this way; it sits behind the Basic firmware.
+
+use super::io::Serial;
+use super::operator::MyCobotSerialOperator;
+use anyhow::Result;
+use std::io::ErrorKind;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const SLIP_END: u8 = 0xC0;
+const SLIP_ESC: u8 = 0xDB;
+
+const FLASH_BEGIN: u8 = 0x02;
+const FLASH_DATA: u8 = 0x03;
+const FLASH_END: u8 = 0x04;
+const SYNC: u8 = 0x08;
+const SPI_ATTACH: u8 = 0x0D;
+
+const BLOCK_SIZE: usize = 0x400;
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(3);
+const ERASE_TIMEOUT_PER_MB: Duration = Duration::from_secs(30);
+
+impl Serial {
+    /// Resets the ESP32 into its ROM bootloader with the DTR/RTS auto-reset
+    /// circuit of the Basic's USB bridge.
+    pub fn enter_bootloader(&mut self) -> Result<()> {
+        let port = self.port_mut();
+        port.write_data_terminal_ready(false)?;
+        port.write_request_to_send(true)?;
+        thread::sleep(Duration::from_millis(100));
+        port.write_data_terminal_ready(true)?;
+        port.write_request_to_send(false)?;
+        thread::sleep(Duration::from_millis(50));
+        port.write_data_terminal_ready(false)?;
+        Ok(())
+    }
+    /// Pulses the reset line to restart the application firmware.
+    pub fn hard_reset(&mut self) -> Result<()> {
+        let port = self.port_mut();
+        port.write_request_to_send(true)?;
+        thread::sleep(Duration::from_millis(100));
+        port.write_request_to_send(false)?;
+        Ok(())
+    }
+}
+
+fn slip_encode(packet: &[u8]) -> Vec<u8> {
+    let mut out = vec![SLIP_END];
+    for &b in packet {
+        match b {
+            SLIP_END => out.extend_from_slice(&[SLIP_ESC, 0xDC]),
+            SLIP_ESC => out.extend_from_slice(&[SLIP_ESC, 0xDD]),
+            b => out.push(b),
+        }
+    }
+    out.push(SLIP_END);
+    out
+}
+
+fn checksum(data: &[u8]) -> u32 {
+    data.iter().fold(0xEF, |acc, b| acc ^ *b as u32)
+}
+
+/// Speaks the ESP32 ROM bootloader protocol on a port already in bootloader mode.
+pub struct EspLoader<'a> {
+    serial: &'a mut Serial,
+}
+
+impl<'a> EspLoader<'a> {
+    /// Resets into the bootloader and synchronises with it.
+    pub fn connect(serial: &'a mut Serial) -> Result<EspLoader<'a>> {
+        serial.enter_bootloader()?;
+        let mut loader = EspLoader { serial };
+        loader.sync()?;
+        loader.command(SPI_ATTACH, &[0; 8], 0, COMMAND_TIMEOUT)?;
+        Ok(loader)
+    }
+    fn sync(&mut self) -> Result<()> {
+        let mut data = vec![0x07, 0x07, 0x12, 0x20];
+        data.extend_from_slice(&[0x55; 32]);
+        for _ in 0..10 {
+            if self
+                .command(SYNC, &data, 0, Duration::from_millis(100))
+                .is_ok()
+            {
+                // The ROM answers a sync several times; drop the extra replies.
+                thread::sleep(Duration::from_millis(50));
+                self.serial
+                    .port_mut()
+                    .clear(serialport::ClearBuffer::Input)?;
+                return Ok(());
+            }
+        }
+        Err(anyhow::anyhow!("ESP32 bootloader did not respond to sync"))
+    }
+    fn command(&mut self, op: u8, data: &[u8], check: u32, timeout: Duration) -> Result<Vec<u8>> {
+        let mut packet = vec![0x00, op];
+        packet.extend_from_slice(&(data.len() as u16).to_le_bytes());
+        packet.extend_from_slice(&check.to_le_bytes());
+        packet.extend_from_slice(data);
+        self.serial.port_mut().write_all(&slip_encode(&packet))?;
+        let deadline = Instant::now() + timeout;
+        loop {
+            let reply = self.read_packet(deadline)?;
+            // Replies are: direction 1, op, size (u16), value (u32), data ending in four status bytes.
+            if reply.len() < 12 || reply[0] != 0x01 || reply[1] != op {
+                continue;
+            }
+            let body = &reply[8..];
+            let status = &body[body.len() - 4..];
+            if status[0] != 0 {
+                return Err(anyhow::anyhow!(
+                    "Bootloader command 0x{:02X} failed with error 0x{:02X}",
+                    op,
+                    status[1]
+                ));
+            }
+            return Ok(body.to_vec());
+        }
+    }
+    fn read_packet(&mut self, deadline: Instant) -> Result<Vec<u8>> {
+        let port = self.serial.port_mut();
+        let mut packet = Vec::new();
+        let mut in_packet = false;
+        let mut escaped = false;
+        let mut byte = [0u8; 1];
+        while Instant::now() < deadline {
+            match port.read(&mut byte) {
+                Ok(1) => {}
+                Ok(_) => continue,
+                Err(e) if e.kind() == ErrorKind::TimedOut => continue,
+                Err(e) => return Err(e.into()),
+            }
+            match (byte[0], in_packet, escaped) {
+                (SLIP_END, false, _) => in_packet = true,
+                (SLIP_END, true, _) if packet.is_empty() => {}
+                (SLIP_END, true, _) => return Ok(packet),
+                (_, false, _) => {}
+                (SLIP_ESC, true, false) => escaped = true,
+                (0xDC, true, true) => {
+                    packet.push(SLIP_END);
+                    escaped = false;
+                }
+                (0xDD, true, true) => {
+                    packet.push(SLIP_ESC);
+                    escaped = false;
+                }
+                (b, true, _) => {
+                    packet.push(b);
+                    escaped = false;
+                }
+            }
+        }
+        Err(anyhow::anyhow!("Timed out waiting for the bootloader"))
+    }
+    /// Erases and writes `image` at flash `offset`, calling `progress` with
+    /// the bytes written so far and the total.
+    pub fn flash<F>(&mut self, offset: u32, image: &[u8], mut progress: F) -> Result<()>
+    where
+        F: FnMut(usize, usize),
+    {
+        let blocks = image.len().div_ceil(BLOCK_SIZE);
+        let mut begin = Vec::with_capacity(16);
+        for v in [image.len() as u32, blocks as u32, BLOCK_SIZE as u32, offset].iter() {
+            begin.extend_from_slice(&v.to_le_bytes());
+        }
+        let megabytes = image.len() as f64 / (1024.0 * 1024.0);
+        let erase_timeout = COMMAND_TIMEOUT.max(ERASE_TIMEOUT_PER_MB.mul_f64(megabytes));
+        self.command(FLASH_BEGIN, &begin, 0, erase_timeout)?;
+        for (seq, chunk) in image.chunks(BLOCK_SIZE).enumerate() {
+            let mut block = chunk.to_vec();
+            block.resize(BLOCK_SIZE, 0xFF);
+            let mut data = Vec::with_capacity(16 + BLOCK_SIZE);
+            for v in [BLOCK_SIZE as u32, seq as u32, 0, 0].iter() {
+                data.extend_from_slice(&v.to_le_bytes());
+            }
+            data.extend_from_slice(&block);
+            self.command(FLASH_DATA, &data, checksum(&block), COMMAND_TIMEOUT)?;
+            progress(
+                (seq * BLOCK_SIZE + chunk.len()).min(image.len()),
+                image.len(),
+            );
+        }
+        Ok(())
+    }
+    /// Ends the flash session, optionally restarting into the new firmware.
+    pub fn finish(mut self, reboot: bool) -> Result<()> {
+        let stay = if reboot { 0u32 } else { 1 };
+        self.command(FLASH_END, &stay.to_le_bytes(), 0, COMMAND_TIMEOUT)?;
+        if reboot {
+            self.serial.hard_reset()?;
+        }
+        Ok(())
+    }
+}
+
+impl MyCobotSerialOperator {
+    /// Flashes a Basic firmware image (e.g. the vendor's `.bin` at offset
+    /// 0x10000) and restarts the controller. Reconnect afterwards.
+    pub fn flash_basic_firmware<F>(&mut self, offset: u32, image: &[u8], progress: F) -> Result<()>
+    where
+        F: FnMut(usize, usize),
+    {
+        let mut loader = EspLoader::connect(self.connection_mut())?;
+        loader.flash(offset, image, progress)?;
+        loader.finish(true)
+    }
+}
@@ -0,0 +1,301 @@
+use super::common::Angle;
+use super::conversion::int_vec_to_coords;
+use super::io::Connection;
+use super::operator::MyCobotOperator;
+use super::transform::*;
+use anyhow::Result;
+use std::thread;
+use std::time::Duration;
+
+/// Zero readings further than this from 0 after calibrating are reported as errors.
+const ZERO_TOLERANCE_DEG: f64 = 1.0;
+
+fn solve3(a: &Matrix3, b: &Vector3) -> Option<Vector3> {
+    let d = det(a);
+    if d.abs() < 1e-12 {
+        return None;
+    }
+    let mut x = [0.0; 3];
+    for (col, xi) in x.iter_mut().enumerate() {
+        let mut m = *a;
+        for row in 0..3 {
+            m[row][col] = b[row];
+        }
+        *xi = det(&m) / d;
+    }
+    Some(x)
+}
+
+fn det(a: &Matrix3) -> f64 {
+    a[0][0] * (a[1][1] * a[2][2] - a[1][2] * a[2][1])
+        - a[0][1] * (a[1][0] * a[2][2] - a[1][2] * a[2][0])
+        + a[0][2] * (a[1][0] * a[2][1] - a[1][1] * a[2][0])
+}
+
+#[derive(Clone, Debug)]
+pub struct HandEyeSolution {
+    /// Pose of the camera expressed in the flange frame.
+    pub camera_to_flange: Pose,
+    pub rotation_residual_deg: f64,
+    pub translation_residual_mm: f64,
+}
+
+/// Eye-in-hand calibration solving `AX = XB` with the Tsai-Lenz method.
+#[derive(Clone, Debug, Default)]
+pub struct HandEyeCalibration {
+    samples: Vec<(Pose, Pose)>,
+}
+
+impl HandEyeCalibration {
+    pub fn new() -> HandEyeCalibration {
+        HandEyeCalibration::default()
+    }
+    /// Records the flange pose from `get_coords` together with the target pose observed by the camera.
+    pub fn add_sample(&mut self, flange_coords: &[f64; 6], target_in_camera: Pose) {
+        self.samples
+            .push((Pose::from_coords(flange_coords), target_in_camera));
+    }
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+    fn motions(&self) -> Vec<(Pose, Pose)> {
+        let mut motions = Vec::new();
+        for i in 0..self.samples.len() {
+            for j in (i + 1)..self.samples.len() {
+                let (gi, ci) = &self.samples[i];
+                let (gj, cj) = &self.samples[j];
+                let a = gj.inverse().compose(gi);
+                let b = cj.compose(&ci.inverse());
+                motions.push((a, b));
+            }
+        }
+        motions
+    }
+    pub fn solve(&self) -> Result<HandEyeSolution> {
+        if self.samples.len() < 3 {
+            return Err(anyhow::anyhow!(
+                "Hand-eye calibration needs at least 3 samples",
+            ));
+        }
+        let motions = self.motions();
+
+        let mut ata = [[0.0; 3]; 3];
+        let mut atb = [0.0; 3];
+        for (a, b) in motions.iter() {
+            let (theta_a, axis_a) = matrix_to_axis_angle(&a.rotation);
+            let (theta_b, axis_b) = matrix_to_axis_angle(&b.rotation);
+            let pa = axis_a.map(|v| 2.0 * (theta_a / 2.0).sin() * v);
+            let pb = axis_b.map(|v| 2.0 * (theta_b / 2.0).sin() * v);
+            let s = skew(&add(&pa, &pb));
+            let rhs = sub(&pb, &pa);
+            accumulate(&mut ata, &mut atb, &s, &rhs);
+        }
+        let p = solve3(&ata, &atb).ok_or_else(|| {
+            anyhow::anyhow!("Degenerate rotations; vary the orientation between samples")
+        })?;
+        let scale = 2.0 / (1.0 + norm(&p).powi(2)).sqrt();
+        let p = p.map(|v| v * scale);
+        let pn2 = norm(&p).powi(2);
+        let sk = skew(&p);
+        let mut rx = [[0.0; 3]; 3];
+        for i in 0..3 {
+            for j in 0..3 {
+                rx[i][j] = (1.0 - pn2 / 2.0) * IDENTITY3[i][j]
+                    + 0.5 * (p[i] * p[j] + (4.0 - pn2).max(0.0).sqrt() * sk[i][j]);
+            }
+        }
+
+        let mut ata = [[0.0; 3]; 3];
+        let mut atb = [0.0; 3];
+        for (a, b) in motions.iter() {
+            let mut m = a.rotation;
+            for (i, row) in m.iter_mut().enumerate() {
+                row[i] -= 1.0;
+            }
+            let rhs = sub(&mat_vec(&rx, &b.position), &a.position);
+            accumulate(&mut ata, &mut atb, &m, &rhs);
+        }
+        let tx = solve3(&ata, &atb).ok_or_else(|| {
+            anyhow::anyhow!("Degenerate translations; vary the rotation axes between samples")
+        })?;
+        let x = Pose::new(tx, rx);
+
+        let (mut rot_err, mut trans_err) = (0.0, 0.0);
+        for (a, b) in motions.iter() {
+            let (trans, rot) = a.compose(&x).distance(&x.compose(b));
+            rot_err += rot;
+            trans_err += trans;
+        }
+        let n = motions.len() as f64;
+        Ok(HandEyeSolution {
+            camera_to_flange: x,
+            rotation_residual_deg: rot_err / n,
+            translation_residual_mm: trans_err / n,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TcpSolution {
+    /// Tool center point in the flange frame, in mm.
+    pub tool_offset: Vector3,
+    /// The touched point in the base frame, in mm.
+    pub point: Vector3,
+    /// Mean and largest distance of the per-sample tool points from `point`, in mm.
+    pub mean_residual_mm: f64,
+    pub max_residual_mm: f64,
+}
+
+impl TcpSolution {
+    pub fn tool_pose(&self) -> Pose {
+        Pose::from_translation(self.tool_offset)
+    }
+    /// Sets the calibrated tool center point as the operator's tool pose, so
+    /// its tool coords refer to it. Nothing is written to the controller; the
+    /// protocol has no documented tool offset genre.
+    pub fn set_operator_tool<T: Connection>(&self, operator: &mut MyCobotOperator<T>) {
+        operator.set_tool_pose(Some(self.tool_pose()));
+    }
+}
+
+/// Tool center point calibration by touching one fixed point from several
+/// orientations (at least four, as different from each other as practical).
+#[derive(Clone, Debug, Default)]
+pub struct TcpCalibration {
+    samples: Vec<Pose>,
+}
+
+impl TcpCalibration {
+    pub fn new() -> TcpCalibration {
+        TcpCalibration::default()
+    }
+    /// Records flange coords, as from `get_coords` with no tool set.
+    pub fn add_sample(&mut self, flange_coords: &[f64; 6]) {
+        self.samples.push(Pose::from_coords(flange_coords));
+    }
+    /// Records the current flange pose, regardless of the operator's tool.
+    pub fn record<T: Connection>(&mut self, operator: &mut MyCobotOperator<T>) -> Result<()> {
+        let coords = int_vec_to_coords(&operator.get_coords_raw()?)?;
+        self.add_sample(&coords);
+        Ok(())
+    }
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+    /// Least-squares tool offset `t` with `R_i t + p_i` equal for every sample.
+    pub fn solve(&self) -> Result<TcpSolution> {
+        if self.samples.len() < 4 {
+            return Err(anyhow::anyhow!("TCP calibration needs at least 4 samples"));
+        }
+        let mut ata = [[0.0; 3]; 3];
+        let mut atb = [0.0; 3];
+        for i in 0..self.samples.len() {
+            for j in (i + 1)..self.samples.len() {
+                let (a, b) = (&self.samples[i], &self.samples[j]);
+                let mut m = a.rotation;
+                for (row, b_row) in m.iter_mut().zip(b.rotation.iter()) {
+                    for (v, w) in row.iter_mut().zip(b_row.iter()) {
+                        *v -= w;
+                    }
+                }
+                let rhs = sub(&b.position, &a.position);
+                accumulate(&mut ata, &mut atb, &m, &rhs);
+            }
+        }
+        let tool_offset = solve3(&ata, &atb).ok_or_else(|| {
+            anyhow::anyhow!("Degenerate orientations; vary the tool angle between samples")
+        })?;
+        let points: Vec<Vector3> = self
+            .samples
+            .iter()
+            .map(|s| s.transform_point(&tool_offset))
+            .collect();
+        let n = points.len() as f64;
+        let point = points
+            .iter()
+            .fold([0.0; 3], |acc, p| add(&acc, p))
+            .map(|v| v / n);
+        let residuals: Vec<f64> = points.iter().map(|p| norm(&sub(p, &point))).collect();
+        Ok(TcpSolution {
+            tool_offset,
+            point,
+            mean_residual_mm: residuals.iter().sum::<f64>() / n,
+            max_residual_mm: residuals.iter().cloned().fold(0.0, f64::max),
+        })
+    }
+}
+
+fn accumulate(ata: &mut Matrix3, atb: &mut Vector3, a: &Matrix3, b: &Vector3) {
+    for i in 0..3 {
+        for j in 0..3 {
+            ata[i][j] += (0..3).map(|k| a[k][i] * a[k][j]).sum::<f64>();
+        }
+        atb[i] += (0..3).map(|k| a[k][i] * b[k]).sum::<f64>();
+    }
+}
+
+/// Confirmation points of the zero calibration workflow.
+#[derive(Clone)]
+pub enum ZeroPrompt {
+    /// The joint is about to be released; support the arm before confirming.
+    Release(Angle),
+    /// The joint is free; align it with its zero mark, then confirm to store it.
+    SetZero(Angle),
+}
+
+impl<T: Connection> MyCobotOperator<T> {
+    /// Guides one joint through release, manual alignment and storing its zero.
+    /// `confirm` is asked at each step; returning `false` re-locks the joint
+    /// without changing its calibration and this returns `Ok(false)`.
+    pub fn calibrate_zero<F>(&mut self, joint: Angle, mut confirm: F) -> Result<bool>
+    where
+        F: FnMut(ZeroPrompt) -> bool,
+    {
+        if !confirm(ZeroPrompt::Release(joint)) {
+            return Ok(false);
+        }
+        self.release_servo(joint)?;
+        if !confirm(ZeroPrompt::SetZero(joint)) {
+            self.focus_servo(joint)?;
+            return Ok(false);
+        }
+        self.set_servo_zero(joint)?;
+        thread::sleep(Duration::from_millis(100));
+        self.focus_servo(joint)?;
+        let index = joint as usize - 1;
+        let reading = self.get_angles()?[index];
+        if reading.abs() > ZERO_TOLERANCE_DEG {
+            return Err(anyhow::anyhow!(
+                "J{} reads {} degrees after zero calibration",
+                index + 1,
+                reading
+            ));
+        }
+        Ok(true)
+    }
+    /// Runs `calibrate_zero` for J1 to J6 in order and returns the joints that were calibrated.
+    pub fn calibrate_all_zeros<F>(&mut self, mut confirm: F) -> Result<Vec<Angle>>
+    where
+        F: FnMut(ZeroPrompt) -> bool,
+    {
+        let mut calibrated = Vec::new();
+        for &joint in Angle::ALL {
+            if self.calibrate_zero(joint, &mut confirm)? {
+                calibrated.push(joint);
+            }
+        }
+        Ok(calibrated)
+    }
+}
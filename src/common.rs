@@ -1,142 +1,400 @@
+use anyhow::Result;
 use num_traits::FromPrimitive;
+use std::convert::TryFrom;
 
-#[derive(Clone)]
-pub enum Angle {
-    J1 = 1,
-    J2 = 2,
-    J3 = 3,
-    J4 = 4,
-    J5 = 5,
-    J6 = 6,
+/// Declares a wire enum with `ALL`, `TryFrom<u8>`, `Display` and optional serde support.
+macro_rules! wire_enum {
+    ($(#[$meta:meta])* $name:ident { $($variant:ident = $value:expr, $display:expr;)* }) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+        #[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+        pub enum $name {
+            $($variant = $value,)*
+        }
+
+        impl $name {
+            pub const ALL: &'static [$name] = &[$($name::$variant,)*];
+        }
+
+        impl TryFrom<u8> for $name {
+            type Error = anyhow::Error;
+            fn try_from(value: u8) -> Result<$name> {
+                match value {
+                    $($value => Ok($name::$variant),)*
+                    _ => Err(anyhow::anyhow!("Invalid {}: {}", stringify!($name), value)),
+                }
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(match self {
+                    $($name::$variant => $display,)*
+                })
+            }
+        }
+    };
 }
 
-#[derive(Clone)]
-pub enum Coord {
-    X = 1,
-    Y = 2,
-    Z = 3,
-    Rx = 4,
-    Ry = 5,
-    Rz = 6,
+wire_enum! {
+    Angle {
+        J1 = 1, "J1";
+        J2 = 2, "J2";
+        J3 = 3, "J3";
+        J4 = 4, "J4";
+        J5 = 5, "J5";
+        J6 = 6, "J6";
+    }
+}
+
+wire_enum! {
+    Coord {
+        X = 1, "x";
+        Y = 2, "y";
+        Z = 3, "z";
+        Rx = 4, "rx";
+        Ry = 5, "ry";
+        Rz = 6, "rz";
+    }
 }
 
 impl FromPrimitive for Coord {
     fn from_i64(n: i64) -> Option<Coord> {
-        match n {
-            1 => Some(Coord::X),
-            2 => Some(Coord::Y),
-            3 => Some(Coord::Z),
-            4 => Some(Coord::Rx),
-            5 => Some(Coord::Ry),
-            6 => Some(Coord::Rz),
-            _ => None,
-        }
+        u8::try_from(n).ok().and_then(|n| Coord::try_from(n).ok())
     }
     fn from_u64(n: u64) -> Option<Coord> {
-        match n {
-            1 => Some(Coord::X),
-            2 => Some(Coord::Y),
-            3 => Some(Coord::Z),
-            4 => Some(Coord::Rx),
-            5 => Some(Coord::Ry),
-            6 => Some(Coord::Rz),
-            _ => None,
+        u8::try_from(n).ok().and_then(|n| Coord::try_from(n).ok())
+    }
+}
+
+wire_enum! {
+    Direction {
+        Decrease = 0, "decrease";
+        Increase = 1, "increase";
+    }
+}
+
+wire_enum! {
+    Mode {
+        Normal = 0, "normal";
+        Angular = 1, "angular";
+        Linear = 2, "linear";
+    }
+}
+
+wire_enum! {
+    /// Whether a new motion command interrupts the current motion or queues behind it.
+    FreshMode {
+        Queue = 0, "queue";
+        Interrupt = 1, "interrupt";
+    }
+}
+
+wire_enum! {
+    /// How the firmware plans `send_coords` motion: joint interpolation or a
+    /// straight Cartesian line.
+    MovementType {
+        MoveJ = 0, "move_j";
+        MoveL = 1, "move_l";
+    }
+}
+
+/// Position loop gains of a joint servo, in register units.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ServoPid {
+    pub p: u8,
+    pub i: u8,
+    pub d: u8,
+}
+
+/// Firmware planner setting (speed or acceleration, 0-100) for each movement type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PlanSetting {
+    pub move_j: u8,
+    pub move_l: u8,
+}
+
+wire_enum! {
+    PinMode {
+        Input = 0, "input";
+        Output = 1, "output";
+        InputPullup = 2, "input_pullup";
+    }
+}
+
+wire_enum! {
+    GripperState {
+        Open = 0, "open";
+        Close = 1, "close";
+    }
+}
+
+wire_enum! {
+    /// Joint servo registers reachable through `set_servo_data`, by address.
+    /// `MinStartForce` is the lowest output that overcomes static friction;
+    /// the dead zones are position errors in encoder counts ignored while holding.
+    ServoRegister {
+        PGain = 21, "p_gain";
+        DGain = 22, "d_gain";
+        IGain = 23, "i_gain";
+        MinStartForce = 24, "min_start_force";
+        CwDeadZone = 26, "cw_dead_zone";
+        CcwDeadZone = 27, "ccw_dead_zone";
+    }
+}
+
+impl ServoRegister {
+    /// Values the servo accepts for this register.
+    pub fn range(self) -> std::ops::RangeInclusive<u8> {
+        match self {
+            ServoRegister::CwDeadZone | ServoRegister::CcwDeadZone => 0..=32,
+            _ => 0..=254,
         }
     }
 }
 
-#[derive(Clone)]
-pub enum Direction {
-    Decrease = 0,
-    Increase = 1,
+/// Valid gripper protection currents in mA.
+pub const GRIPPER_PROTECT_CURRENT_RANGE: std::ops::RangeInclusive<u16> = 1..=500;
+
+/// Wire type of one payload field. Multi-byte fields are big-endian.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Field {
+    U8,
+    I8,
+    I16,
+    /// A byte that carries no value, skipped when decoding and sent as zero.
+    Pad,
 }
 
-#[derive(Clone)]
-pub enum Mode {
-    Normal = 0,
-    Angular = 1,
-    Linear = 2,
+impl Field {
+    pub fn size(self) -> usize {
+        match self {
+            Field::U8 | Field::I8 | Field::Pad => 1,
+            Field::I16 => 2,
+        }
+    }
 }
 
-#[derive(Clone)]
-pub enum PinMode {
-    Input = 0,
-    Output = 1,
-    InputPullup = 2,
+/// Declared shape of a request or reply payload.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Layout {
+    Fields(&'static [Field]),
+    /// One of several field lists, selected by the exact payload length,
+    /// for genres whose reply differs between firmware versions.
+    OneOf(&'static [&'static [Field]]),
+    /// Bytes passed through unchecked and undecoded, e.g. variable-length data.
+    Raw,
 }
 
-#[derive(Clone)]
-pub enum GripperState {
-    Open = 0,
-    Close = 1,
+impl Layout {
+    pub const NONE: Layout = Layout::Fields(&[]);
+
+    /// Payload length in bytes, `None` when it is not fixed.
+    pub fn len(&self) -> Option<usize> {
+        match self {
+            Layout::Fields(fields) => Some(fields_len(fields)),
+            Layout::OneOf(_) | Layout::Raw => None,
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == Some(0)
+    }
 }
 
-#[non_exhaustive]
-pub struct Command;
+pub(crate) fn fields_len(fields: &[Field]) -> usize {
+    fields.iter().map(|f| f.size()).sum()
+}
+
+use self::Field::{Pad, I16, I8, U8};
+
+const BYTE: Layout = Layout::Fields(&[I8]);
+const TWO_BYTES: Layout = Layout::Fields(&[U8, U8]);
+const INT16: Layout = Layout::Fields(&[I16]);
+const ID: Layout = Layout::Fields(&[U8]);
+const ID_AND_BYTE: Layout = Layout::Fields(&[U8, I8]);
+const SIX_INT16: Layout = Layout::Fields(&[I16, I16, I16, I16, I16, I16]);
+/// Some firmware appends a status byte to six-value replies.
+const SIX_INT16_OR_PADDED: Layout = Layout::OneOf(&[
+    &[I16, I16, I16, I16, I16, I16],
+    &[I16, I16, I16, I16, I16, I16, Pad],
+]);
+const BYTE_OR_INT16: Layout = Layout::OneOf(&[&[I8], &[I16]]);
+const NONE: Layout = Layout::NONE;
+const RAW: Layout = Layout::Raw;
+
+macro_rules! commands {
+    ($($name:ident = $code:expr, $display:expr, $request:expr, $response:expr;)*) => {
+        #[non_exhaustive]
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        #[repr(u8)]
+        pub enum Command {
+            $($name = $code,)*
+        }
+
+        impl Command {
+            pub const ALL: &'static [Command] = &[$(Command::$name,)*];
+
+            pub fn from_u8(value: u8) -> Option<Command> {
+                match value {
+                    $($code => Some(Command::$name),)*
+                    _ => None,
+                }
+            }
+            pub fn name(self) -> &'static str {
+                match self {
+                    $(Command::$name => $display,)*
+                }
+            }
+            pub fn request(self) -> Layout {
+                match self {
+                    $(Command::$name => $request,)*
+                }
+            }
+            /// Request payload length in bytes, `None` when it is variable.
+            pub fn request_len(self) -> Option<usize> {
+                self.request().len()
+            }
+            pub fn response(self) -> Layout {
+                match self {
+                    $(Command::$name => $response,)*
+                }
+            }
+        }
+    };
+}
+
+commands! {
+    Version = 0x00, "version", NONE, RAW;
+    GetRobotVersion = 0x01, "get_robot_version", NONE, BYTE;
+    GetSystemVersion = 0x02, "get_system_version", NONE, BYTE;
+    GetRobotId = 0x03, "get_robot_id", NONE, BYTE;
+    SetRobotId = 0x04, "set_robot_id", ID, NONE;
+    GetErrorInformation = 0x07, "get_error_information", NONE, BYTE;
+    ClearErrorInformation = 0x08, "clear_error_information", NONE, NONE;
+    GetAtomVersion = 0x09, "get_atom_version", NONE, BYTE;
+    PowerOn = 0x10, "power_on", NONE, NONE;
+    PowerOff = 0x11, "power_off", NONE, NONE;
+    IsPowerOn = 0x12, "is_power_on", NONE, BYTE;
+    ReleaseAllServos = 0x13, "release_all_servos", NONE, NONE;
+    IsControllerConnected = 0x14, "is_controller_connected", NONE, BYTE;
+    ReadNextError = 0x15, "read_next_error", NONE, BYTE;
+    SetFreshMode = 0x16, "set_fresh_mode", ID, NONE;
+    GetFreshMode = 0x17, "get_fresh_mode", NONE, BYTE;
+    SetFreeMode = 0x1A, "set_free_mode", ID, NONE;
+    IsFreeMode = 0x1B, "is_free_mode", NONE, BYTE;
+    GetAngles = 0x20, "get_angles", NONE, SIX_INT16_OR_PADDED;
+    SendAngle = 0x21, "send_angle", Layout::Fields(&[U8, I16, U8]), NONE;
+    SendAngles = 0x22, "send_angles", Layout::Fields(&[I16, I16, I16, I16, I16, I16, U8]), NONE;
+    GetCoords = 0x23, "get_coords", NONE, SIX_INT16_OR_PADDED;
+    SendCoord = 0x24, "send_coord", Layout::Fields(&[U8, I16, U8]), NONE;
+    SendCoords = 0x25, "send_coords", Layout::Fields(&[I16, I16, I16, I16, I16, I16, U8, U8]), NONE;
+    Pause = 0x26, "pause", NONE, NONE;
+    IsPaused = 0x27, "is_paused", NONE, BYTE;
+    Resume = 0x28, "resume", NONE, NONE;
+    Stop = 0x29, "stop", NONE, NONE;
+    IsInPosition = 0x2A, "is_in_position", Layout::Fields(&[I16, I16, I16, I16, I16, I16, U8]), BYTE;
+    IsMoving = 0x2B, "is_moving", NONE, BYTE;
+    JogAngle = 0x30, "jog_angle", Layout::Fields(&[U8, U8, U8]), NONE;
+    JogCoord = 0x32, "jog_coord", Layout::Fields(&[U8, U8, U8]), NONE;
+    JogIncrement = 0x33, "jog_increment", Layout::Fields(&[U8, I16, U8]), NONE;
+    JogStop = 0x34, "jog_stop", NONE, NONE;
+    // Codes as in pymycobot's ProtocolCode (SetHTSGripperTorque and friends).
+    SetGripperTorque = 0x35, "set_gripper_torque", INT16, NONE;
+    GetGripperTorque = 0x36, "get_gripper_torque", NONE, INT16;
+    GetGripperProtectCurrent = 0x37, "get_gripper_protect_current", NONE, INT16;
+    SetGripperProtectCurrent = 0x39, "set_gripper_protect_current", INT16, NONE;
+    SetEncoder = 0x3A, "set_encoder", Layout::Fields(&[U8, I16]), NONE;
+    GetEncoder = 0x3B, "get_encoder", ID, INT16;
+    SetEncoders = 0x3C, "set_encoders", RAW, NONE;
+    GetEncoders = 0x3D, "get_encoders", NONE, SIX_INT16_OR_PADDED;
+    GetSpeed = 0x40, "get_speed", NONE, BYTE;
+    SetSpeed = 0x41, "set_speed", ID, NONE;
+    GetFeedOverride = 0x42, "get_feed_override", NONE, BYTE;
+    GetAcceleration = 0x44, "get_acceleration", NONE, INT16;
+    GetJointMinAngle = 0x4A, "get_joint_min_angle", ID, INT16;
+    GetJointMaxAngle = 0x4B, "get_joint_max_angle", ID, INT16;
+    IsServoEnable = 0x50, "is_servo_enable", ID, ID_AND_BYTE;
+    IsAllServoEnable = 0x51, "is_all_servo_enable", NONE, BYTE;
+    SetServoData = 0x52, "set_servo_data", Layout::Fields(&[U8, U8, U8]), NONE;
+    GetServoData = 0x53, "get_servo_data", Layout::Fields(&[U8, U8]), BYTE_OR_INT16;
+    SetServoCalibration = 0x54, "set_servo_calibration", RAW, NONE;
+    ReleaseServo = 0x56, "release_servo", ID, NONE;
+    FocusServo = 0x57, "focus_servo", ID, NONE;
+    SetPinMode = 0x60, "set_pin_mode", Layout::Fields(&[U8, U8]), NONE;
+    SetDigitalOutput = 0x61, "set_digital_output", Layout::Fields(&[U8, U8]), NONE;
+    GetDigitalInput = 0x62, "get_digital_input", ID, BYTE;
+    SetPwmMode = 0x63, "set_pwm_mode", RAW, NONE;
+    SetPwmOutput = 0x64, "set_pwm_output", Layout::Fields(&[U8, I16, U8]), NONE;
+    GetGripperValue = 0x65, "get_gripper_value", NONE, BYTE_OR_INT16;
+    SetGripperState = 0x66, "set_gripper_state", Layout::Fields(&[U8, U8]), NONE;
+    SetGripperValue = 0x67, "set_gripper_value", Layout::Fields(&[U8, U8]), NONE;
+    SetGripperIni = 0x68, "set_gripper_ini", NONE, NONE;
+    IsGripperMoving = 0x69, "is_gripper_moving", NONE, BYTE;
+    SetColor = 0x6A, "set_color", Layout::Fields(&[U8, U8, U8]), NONE;
+    // pymycobot ProtocolCode SET_MOVEMENT_TYPE / GET_MOVEMENT_TYPE, in the
+    // coordinate block after the tool and world reference genres (0x81-0x86).
+    SetMovementType = 0x87, "set_movement_type", ID, NONE;
+    GetMovementType = 0x88, "get_movement_type", NONE, BYTE;
+    SetBasicOutput = 0xA0, "set_basic_output", Layout::Fields(&[U8, U8]), NONE;
+    GetBasicInput = 0xA1, "get_basic_input", ID, BYTE;
+    GetBasicVersion = 0xC1, "get_basic_version", NONE, BYTE;
+    // pymycobot ProtocolCode GET/SET_PLAN_SPEED and GET/SET_PLAN_ACCELERATION.
+    GetPlanSpeed = 0xD0, "get_plan_speed", NONE, TWO_BYTES;
+    GetPlanAcceleration = 0xD1, "get_plan_acceleration", NONE, TWO_BYTES;
+    SetPlanSpeed = 0xD2, "set_plan_speed", Layout::Fields(&[U8, U8]), NONE;
+    SetPlanAcceleration = 0xD3, "set_plan_acceleration", Layout::Fields(&[U8, U8]), NONE;
+    GetServoCurrents = 0xE2, "get_servo_currents", NONE, SIX_INT16;
+    GetServoTemps = 0xE5, "get_servo_temps", NONE, SIX_INT16;
+}
 
 impl Command {
     pub const HEADER: u8 = 0xFE;
     pub const FOOTER: u8 = 0xFA;
 
-    pub const VERSION: u8 = 0x00;
-
-    pub const POWER_ON: u8 = 0x10;
-    pub const POWER_OFF: u8 = 0x11;
-    pub const IS_POWER_ON: u8 = 0x12;
-    pub const RELEASE_ALL_SERVOS: u8 = 0x13;
-    pub const IS_CONTROLLER_CONNECTED: u8 = 0x14;
-    pub const READ_NEXT_ERROR: u8 = 0x15;
-    pub const SET_FREE_MODE: u8 = 0x1A;
-    pub const IS_FREE_MODE: u8 = 0x1B;
-
-    pub const GET_ANGLES: u8 = 0x20;
-    pub const SEND_ANGLE: u8 = 0x21;
-    pub const SEND_ANGLES: u8 = 0x22;
-    pub const GET_COORDS: u8 = 0x23;
-    pub const SEND_COORD: u8 = 0x24;
-    pub const SEND_COORDS: u8 = 0x25;
-    pub const PAUSE: u8 = 0x26;
-    pub const IS_PAUSED: u8 = 0x27;
-    pub const RESUME: u8 = 0x28;
-    pub const STOP: u8 = 0x29;
-    pub const IS_IN_POSITION: u8 = 0x2A;
-    pub const IS_MOVING: u8 = 0x2B;
-
-    pub const JOG_ANGLE: u8 = 0x30;
-    pub const JOG_COORD: u8 = 0x32;
-    pub const JOG_STOP: u8 = 0x34;
-    pub const SET_ENCODER: u8 = 0x3A;
-    pub const GET_ENCODER: u8 = 0x3B;
-    pub const SET_ENCODERS: u8 = 0x3C;
-    pub const GET_ENCODERS: u8 = 0x3D;
-
-    pub const GET_SPEED: u8 = 0x40;
-    pub const SET_SPEED: u8 = 0x41;
-    pub const GET_FEED_OVERRIDE: u8 = 0x42;
-    pub const GET_ACCELERATION: u8 = 0x44;
-    pub const GET_JOINT_MIN_ANGLE: u8 = 0x4A;
-    pub const GET_JOINT_MAX_ANGLE: u8 = 0x4B;
-
-    pub const IS_SERVO_ENABLE: u8 = 0x50;
-    pub const IS_ALL_SERVO_ENABLE: u8 = 0x51;
-    pub const SET_SERVO_DATA: u8 = 0x52;
-    pub const GET_SERVO_DATA: u8 = 0x53;
-    pub const SET_SERVO_CALIBRATION: u8 = 0x54;
-    pub const RELEASE_SERVO: u8 = 0x56;
-    pub const FOCUS_SERVO: u8 = 0x57;
-
-    pub const SET_PIN_MODE: u8 = 0x60;
-    pub const SET_DIGITAL_OUTPUT: u8 = 0x61;
-    pub const GET_DIGITAL_INPUT: u8 = 0x62;
-    pub const SET_PWM_MODE: u8 = 0x63;
-    pub const SET_PWM_OUTPUT: u8 = 0x64;
-    pub const GET_GRIPPER_VALUE: u8 = 0x65;
-    pub const SET_GRIPPER_STATE: u8 = 0x66;
-    pub const SET_GRIPPER_VALUE: u8 = 0x67;
-    pub const SET_GRIPPER_INI: u8 = 0x68;
-    pub const IS_GRIPPER_MOVING: u8 = 0x69;
-    pub const SET_COLOR: u8 = 0x6A;
-
-    pub const SET_BASIC_OUTPUT: u8 = 0xA0;
-    pub const GET_BASIC_INPUT: u8 = 0xA1;
+    pub fn as_u8(self) -> u8 {
+        self as u8
+    }
+    /// Whether the genre starts motion or energizes the arm.
+    pub fn starts_motion(self) -> bool {
+        matches!(
+            self,
+            Command::PowerOn
+                | Command::SendAngle
+                | Command::SendAngles
+                | Command::SendCoord
+                | Command::SendCoords
+                | Command::Resume
+                | Command::JogAngle
+                | Command::JogCoord
+                | Command::JogIncrement
+                | Command::SetEncoder
+                | Command::SetEncoders
+                | Command::FocusServo
+        )
+    }
+    pub fn class(self) -> CommandClass {
+        match self.as_u8() {
+            0x09 | 0x35..=0x39 | 0x60..=0x6A => CommandClass::Atom,
+            0x20..=0x3F => CommandClass::Motion,
+            _ => CommandClass::Query,
+        }
+    }
+}
+
+/// Broad groups of genres with similar reply latency.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CommandClass {
+    /// Angle, coordinate, jog and encoder genres, handled by the motion planner.
+    Motion,
+    /// Pins, gripper and LED genres, forwarded to the ATOM.
+    Atom,
+    /// Everything else: status, settings and servo registers.
+    Query,
+}
+
+impl std::fmt::Display for Command {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
 }
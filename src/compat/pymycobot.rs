@@ -0,0 +1,287 @@
+//! Mirrors pymycobot's `MyCobot` method names and argument orders so Python
+//! scripts can be ported line by line. Joint and axis ids are 1-based integers
+//! as in pymycobot.
+
+use crate::common::*;
+use crate::io::{Connection, Serial};
+use crate::operator::MyCobotOperator;
+use anyhow::Result;
+use std::convert::{TryFrom, TryInto};
+
+fn direction(direction: u8) -> Direction {
+    if direction == 0 {
+        Direction::Decrease
+    } else {
+        Direction::Increase
+    }
+}
+
+fn movement_type(flag: u8) -> MovementType {
+    if flag == 0 {
+        MovementType::MoveJ
+    } else {
+        MovementType::MoveL
+    }
+}
+
+fn six(values: &[f64]) -> Result<[f64; 6]> {
+    values
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Expected 6 values, got {}", values.len()))
+}
+
+pub struct MyCobot<T: Connection = Serial> {
+    operator: MyCobotOperator<T>,
+}
+
+impl MyCobot<Serial> {
+    pub fn new(port: &str, baudrate: u32) -> MyCobot<Serial> {
+        MyCobot {
+            operator: MyCobotOperator::<Serial>::new(port, baudrate),
+        }
+    }
+}
+
+impl<T: Connection> MyCobot<T> {
+    pub fn from_operator(operator: MyCobotOperator<T>) -> MyCobot<T> {
+        MyCobot { operator }
+    }
+    pub fn operator(&mut self) -> &mut MyCobotOperator<T> {
+        &mut self.operator
+    }
+    pub fn into_operator(self) -> MyCobotOperator<T> {
+        self.operator
+    }
+
+    pub fn power_on(&mut self) -> Result<()> {
+        self.operator.power_on()
+    }
+    pub fn power_off(&mut self) -> Result<()> {
+        self.operator.power_off()
+    }
+    pub fn is_power_on(&mut self) -> Result<i32> {
+        self.operator.is_power_on()
+    }
+    pub fn release_all_servos(&mut self) -> Result<()> {
+        self.operator.release_all_servos()
+    }
+    pub fn is_controller_connected(&mut self) -> Result<i32> {
+        self.operator.is_controller_connected()
+    }
+
+    pub fn set_fresh_mode(&mut self, mode: u8) -> Result<()> {
+        self.operator.set_fresh_mode(if mode == 0 {
+            FreshMode::Queue
+        } else {
+            FreshMode::Interrupt
+        })
+    }
+    pub fn get_fresh_mode(&mut self) -> Result<i32> {
+        Ok(self.operator.get_fresh_mode()? as i32)
+    }
+    /// `move_type`: 1 moveL, 0 moveJ.
+    pub fn set_movement_type(&mut self, move_type: u8) -> Result<()> {
+        self.operator.set_movement_type(movement_type(move_type))
+    }
+    pub fn get_movement_type(&mut self) -> Result<i32> {
+        Ok(self.operator.get_movement_type()? as i32)
+    }
+    /// `[moveL, moveJ]` planner speeds, as pymycobot returns them.
+    pub fn get_plan_speed(&mut self) -> Result<Vec<i32>> {
+        let plan = self.operator.get_plan_speed()?;
+        Ok(vec![plan.move_l as i32, plan.move_j as i32])
+    }
+    pub fn set_plan_speed(&mut self, speed: u8, is_linear: u8) -> Result<()> {
+        self.operator
+            .set_plan_speed(speed, movement_type(is_linear))
+    }
+    pub fn get_plan_acceleration(&mut self) -> Result<Vec<i32>> {
+        let plan = self.operator.get_plan_acceleration()?;
+        Ok(vec![plan.move_l as i32, plan.move_j as i32])
+    }
+    pub fn set_plan_acceleration(&mut self, acceleration: u8, is_linear: u8) -> Result<()> {
+        self.operator
+            .set_plan_acceleration(acceleration, movement_type(is_linear))
+    }
+    pub fn get_angles(&mut self) -> Result<Vec<f64>> {
+        Ok(self.operator.get_angles()?.to_vec())
+    }
+    pub fn send_angle(&mut self, id: u8, degree: f64, speed: u8) -> Result<()> {
+        self.operator
+            .send_angle(Angle::try_from(id)?, degree, speed)
+    }
+    pub fn send_angles(&mut self, degrees: &[f64], speed: u8) -> Result<()> {
+        self.operator.send_angles(&six(degrees)?, speed)
+    }
+    pub fn get_radians(&mut self) -> Result<Vec<f64>> {
+        Ok(self.operator.get_radians()?.to_vec())
+    }
+    pub fn send_radians(&mut self, radians: &[f64], speed: u8) -> Result<()> {
+        self.operator.send_radians(&six(radians)?, speed)
+    }
+    pub fn get_coords(&mut self) -> Result<Vec<f64>> {
+        Ok(self.operator.get_coords()?.to_vec())
+    }
+    pub fn send_coord(&mut self, id: u8, coord_value: f64, speed: u8) -> Result<()> {
+        self.operator
+            .send_coord(Coord::try_from(id)?, coord_value, speed)
+    }
+    pub fn send_coords(&mut self, coords: &[f64], speed: u8, coord_mode: u8) -> Result<()> {
+        self.operator
+            .send_coords(&six(coords)?, speed, Mode::try_from(coord_mode)?)
+    }
+    pub fn sync_send_angles(&mut self, degrees: &[f64], speed: u8, timeout: f64) -> Result<()> {
+        self.operator
+            .sync_send_angles(&six(degrees)?, speed, timeout)?;
+        Ok(())
+    }
+    pub fn sync_send_coords(
+        &mut self,
+        coords: &[f64],
+        speed: u8,
+        coord_mode: u8,
+        timeout: f64,
+    ) -> Result<()> {
+        self.operator.sync_send_coords(
+            &six(coords)?,
+            speed,
+            Mode::try_from(coord_mode)?,
+            timeout,
+        )?;
+        Ok(())
+    }
+    /// `id` is 0 for angles and 1 for coords, as in pymycobot.
+    pub fn is_in_position(&mut self, data: &[f64], id: u8) -> Result<i32> {
+        if id == 1 {
+            self.operator.is_in_coord_position(&six(data)?)
+        } else {
+            self.operator.is_in_angle_position(&six(data)?)
+        }
+    }
+    pub fn is_moving(&mut self) -> Result<i32> {
+        self.operator.is_moving()
+    }
+    pub fn pause(&mut self) -> Result<()> {
+        self.operator.pause()
+    }
+    pub fn is_paused(&mut self) -> Result<i32> {
+        self.operator.is_paused()
+    }
+    pub fn resume(&mut self) -> Result<()> {
+        self.operator.resume()
+    }
+    pub fn stop(&mut self) -> Result<()> {
+        self.operator.stop()
+    }
+
+    pub fn jog_angle(&mut self, joint_id: u8, direction_flag: u8, speed: u8) -> Result<()> {
+        self.operator
+            .jog_angle(Angle::try_from(joint_id)?, direction(direction_flag), speed)
+    }
+    pub fn jog_coord(&mut self, coord_id: u8, direction_flag: u8, speed: u8) -> Result<()> {
+        self.operator
+            .jog_coord(Coord::try_from(coord_id)?, direction(direction_flag), speed)
+    }
+    pub fn jog_increment(&mut self, joint_id: u8, increment: f64, speed: u8) -> Result<()> {
+        self.operator
+            .jog_increment(Angle::try_from(joint_id)?, increment, speed)
+    }
+    pub fn jog_stop(&mut self) -> Result<()> {
+        self.operator.jog_stop()
+    }
+    pub fn set_encoder(&mut self, joint_id: u8, encoder: i16) -> Result<()> {
+        self.operator
+            .set_encoder(Angle::try_from(joint_id)?, encoder)
+    }
+    pub fn get_encoder(&mut self, joint_id: u8) -> Result<i32> {
+        self.operator.get_encoder(Angle::try_from(joint_id)?)
+    }
+    pub fn set_encoders(&mut self, encoders: &[i16], sp: u8) -> Result<()> {
+        self.operator.set_encoders(encoders, sp)
+    }
+    pub fn get_encoders(&mut self) -> Result<Vec<i16>> {
+        self.operator.get_encoders()
+    }
+
+    pub fn get_speed(&mut self) -> Result<Vec<i16>> {
+        self.operator.get_speed()
+    }
+    pub fn set_speed(&mut self, speed: u8) -> Result<()> {
+        self.operator.set_speed(speed)
+    }
+    pub fn get_joint_min_angle(&mut self, joint_id: u8) -> Result<Vec<i16>> {
+        self.operator
+            .get_joint_min_angle(Angle::try_from(joint_id)?)
+    }
+    pub fn get_joint_max_angle(&mut self, joint_id: u8) -> Result<Vec<i16>> {
+        self.operator
+            .get_joint_max_angle(Angle::try_from(joint_id)?)
+    }
+
+    pub fn is_servo_enable(&mut self, servo_id: u8) -> Result<i32> {
+        self.operator.is_servo_enable(Angle::try_from(servo_id)?)
+    }
+    pub fn is_all_servo_enable(&mut self) -> Result<i32> {
+        self.operator.is_all_servo_enable()
+    }
+    pub fn set_servo_data(&mut self, servo_no: u8, data_id: u8, value: u8) -> Result<()> {
+        self.operator.set_servo_data(servo_no, data_id, value)
+    }
+    pub fn get_servo_data(&mut self, servo_no: u8, data_id: u8) -> Result<Vec<i16>> {
+        self.operator.get_servo_data(servo_no, data_id)
+    }
+    pub fn release_servo(&mut self, servo_id: u8) -> Result<()> {
+        self.operator.release_servo(Angle::try_from(servo_id)?)
+    }
+    pub fn focus_servo(&mut self, servo_id: u8) -> Result<()> {
+        self.operator.focus_servo(Angle::try_from(servo_id)?)
+    }
+
+    pub fn set_color(&mut self, r: u8, g: u8, b: u8) -> Result<()> {
+        self.operator.set_color(r, g, b)
+    }
+    pub fn set_pin_mode(&mut self, pin_no: u8, pin_mode: u8) -> Result<()> {
+        let pin_mode = PinMode::try_from(pin_mode)?;
+        self.operator.set_pin_mode(pin_no, pin_mode)
+    }
+    pub fn set_digital_output(&mut self, pin_no: u8, pin_signal: u8) -> Result<()> {
+        self.operator.set_digital_output(pin_no, pin_signal != 0)
+    }
+    pub fn get_digital_input(&mut self, pin_no: u8) -> Result<i32> {
+        self.operator.get_digital_intput(pin_no)
+    }
+    pub fn set_pwm_output(&mut self, channel: u8, frequency: i16, pin_val: u8) -> Result<()> {
+        self.operator.set_pwm_output(channel, frequency, pin_val)
+    }
+    pub fn set_gripper_state(&mut self, flag: u8, speed: u8) -> Result<()> {
+        let state = if flag == 0 {
+            GripperState::Open
+        } else {
+            GripperState::Close
+        };
+        self.operator.set_gripper_state(state, speed)
+    }
+    pub fn set_gripper_value(&mut self, value: u8, speed: u8) -> Result<()> {
+        self.operator.set_gripper_value(value, speed)
+    }
+    pub fn get_gripper_value(&mut self) -> Result<Vec<i16>> {
+        self.operator.get_gripper_value()
+    }
+    pub fn set_gripper_ini(&mut self) -> Result<()> {
+        self.operator.set_gripper_ini()
+    }
+    pub fn is_gripper_moving(&mut self) -> Result<i32> {
+        self.operator.is_gripper_moving()
+    }
+    pub fn set_basic_output(&mut self, pin_no: u8, pin_signal: u8) -> Result<()> {
+        self.operator.set_basic_output(pin_no, pin_signal != 0)
+    }
+    pub fn get_basic_input(&mut self, pin_no: u8) -> Result<i32> {
+        self.operator.get_basic_input(pin_no)
+    }
+    /// Sleeps and returns `self` to allow pymycobot-style chaining.
+    pub fn wait(&mut self, t: f64) -> &mut MyCobot<T> {
+        MyCobotOperator::<T>::wait(t);
+        self
+    }
+}
@@ -0,0 +1,128 @@
+//! Operator settings read from a TOML file, so a deployment can switch
+//! robots without recompiling:
+//!
+//! ```toml
+//! port = "/dev/ttyUSB0"
+//! baud = 115200
+//! model = "mycobot_280"
+//! tool = [0, 0, 100, 0, 0, 0]
+//!
+//! [limits]
+//! min = [-160, -130, -150, -160, -160, -175]
+//! max = [160, 130, 150, 160, 160, 175]
+//!
+//! [poses]
+//! home = { angles = [0, 0, 0, 0, 0, 0] }
+//! ```
+
+use super::io::{Connection, Serial};
+use super::kinematics::KinematicModel;
+use super::limits::{JointLimits, Workspace};
+use super::operator::{MyCobotOperator, MyCobotSerialOperator};
+use super::poses::PoseLibrary;
+use super::transform::Pose;
+use anyhow::Result;
+use std::fs;
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum RobotModel {
+    #[serde(rename = "mycobot_280")]
+    MyCobot280,
+    #[serde(rename = "mycobot_320")]
+    MyCobot320,
+    #[serde(rename = "mycobot_pro_630")]
+    MyCobotPro630,
+    #[serde(rename = "mypalletizer_260")]
+    MyPalletizer260,
+}
+
+impl RobotModel {
+    pub fn workspace(self) -> Workspace {
+        match self {
+            RobotModel::MyCobot280 => Workspace::mycobot_280(),
+            RobotModel::MyCobot320 => Workspace::mycobot_320(),
+            RobotModel::MyCobotPro630 => Workspace::mycobot_pro_630(),
+            RobotModel::MyPalletizer260 => Workspace::mypalletizer_260(),
+        }
+    }
+    /// `None` for models without DH parameters in `KinematicModel`.
+    pub fn kinematic_model(self) -> Option<KinematicModel> {
+        match self {
+            RobotModel::MyCobot280 => Some(KinematicModel::mycobot_280()),
+            RobotModel::MyCobot320 => Some(KinematicModel::mycobot_320()),
+            _ => None,
+        }
+    }
+}
+
+fn default_baud() -> u32 {
+    115200
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OperatorConfig {
+    pub port: String,
+    #[serde(default = "default_baud")]
+    pub baud: u32,
+    /// Selects the workspace, and the joint limits when neither `limits`
+    /// nor the firmware provides them.
+    pub model: Option<RobotModel>,
+    pub limits: Option<JointLimits>,
+    /// Tool center point in the flange frame, as coords.
+    pub tool: Option<[f64; 6]>,
+    #[serde(default)]
+    pub poses: PoseLibrary,
+}
+
+impl OperatorConfig {
+    pub fn from_toml_str(s: &str) -> Result<OperatorConfig> {
+        Ok(toml::from_str(s)?)
+    }
+    pub fn to_toml_string(&self) -> Result<String> {
+        Ok(toml::to_string(self)?)
+    }
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<OperatorConfig> {
+        OperatorConfig::from_toml_str(&fs::read_to_string(path)?)
+    }
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        Ok(fs::write(path, self.to_toml_string()?)?)
+    }
+    /// Applies everything but the port settings to `operator`.
+    pub fn apply<T: Connection>(&self, operator: &mut MyCobotOperator<T>) {
+        if let Some(model) = self.model {
+            operator.set_workspace(Some(model.workspace()));
+        }
+        let limits = self
+            .limits
+            .clone()
+            .or_else(|| match operator.joint_limits() {
+                Some(_) => None,
+                None => self
+                    .model
+                    .and_then(RobotModel::kinematic_model)
+                    .map(|m| JointLimits::from_model(&m)),
+            });
+        if limits.is_some() {
+            operator.set_joint_limits(limits);
+        }
+        if let Some(tool) = &self.tool {
+            operator.set_tool_pose(Some(Pose::from_coords(tool)));
+        }
+        operator.set_pose_library(self.poses.clone());
+    }
+    /// Opens the port and connects as `MyCobotOperator::connect` does, then applies the rest.
+    pub fn open(&self) -> Result<MyCobotSerialOperator> {
+        let mut operator = MyCobotOperator::connect(Serial::open(&self.port, self.baud)?)?;
+        self.apply(&mut operator);
+        Ok(operator)
+    }
+}
+
+impl MyCobotSerialOperator {
+    /// Connects with the settings in the TOML file at `path`; see `OperatorConfig`.
+    pub fn from_config<P: AsRef<Path>>(path: P) -> Result<MyCobotSerialOperator> {
+        OperatorConfig::load(path)?.open()
+    }
+}
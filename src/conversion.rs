@@ -0,0 +1,74 @@
+//! Scaling between physical units and the firmware's integer encoding.
+//! Angles are sent as hundredths of a degree and positions as tenths of a millimeter.
+
+use anyhow::{Error, Result};
+use std::convert::TryInto;
+
+pub const ANGLE_SCALE: f64 = 100.0;
+pub const COORD_SCALE: f64 = 10.0;
+
+pub fn angle_to_int(degree: f64) -> i16 {
+    (degree * ANGLE_SCALE).round() as i16
+}
+
+pub fn coord_to_int(coord: f64) -> i16 {
+    (coord * COORD_SCALE).round() as i16
+}
+
+pub fn int_to_angle(val: i16) -> f64 {
+    (val as f64) / ANGLE_SCALE
+}
+
+pub fn int_to_coord(val: i16) -> f64 {
+    (val as f64) / COORD_SCALE
+}
+
+/// Converts a raw servo encoder reading to degrees. Half of `resolution`
+/// (2048 on 4096-count servos) is the calibrated zero.
+pub fn encoder_to_angle(encoder: i16, resolution: u16) -> f64 {
+    let resolution = resolution as f64;
+    (encoder as f64 - resolution / 2.0) * 360.0 / resolution
+}
+
+pub fn angle_to_encoder(degree: f64, resolution: u16) -> i16 {
+    let resolution = resolution as f64;
+    (degree * resolution / 360.0 + resolution / 2.0).round() as i16
+}
+
+pub fn angles_to_int_vec(degrees: &[f64; 6]) -> [i16; 6] {
+    degrees.map(angle_to_int)
+}
+
+pub fn int_vec_to_angles(vals: &[i16; 6]) -> [f64; 6] {
+    vals.map(int_to_angle)
+}
+
+/// Positions (first three values) use the coordinate scale, rotations the angle scale.
+pub fn coords_to_int_vec(coords: &[f64; 6]) -> Vec<i16> {
+    coords
+        .iter()
+        .enumerate()
+        .map(|(i, coord)| {
+            if i < 3 {
+                coord_to_int(*coord)
+            } else {
+                angle_to_int(*coord)
+            }
+        })
+        .collect()
+}
+
+pub fn int_vec_to_coords(vals: &[i16]) -> Result<[f64; 6]> {
+    vals.iter()
+        .enumerate()
+        .map(|(i, v)| {
+            if i < 3 {
+                int_to_coord(*v)
+            } else {
+                int_to_angle(*v)
+            }
+        })
+        .collect::<Vec<_>>()[..]
+        .try_into()
+        .map_err(Error::msg)
+}
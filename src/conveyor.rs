@@ -0,0 +1,169 @@
+//! Picking from a moving belt. An external encoder reports belt travel,
+//! from which the current pose of a part seen earlier is predicted, ahead by
+//! the encoder-to-robot latency, and the tool is streamed along with it.
+
+use super::io::Connection;
+use super::jog::tool_twist_to_joint_velocities;
+use super::kinematics::KinematicModel;
+use super::operator::MyCobotOperator;
+use super::servoing::{stream_period, JointStreamConfig, JointVelocityStreamer, Twist};
+use super::transform::*;
+use anyhow::Result;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// A belt moving along a fixed direction in the robot base frame.
+#[derive(Clone, Debug)]
+pub struct Conveyor {
+    direction: Vector3,
+    mm_per_count: f64,
+    /// Delay between the encoder moving and its reading reaching `feed_encoder`.
+    pub latency: Duration,
+    /// Span of readings over which the belt speed is averaged.
+    pub window: Duration,
+    samples: VecDeque<(Instant, i64)>,
+}
+
+/// A part on the belt: its pose when seen, and the encoder count at that moment.
+#[derive(Clone, Debug)]
+pub struct TrackedPart {
+    pub pose: Pose,
+    pub count: i64,
+}
+
+impl Conveyor {
+    /// `direction` is the belt travel for increasing counts, in the base frame.
+    pub fn new(direction: Vector3, mm_per_count: f64) -> Result<Conveyor> {
+        let length = norm(&direction);
+        if length < 1e-9 {
+            return Err(anyhow::anyhow!("Conveyor direction must be non-zero"));
+        }
+        Ok(Conveyor {
+            direction: direction.map(|v| v / length),
+            mm_per_count,
+            latency: Duration::from_millis(30),
+            window: Duration::from_millis(500),
+            samples: VecDeque::new(),
+        })
+    }
+    pub fn direction(&self) -> &Vector3 {
+        &self.direction
+    }
+    pub fn mm_per_count(&self) -> f64 {
+        self.mm_per_count
+    }
+    pub fn feed_encoder(&mut self, count: i64) {
+        self.feed_encoder_at(count, Instant::now());
+    }
+    pub fn feed_encoder_at(&mut self, count: i64, at: Instant) {
+        self.samples.push_back((at, count));
+        while self.samples.len() > 2
+            && self
+                .samples
+                .front()
+                .is_some_and(|(t, _)| at.saturating_duration_since(*t) > self.window)
+        {
+            self.samples.pop_front();
+        }
+    }
+    /// Latest encoder count, `None` before the first reading.
+    pub fn count(&self) -> Option<i64> {
+        self.samples.back().map(|(_, c)| *c)
+    }
+    /// Belt speed in mm/s along `direction`.
+    pub fn speed(&self) -> f64 {
+        match (self.samples.front(), self.samples.back()) {
+            (Some((t0, c0)), Some((t1, c1))) => {
+                let dt = t1.saturating_duration_since(*t0).as_secs_f64();
+                if dt > 0.0 {
+                    (c1 - c0) as f64 * self.mm_per_count / dt
+                } else {
+                    0.0
+                }
+            }
+            _ => 0.0,
+        }
+    }
+    /// Belt travel in mm from `count` to where the belt is expected `lookahead`
+    /// from now, extrapolating past the latest reading and the latency.
+    pub fn travel_since(&self, count: i64, lookahead: Duration) -> f64 {
+        let (at, latest) = match self.samples.back() {
+            Some(sample) => *sample,
+            None => return 0.0,
+        };
+        let ahead = at.elapsed() + self.latency + lookahead;
+        (latest - count) as f64 * self.mm_per_count + self.speed() * ahead.as_secs_f64()
+    }
+    /// Pose of `part` expected `lookahead` from now.
+    pub fn predict(&self, part: &TrackedPart, lookahead: Duration) -> Pose {
+        let travel = self.travel_since(part.count, lookahead);
+        Pose::new(
+            add(&part.pose.position, &self.direction.map(|v| v * travel)),
+            part.pose.rotation,
+        )
+    }
+}
+
+/// Per-cycle input to `track_conveyor`.
+#[derive(Clone, Copy, Debug)]
+pub struct ConveyorInput {
+    pub encoder: i64,
+    /// Height of the tool above the part in mm, for approaching and lifting off.
+    pub hover_mm: f64,
+}
+
+impl<T: Connection> MyCobotOperator<T> {
+    /// Keeps the tool center point on `part` as it rides the belt, until
+    /// `next_input` returns `None`. Each cycle the encoder reading is fed to
+    /// `conveyor`, the part pose one stream period ahead is predicted, and the
+    /// belt velocity plus `gain` (1/s) times the remaining pose error is
+    /// streamed as joint velocities through the Jacobian of `model`.
+    pub fn track_conveyor<F>(
+        &mut self,
+        model: &KinematicModel,
+        config: JointStreamConfig,
+        gain: f64,
+        conveyor: &mut Conveyor,
+        part: &TrackedPart,
+        mut next_input: F,
+    ) -> Result<()>
+    where
+        F: FnMut() -> Option<ConveyorInput>,
+    {
+        let period = stream_period(config.rate_hz);
+        let tool = self.tool_pose().copied();
+        let mut streamer = JointVelocityStreamer::new(self, config)?;
+        while let Some(input) = next_input() {
+            conveyor.feed_encoder(input.encoder);
+            let mut target = conveyor.predict(part, period);
+            target.position[2] += input.hover_mm;
+            let flange = model.forward(streamer.target());
+            let current = match &tool {
+                Some(tool) => flange.compose(tool),
+                None => flange,
+            };
+            let belt = conveyor.direction().map(|v| v * conveyor.speed());
+            let linear = add(
+                &belt,
+                &sub(&target.position, &current.position).map(|v| v * gain),
+            );
+            let error = mat_mul(&target.rotation, &transpose(&current.rotation));
+            let (angle, axis) = matrix_to_axis_angle(&error);
+            let angular = axis.map(|v| (v * angle * gain).to_degrees());
+            // The jog solver takes the twist in the tool's own frame.
+            let to_tool = transpose(&current.rotation);
+            let twist = Twist {
+                linear: mat_vec(&to_tool, &linear),
+                angular: mat_vec(&to_tool, &angular),
+            };
+            let velocities =
+                tool_twist_to_joint_velocities(model, streamer.target(), tool.as_ref(), &twist)
+                    .unwrap_or([0.0; 6]);
+            streamer.stream_joint_velocities(&velocities);
+            if !streamer.spin(self)? {
+                return Ok(());
+            }
+        }
+        self.stop()
+    }
+}
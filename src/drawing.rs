@@ -0,0 +1,242 @@
+//! Drawing SVG paths with a pen held by the arm.
+//!
+//! SVG `x` maps to the base +X axis and SVG `y` (which points down the page)
+//! to the base -Y axis, so a drawing viewed from above is not mirrored.
+//! Element `transform` attributes are ignored.
+
+use super::io::Connection;
+use super::motion::{CartesianMoveOptions, CartesianPath, CartesianSpeed};
+use super::operator::MyCobotOperator;
+use anyhow::Result;
+use svgtypes::{SimplePathSegment, SimplifyingPathParser};
+
+/// Polylines in SVG user units, one per pen-down stroke.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Drawing {
+    pub strokes: Vec<Vec<[f64; 2]>>,
+}
+
+fn flatten(points: &[[f64; 2]], max_segment: f64) -> usize {
+    let length: f64 = points
+        .windows(2)
+        .map(|w| ((w[1][0] - w[0][0]).powi(2) + (w[1][1] - w[0][1]).powi(2)).sqrt())
+        .sum();
+    ((length / max_segment).ceil() as usize).max(1)
+}
+
+impl Drawing {
+    /// Parses SVG path data (`d` attribute). Curves are split into segments
+    /// no longer than `max_segment` user units.
+    pub fn from_path_data(d: &str, max_segment: f64) -> Result<Drawing> {
+        let mut drawing = Drawing::default();
+        drawing.add_path_data(d, max_segment)?;
+        Ok(drawing)
+    }
+    /// Collects `path`, `line`, `polyline` and `polygon` elements from an SVG document.
+    pub fn from_svg_str(svg: &str, max_segment: f64) -> Result<Drawing> {
+        let doc = roxmltree::Document::parse(svg)?;
+        let mut drawing = Drawing::default();
+        for node in doc.descendants().filter(|n| n.is_element()) {
+            let attr = |name: &str| node.attribute(name).unwrap_or("");
+            let number = |name: &str| attr(name).trim().parse::<f64>().unwrap_or(0.0);
+            match node.tag_name().name() {
+                "path" => drawing.add_path_data(attr("d"), max_segment)?,
+                "line" => drawing.strokes.push(vec![
+                    [number("x1"), number("y1")],
+                    [number("x2"), number("y2")],
+                ]),
+                tag @ ("polyline" | "polygon") => {
+                    let values = attr("points")
+                        .split(|c: char| c == ',' || c.is_whitespace())
+                        .filter(|v| !v.is_empty())
+                        .map(|v| v.parse::<f64>())
+                        .collect::<Result<Vec<_>, _>>()?;
+                    let mut stroke: Vec<[f64; 2]> =
+                        values.chunks_exact(2).map(|p| [p[0], p[1]]).collect();
+                    if tag == "polygon" {
+                        if let Some(first) = stroke.first().copied() {
+                            stroke.push(first);
+                        }
+                    }
+                    if stroke.len() > 1 {
+                        drawing.strokes.push(stroke);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(drawing)
+    }
+    fn add_path_data(&mut self, d: &str, max_segment: f64) -> Result<()> {
+        let max_segment = max_segment.max(1e-3);
+        let mut stroke: Vec<[f64; 2]> = Vec::new();
+        let mut start = [0.0, 0.0];
+        for segment in SimplifyingPathParser::from(d) {
+            let last = stroke.last().copied().unwrap_or(start);
+            match segment? {
+                SimplePathSegment::MoveTo { x, y } => {
+                    if stroke.len() > 1 {
+                        self.strokes.push(std::mem::take(&mut stroke));
+                    }
+                    stroke.clear();
+                    start = [x, y];
+                    stroke.push(start);
+                }
+                SimplePathSegment::LineTo { x, y } => stroke.push([x, y]),
+                SimplePathSegment::Quadratic { x1, y1, x, y } => {
+                    let n = flatten(&[last, [x1, y1], [x, y]], max_segment);
+                    for i in 1..=n {
+                        let t = i as f64 / n as f64;
+                        let u = 1.0 - t;
+                        stroke.push([
+                            u * u * last[0] + 2.0 * u * t * x1 + t * t * x,
+                            u * u * last[1] + 2.0 * u * t * y1 + t * t * y,
+                        ]);
+                    }
+                }
+                SimplePathSegment::CurveTo {
+                    x1,
+                    y1,
+                    x2,
+                    y2,
+                    x,
+                    y,
+                } => {
+                    let n = flatten(&[last, [x1, y1], [x2, y2], [x, y]], max_segment);
+                    for i in 1..=n {
+                        let t = i as f64 / n as f64;
+                        let u = 1.0 - t;
+                        let (a, b, c, e) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+                        stroke.push([
+                            a * last[0] + b * x1 + c * x2 + e * x,
+                            a * last[1] + b * y1 + c * y2 + e * y,
+                        ]);
+                    }
+                }
+                SimplePathSegment::ClosePath => stroke.push(start),
+            }
+        }
+        if stroke.len() > 1 {
+            self.strokes.push(stroke);
+        }
+        Ok(())
+    }
+    /// Minimum and maximum corners in user units.
+    pub fn bounds(&self) -> Option<([f64; 2], [f64; 2])> {
+        let mut points = self.strokes.iter().flatten();
+        let first = *points.next()?;
+        Some(points.fold((first, first), |(min, max), p| {
+            (
+                [min[0].min(p[0]), min[1].min(p[1])],
+                [max[0].max(p[0]), max[1].max(p[1])],
+            )
+        }))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct DrawingConfig {
+    /// Base-frame position in mm of the SVG origin on the paper surface.
+    pub origin: [f64; 3],
+    /// mm per SVG user unit.
+    pub scale: f64,
+    /// Tool orientation (rx, ry, rz) in degrees held throughout the drawing.
+    pub orientation: [f64; 3],
+    /// Height in mm the pen is lifted between strokes.
+    pub pen_lift: f64,
+    pub draw_speed: CartesianSpeed,
+    pub travel_speed: CartesianSpeed,
+}
+
+impl Default for DrawingConfig {
+    fn default() -> DrawingConfig {
+        DrawingConfig {
+            origin: [150.0, 50.0, 100.0],
+            scale: 1.0,
+            orientation: [180.0, 0.0, 0.0],
+            pen_lift: 10.0,
+            draw_speed: CartesianSpeed {
+                linear: 20.0,
+                angular: 30.0,
+            },
+            travel_speed: CartesianSpeed::default(),
+        }
+    }
+}
+
+impl DrawingConfig {
+    /// Coords for a point in SVG user units, raised by `lift` mm.
+    pub fn coords(&self, point: &[f64; 2], lift: f64) -> [f64; 6] {
+        let [rx, ry, rz] = self.orientation;
+        [
+            self.origin[0] + point[0] * self.scale,
+            self.origin[1] - point[1] * self.scale,
+            self.origin[2] + lift,
+            rx,
+            ry,
+            rz,
+        ]
+    }
+    /// One path drawing every stroke, lifting the pen by `pen_lift` between strokes.
+    pub fn plan(&self, start: &[f64; 6], drawing: &Drawing) -> Result<CartesianPath> {
+        let mut path = CartesianPath::default();
+        path.push(*start, &self.travel_speed)?;
+        for stroke in &drawing.strokes {
+            let first = match stroke.first() {
+                Some(first) => first,
+                None => continue,
+            };
+            path.push(self.coords(first, self.pen_lift), &self.travel_speed)?;
+            path.push(self.coords(first, 0.0), &self.travel_speed)?;
+            for point in &stroke[1..] {
+                path.push(self.coords(point, 0.0), &self.draw_speed)?;
+            }
+            let last = stroke.last().unwrap();
+            path.push(self.coords(last, self.pen_lift), &self.travel_speed)?;
+        }
+        Ok(path)
+    }
+}
+
+impl<T: Connection> MyCobotOperator<T> {
+    /// Draws with the pen lifted by a z offset between strokes.
+    pub fn draw(
+        &mut self,
+        drawing: &Drawing,
+        config: &DrawingConfig,
+        options: &CartesianMoveOptions,
+    ) -> Result<()> {
+        let start = self.get_coords()?;
+        let path = config.plan(&start, drawing)?;
+        self.execute_cartesian_path(&path, options)
+    }
+    /// Draws at the paper height, calling `pen(operator, true)` before each
+    /// stroke and `pen(operator, false)` after it, e.g. to drive a tool.
+    pub fn draw_with_pen<F>(
+        &mut self,
+        drawing: &Drawing,
+        config: &DrawingConfig,
+        options: &CartesianMoveOptions,
+        mut pen: F,
+    ) -> Result<()>
+    where
+        F: FnMut(&mut MyCobotOperator<T>, bool) -> Result<()>,
+    {
+        for stroke in drawing.strokes.iter().filter(|s| !s.is_empty()) {
+            let start = self.get_coords()?;
+            let travel = CartesianPath::plan(
+                &[start, config.coords(&stroke[0], 0.0)],
+                &config.travel_speed,
+            )?;
+            self.execute_cartesian_path(&travel, options)?;
+            pen(self, true)?;
+            let mut path = CartesianPath::default();
+            for point in stroke {
+                path.push(config.coords(point, 0.0), &config.draw_speed)?;
+            }
+            self.execute_cartesian_path(&path, options)?;
+            pen(self, false)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+use super::common::Command;
+use super::conversion::*;
+use super::kinematics::KinematicModel;
+use super::transform::Pose;
+use super::utils::decode_int16_vec;
+use anyhow::Result;
+use std::convert::TryInto;
+
+/// Tolerance used to answer `is_in_position` queries from the simulated state.
+const POSITION_TOLERANCE_DEG: f64 = 0.5;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DryRunEntry {
+    pub command: Command,
+    pub data: Vec<u8>,
+    /// Simulated joint angles after the command.
+    pub angles: [f64; 6],
+    /// Why the command was rejected, if it was.
+    pub error: Option<String>,
+}
+
+/// Simulated arm used while the operator is in dry-run mode. Commands are
+/// logged and applied to a kinematic model instead of being written to the port.
+#[derive(Clone, Debug)]
+pub struct DryRun {
+    pub model: KinematicModel,
+    angles: [f64; 6],
+    log: Vec<DryRunEntry>,
+}
+
+impl DryRun {
+    pub fn new(model: KinematicModel, angles: [f64; 6]) -> DryRun {
+        DryRun {
+            model,
+            angles,
+            log: Vec::new(),
+        }
+    }
+    pub fn angles(&self) -> &[f64; 6] {
+        &self.angles
+    }
+    pub fn coords(&self) -> [f64; 6] {
+        self.model.forward(&self.angles).to_coords()
+    }
+    pub fn log(&self) -> &[DryRunEntry] {
+        &self.log
+    }
+    pub fn clear_log(&mut self) {
+        self.log.clear();
+    }
+    fn target(&self, genre: Command, data: &[u8]) -> Result<Option<[f64; 6]>> {
+        let vals = |n: usize| -> Result<Vec<i16>> {
+            data.get(..2 * n)
+                .map(decode_int16_vec)
+                .ok_or_else(|| anyhow::anyhow!("Truncated {} data", genre))
+        };
+        let from_coords = |coords: [f64; 6]| -> Result<[f64; 6]> {
+            self.model
+                .inverse(&Pose::from_coords(&coords), &self.angles)
+                .ok_or_else(|| anyhow::anyhow!("No IK solution for {:?}", coords))
+        };
+        Ok(Some(match genre {
+            Command::SendAngles => int_vec_to_angles(&vals(6)?[..].try_into()?),
+            Command::SendAngle | Command::JogIncrement => {
+                let joint = *data.first().unwrap_or(&0) as usize;
+                if !(1..=6).contains(&joint) {
+                    return Err(anyhow::anyhow!("Invalid joint {}", joint));
+                }
+                let value = int_to_angle(decode_int16_vec(&data[1..3])[0]);
+                let mut angles = self.angles;
+                if genre == Command::JogIncrement {
+                    angles[joint - 1] += value;
+                } else {
+                    angles[joint - 1] = value;
+                }
+                angles
+            }
+            Command::SendCoords => from_coords(int_vec_to_coords(&vals(6)?)?)?,
+            Command::SendCoord => {
+                let axis = *data.first().unwrap_or(&6) as usize;
+                if axis >= 6 {
+                    return Err(anyhow::anyhow!("Invalid coord axis {}", axis));
+                }
+                let raw = decode_int16_vec(&data[1..3])[0];
+                let mut coords = self.coords();
+                coords[axis] = if axis < 3 {
+                    int_to_coord(raw)
+                } else {
+                    int_to_angle(raw)
+                };
+                from_coords(coords)?
+            }
+            _ => return Ok(None),
+        }))
+    }
+    /// Validates and applies a write command, recording it in the log.
+    pub(crate) fn write(&mut self, genre: Command, data: &[u8]) -> Result<()> {
+        let result = self.target(genre, data).and_then(|target| match target {
+            Some(angles) => {
+                let violated = self.model.violated_joints(&angles);
+                if violated.is_empty() {
+                    self.angles = angles;
+                    Ok(())
+                } else {
+                    Err(anyhow::anyhow!(
+                        "Joint limits exceeded on {:?}",
+                        violated.iter().map(|j| j + 1).collect::<Vec<_>>()
+                    ))
+                }
+            }
+            None => Ok(()),
+        });
+        self.log.push(DryRunEntry {
+            command: genre,
+            data: data.to_vec(),
+            angles: self.angles,
+            error: result.as_ref().err().map(|e| e.to_string()),
+        });
+        result
+    }
+    /// Answers state queries from the simulation; other queries go to the robot.
+    pub(crate) fn query(&self, genre: Command, data: &[u8]) -> Option<Vec<i16>> {
+        match genre {
+            Command::GetAngles => Some(angles_to_int_vec(&self.angles).to_vec()),
+            Command::GetCoords => Some(coords_to_int_vec(&self.coords())),
+            Command::IsMoving => Some(vec![0]),
+            Command::IsInPosition if data.len() >= 13 => {
+                let vals: [i16; 6] = decode_int16_vec(&data[..12])[..].try_into().ok()?;
+                let target = if data[12] == 1 {
+                    self.model.inverse(
+                        &Pose::from_coords(&int_vec_to_coords(&vals).ok()?),
+                        &self.angles,
+                    )?
+                } else {
+                    int_vec_to_angles(&vals)
+                };
+                let reached = target
+                    .iter()
+                    .zip(self.angles.iter())
+                    .all(|(t, a)| (t - a).abs() <= POSITION_TOLERANCE_DEG);
+                Some(vec![reached as i16])
+            }
+            _ => None,
+        }
+    }
+}
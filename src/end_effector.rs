@@ -0,0 +1,162 @@
+//! Tools attached to the flange, behind a common interface so task code can
+//! be written once and run with any of them.
+//!
+//! `actuate` takes a normalized command: 0.0 is fully open (released) and
+//! 1.0 is fully closed (engaged). `read` reports the same scale when the tool
+//! can measure it.
+
+use super::common::*;
+use super::io::Connection;
+use super::operator::MyCobotOperator;
+use anyhow::Result;
+
+pub trait EndEffector<T: Connection> {
+    fn actuate(&mut self, operator: &mut MyCobotOperator<T>, value: f64) -> Result<()>;
+    fn read(&mut self, operator: &mut MyCobotOperator<T>) -> Result<Option<f64>>;
+
+    fn open(&mut self, operator: &mut MyCobotOperator<T>) -> Result<()> {
+        self.actuate(operator, 0.0)
+    }
+    fn close(&mut self, operator: &mut MyCobotOperator<T>) -> Result<()> {
+        self.actuate(operator, 1.0)
+    }
+}
+
+fn percent(value: f64) -> u8 {
+    (value.clamp(0.0, 1.0) * 100.0).round() as u8
+}
+
+/// The standard adaptive gripper driven over the gripper genres.
+#[derive(Clone, Debug)]
+pub struct Gripper {
+    pub speed: u8,
+}
+
+impl Default for Gripper {
+    fn default() -> Gripper {
+        Gripper { speed: 50 }
+    }
+}
+
+impl<T: Connection> EndEffector<T> for Gripper {
+    fn actuate(&mut self, operator: &mut MyCobotOperator<T>, value: f64) -> Result<()> {
+        // The firmware reports 100 when open and 0 when closed.
+        operator.set_gripper_value(100 - percent(value), self.speed)
+    }
+    fn read(&mut self, operator: &mut MyCobotOperator<T>) -> Result<Option<f64>> {
+        let res = operator.get_gripper_value()?;
+        Ok(res
+            .first()
+            .map(|v| 1.0 - (*v as f64 / 100.0).clamp(0.0, 1.0)))
+    }
+    fn open(&mut self, operator: &mut MyCobotOperator<T>) -> Result<()> {
+        operator.set_gripper_state(GripperState::Open, self.speed)
+    }
+    fn close(&mut self, operator: &mut MyCobotOperator<T>) -> Result<()> {
+        operator.set_gripper_state(GripperState::Close, self.speed)
+    }
+}
+
+/// The pro adaptive gripper, addressed as a bus servo through servo data registers.
+#[derive(Clone, Debug)]
+pub struct ProGripper {
+    pub servo_id: u8,
+}
+
+impl ProGripper {
+    pub const SET_ANGLE_ADDRESS: u8 = 11;
+    pub const GET_ANGLE_ADDRESS: u8 = 12;
+}
+
+impl Default for ProGripper {
+    fn default() -> ProGripper {
+        ProGripper { servo_id: 14 }
+    }
+}
+
+impl<T: Connection> EndEffector<T> for ProGripper {
+    fn actuate(&mut self, operator: &mut MyCobotOperator<T>, value: f64) -> Result<()> {
+        // Angle 100 is fully open.
+        operator.set_servo_data(
+            self.servo_id,
+            ProGripper::SET_ANGLE_ADDRESS,
+            100 - percent(value),
+        )
+    }
+    fn read(&mut self, operator: &mut MyCobotOperator<T>) -> Result<Option<f64>> {
+        let res = operator.get_servo_data(self.servo_id, ProGripper::GET_ANGLE_ADDRESS)?;
+        Ok(res
+            .first()
+            .map(|v| 1.0 - (*v as f64 / 100.0).clamp(0.0, 1.0)))
+    }
+}
+
+/// An on/off tool switched by one of the M5 Basic output pins.
+#[derive(Clone, Debug)]
+pub struct BasicOutputTool {
+    pub pin: u8,
+    /// When set, writing low switches the tool on.
+    pub active_low: bool,
+    engaged: Option<bool>,
+}
+
+impl BasicOutputTool {
+    pub fn new(pin: u8, active_low: bool) -> BasicOutputTool {
+        BasicOutputTool {
+            pin,
+            active_low,
+            engaged: None,
+        }
+    }
+}
+
+impl<T: Connection> EndEffector<T> for BasicOutputTool {
+    fn actuate(&mut self, operator: &mut MyCobotOperator<T>, value: f64) -> Result<()> {
+        let engaged = value >= 0.5;
+        operator.set_basic_output(self.pin, engaged != self.active_low)?;
+        self.engaged = Some(engaged);
+        Ok(())
+    }
+    /// Output pins cannot be read back, so this reports the last commanded state.
+    fn read(&mut self, _operator: &mut MyCobotOperator<T>) -> Result<Option<f64>> {
+        Ok(self.engaged.map(|e| if e { 1.0 } else { 0.0 }))
+    }
+}
+
+/// The suction pump kit, which is on while pin 5 is low.
+#[derive(Clone, Debug)]
+pub struct SuctionPump(pub BasicOutputTool);
+
+impl Default for SuctionPump {
+    fn default() -> SuctionPump {
+        SuctionPump(BasicOutputTool::new(5, true))
+    }
+}
+
+impl<T: Connection> EndEffector<T> for SuctionPump {
+    fn actuate(&mut self, operator: &mut MyCobotOperator<T>, value: f64) -> Result<()> {
+        self.0.actuate(operator, value)
+    }
+    fn read(&mut self, operator: &mut MyCobotOperator<T>) -> Result<Option<f64>> {
+        EndEffector::<T>::read(&mut self.0, operator)
+    }
+}
+
+/// An electromagnet switched by a basic output pin, active high.
+#[derive(Clone, Debug)]
+pub struct Electromagnet(pub BasicOutputTool);
+
+impl Electromagnet {
+    pub fn new(pin: u8) -> Electromagnet {
+        Electromagnet(BasicOutputTool::new(pin, false))
+    }
+}
+
+impl<T: Connection> EndEffector<T> for Electromagnet {
+    fn actuate(&mut self, operator: &mut MyCobotOperator<T>, value: f64) -> Result<()> {
+        self.0.actuate(operator, value)
+    }
+    fn read(&mut self, operator: &mut MyCobotOperator<T>) -> Result<Option<f64>> {
+        EndEffector::<T>::read(&mut self.0, operator)
+    }
+}
@@ -0,0 +1,42 @@
+/// Categories reported by the error-information genre. The firmware only
+/// reports limit, collision and kinematics errors there (codes as documented
+/// for pymycobot's `get_error_information`); it has no over-temperature or
+/// communication codes. Read temperatures with `get_servo_temperatures`, and
+/// expect link faults as I/O errors or empty replies instead.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RobotError {
+    /// Zero-based index of the joint that exceeded its limit.
+    JointOutOfLimit(usize),
+    /// Collision protection triggered.
+    Collision,
+    /// Target has no inverse kinematics solution.
+    NoSolution,
+    /// Linear motion has no adjacent solution.
+    LinearNoSolution,
+    Unknown(i32),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RobotErrorReport {
+    pub code: i32,
+    pub error: Option<RobotError>,
+}
+
+impl RobotErrorReport {
+    /// 0 is no error, 1-6 a joint over its limit, 16-19 collision, 32 no
+    /// IK solution and 33-34 no linear solution; other codes are `Unknown`.
+    pub fn from_code(code: i32) -> RobotErrorReport {
+        let error = match code {
+            0 => None,
+            1..=6 => Some(RobotError::JointOutOfLimit((code - 1) as usize)),
+            16..=19 => Some(RobotError::Collision),
+            32 => Some(RobotError::NoSolution),
+            33 | 34 => Some(RobotError::LinearNoSolution),
+            _ => Some(RobotError::Unknown(code)),
+        };
+        RobotErrorReport { code, error }
+    }
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none()
+    }
+}
@@ -0,0 +1,100 @@
+use super::common::Command;
+use super::io::Connection;
+use super::operator::MyCobotOperator;
+use super::watchdog::WatchdogPolicy;
+use anyhow::Result;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EstopEvent {
+    Triggered,
+    Reset,
+}
+
+/// Returned (inside `anyhow::Error`) for motion commands sent while the
+/// emergency stop is latched.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EstopLatched {
+    pub command: Command,
+}
+
+impl std::fmt::Display for EstopLatched {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} refused: emergency stop is latched, see reset_estop",
+            self.command
+        )
+    }
+}
+
+impl std::error::Error for EstopLatched {}
+
+type EstopCallback = Box<dyn FnMut(EstopEvent) + Send>;
+
+/// Latch and observers behind `emergency_stop`.
+pub(crate) struct EmergencyStop {
+    pub(crate) latched: bool,
+    pub(crate) policy: WatchdogPolicy,
+    observers: Vec<EstopCallback>,
+}
+
+impl Default for EmergencyStop {
+    fn default() -> EmergencyStop {
+        EmergencyStop {
+            latched: false,
+            policy: WatchdogPolicy::Stop,
+            observers: Vec::new(),
+        }
+    }
+}
+
+impl EmergencyStop {
+    fn notify(&mut self, event: EstopEvent) {
+        for observer in self.observers.iter_mut() {
+            observer(event);
+        }
+    }
+}
+
+impl<T: Connection> MyCobotOperator<T> {
+    /// Stops the arm, applies the e-stop policy (holding torque by default)
+    /// and latches: motion commands then fail with `EstopLatched` until
+    /// `reset_estop`. Unlike `stop`, which allows the next move right away.
+    /// The latch is set and observers notified even when sending fails.
+    pub fn emergency_stop(&mut self) -> Result<()> {
+        self.end_batch();
+        self.estop.latched = true;
+        self.estop.notify(EstopEvent::Triggered);
+        let policy = self.estop.policy;
+        policy.apply(self)
+    }
+    /// Clears the latch; the arm stays where it stopped until commanded.
+    pub fn reset_estop(&mut self) {
+        if self.estop.latched {
+            self.estop.latched = false;
+            self.estop.notify(EstopEvent::Reset);
+        }
+    }
+    pub fn is_estopped(&self) -> bool {
+        self.estop.latched
+    }
+    pub fn estop_policy(&self) -> WatchdogPolicy {
+        self.estop.policy
+    }
+    pub fn set_estop_policy(&mut self, policy: WatchdogPolicy) {
+        self.estop.policy = policy;
+    }
+    /// Called on every trigger and reset.
+    pub fn on_estop<F>(&mut self, callback: F)
+    where
+        F: FnMut(EstopEvent) + Send + 'static,
+    {
+        self.estop.observers.push(Box::new(callback));
+    }
+    pub(crate) fn check_estop(&self, genre: Command) -> Result<()> {
+        if self.estop.latched && genre.starts_motion() {
+            return Err(EstopLatched { command: genre }.into());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,258 @@
+//! C ABI for embedding the crate in C, C++, LabVIEW or Unity. See
+//! `include/mycobot.h` for the matching declarations.
+//!
+//! Every function returns a `MYCOBOT_*` status code; on failure the message
+//! is available from `mycobot_last_error` on the same thread. Panics are
+//! caught at the boundary and reported as `MYCOBOT_PANIC`.
+
+use super::io::Serial;
+use super::operator::MyCobotOperator;
+use anyhow::Result;
+use std::cell::RefCell;
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+pub const MYCOBOT_OK: c_int = 0;
+pub const MYCOBOT_NULL_POINTER: c_int = -1;
+pub const MYCOBOT_INVALID_ARGUMENT: c_int = -2;
+pub const MYCOBOT_ERROR: c_int = -3;
+pub const MYCOBOT_NO_DATA: c_int = -4;
+pub const MYCOBOT_PANIC: c_int = -5;
+
+/// Opaque operator handle owned by the caller between open and close.
+pub struct MycobotHandle {
+    operator: MyCobotOperator<Serial>,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+fn set_last_error(message: String) {
+    LAST_ERROR.with(|e| *e.borrow_mut() = message);
+}
+
+fn null_argument(name: &str) -> c_int {
+    set_last_error(format!("null {}", name));
+    MYCOBOT_NULL_POINTER
+}
+
+fn guard<F: FnOnce() -> Result<c_int>>(f: F) -> c_int {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(status)) => status,
+        Ok(Err(e)) => {
+            set_last_error(format!("{:#}", e));
+            MYCOBOT_ERROR
+        }
+        Err(_) => {
+            set_last_error("panic in mycobot".to_string());
+            MYCOBOT_PANIC
+        }
+    }
+}
+
+unsafe fn with_operator<F>(handle: *mut MycobotHandle, f: F) -> c_int
+where
+    F: FnOnce(&mut MyCobotOperator<Serial>) -> Result<c_int>,
+{
+    match handle.as_mut() {
+        Some(handle) => guard(|| f(&mut handle.operator)),
+        None => {
+            set_last_error("null handle".to_string());
+            MYCOBOT_NULL_POINTER
+        }
+    }
+}
+
+/// Opens `port` at `baudrate` and stores a new handle in `*out`.
+///
+/// # Safety
+/// `port` must be a NUL-terminated string and `out` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn mycobot_open(
+    port: *const c_char,
+    baudrate: u32,
+    out: *mut *mut MycobotHandle,
+) -> c_int {
+    if port.is_null() {
+        return null_argument("port");
+    }
+    if out.is_null() {
+        return null_argument("out");
+    }
+    *out = ptr::null_mut();
+    let port = match CStr::from_ptr(port).to_str() {
+        Ok(port) => port,
+        Err(_) => {
+            set_last_error("port name is not UTF-8".to_string());
+            return MYCOBOT_INVALID_ARGUMENT;
+        }
+    };
+    guard(|| {
+        let operator = MyCobotOperator::from_connection(Serial::open(port, baudrate)?);
+        *out = Box::into_raw(Box::new(MycobotHandle { operator }));
+        Ok(MYCOBOT_OK)
+    })
+}
+
+/// Closes the port and frees the handle. Null is ignored.
+///
+/// # Safety
+/// `handle` must come from `mycobot_open` and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn mycobot_close(handle: *mut MycobotHandle) {
+    if !handle.is_null() {
+        drop(Box::from_raw(handle));
+    }
+}
+
+/// Copies the last error message of this thread into `buffer`, truncated and
+/// NUL-terminated. Returns the full message length in bytes.
+///
+/// # Safety
+/// `buffer` must be valid for `len` bytes, or null with `len` 0.
+#[no_mangle]
+pub unsafe extern "C" fn mycobot_last_error(buffer: *mut c_char, len: usize) -> c_int {
+    LAST_ERROR.with(|e| {
+        let message = e.borrow();
+        if !buffer.is_null() && len > 0 {
+            let n = message.len().min(len - 1);
+            ptr::copy_nonoverlapping(message.as_ptr() as *const c_char, buffer, n);
+            *buffer.add(n) = 0;
+        }
+        message.len() as c_int
+    })
+}
+
+/// # Safety
+/// `handle` must be a live handle from `mycobot_open`.
+#[no_mangle]
+pub unsafe extern "C" fn mycobot_power_on(handle: *mut MycobotHandle) -> c_int {
+    with_operator(handle, |op| op.power_on().map(|_| MYCOBOT_OK))
+}
+
+/// # Safety
+/// `handle` must be a live handle from `mycobot_open`.
+#[no_mangle]
+pub unsafe extern "C" fn mycobot_power_off(handle: *mut MycobotHandle) -> c_int {
+    with_operator(handle, |op| op.power_off().map(|_| MYCOBOT_OK))
+}
+
+/// # Safety
+/// `handle` must be a live handle from `mycobot_open`.
+#[no_mangle]
+pub unsafe extern "C" fn mycobot_release_all_servos(handle: *mut MycobotHandle) -> c_int {
+    with_operator(handle, |op| op.release_all_servos().map(|_| MYCOBOT_OK))
+}
+
+/// # Safety
+/// `handle` must be a live handle from `mycobot_open`.
+#[no_mangle]
+pub unsafe extern "C" fn mycobot_stop(handle: *mut MycobotHandle) -> c_int {
+    with_operator(handle, |op| op.stop().map(|_| MYCOBOT_OK))
+}
+
+/// Sends six joint angles in degrees.
+///
+/// # Safety
+/// `handle` must be live and `degrees` point to six doubles.
+#[no_mangle]
+pub unsafe extern "C" fn mycobot_send_angles(
+    handle: *mut MycobotHandle,
+    degrees: *const f64,
+    speed: u8,
+) -> c_int {
+    if degrees.is_null() {
+        return null_argument("degrees");
+    }
+    let degrees = *(degrees as *const [f64; 6]);
+    with_operator(handle, |op| {
+        op.send_angles(&degrees, speed).map(|_| MYCOBOT_OK)
+    })
+}
+
+/// Writes six joint angles in degrees to `degrees`.
+///
+/// # Safety
+/// `handle` must be live and `degrees` point to room for six doubles.
+#[no_mangle]
+pub unsafe extern "C" fn mycobot_get_angles(
+    handle: *mut MycobotHandle,
+    degrees: *mut f64,
+) -> c_int {
+    if degrees.is_null() {
+        return null_argument("degrees");
+    }
+    with_operator(handle, |op| {
+        *(degrees as *mut [f64; 6]) = op.get_angles()?;
+        Ok(MYCOBOT_OK)
+    })
+}
+
+/// Sends coordinates (x, y, z in mm, rx, ry, rz in degrees); `mode` is 0
+/// normal, 1 angular or 2 linear.
+///
+/// # Safety
+/// `handle` must be live and `coords` point to six doubles.
+#[no_mangle]
+pub unsafe extern "C" fn mycobot_send_coords(
+    handle: *mut MycobotHandle,
+    coords: *const f64,
+    speed: u8,
+    mode: c_int,
+) -> c_int {
+    if coords.is_null() {
+        return null_argument("coords");
+    }
+    let mode = match mode {
+        0 => super::common::Mode::Normal,
+        1 => super::common::Mode::Angular,
+        2 => super::common::Mode::Linear,
+        _ => {
+            set_last_error(format!("invalid mode {}", mode));
+            return MYCOBOT_INVALID_ARGUMENT;
+        }
+    };
+    let coords = *(coords as *const [f64; 6]);
+    with_operator(handle, |op| {
+        op.send_coords(&coords, speed, mode).map(|_| MYCOBOT_OK)
+    })
+}
+
+/// Writes the current coordinates to `coords`.
+///
+/// # Safety
+/// `handle` must be live and `coords` point to room for six doubles.
+#[no_mangle]
+pub unsafe extern "C" fn mycobot_get_coords(handle: *mut MycobotHandle, coords: *mut f64) -> c_int {
+    if coords.is_null() {
+        return null_argument("coords");
+    }
+    with_operator(handle, |op| {
+        *(coords as *mut [f64; 6]) = op.get_coords()?;
+        Ok(MYCOBOT_OK)
+    })
+}
+
+/// Stores 1 in `*moving` while the arm moves, 0 otherwise.
+///
+/// # Safety
+/// `handle` must be live and `moving` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn mycobot_is_moving(
+    handle: *mut MycobotHandle,
+    moving: *mut c_int,
+) -> c_int {
+    if moving.is_null() {
+        return null_argument("moving");
+    }
+    with_operator(handle, |op| match op.is_moving()? {
+        -1 => Ok(MYCOBOT_NO_DATA),
+        value => {
+            *moving = value;
+            Ok(MYCOBOT_OK)
+        }
+    })
+}
@@ -0,0 +1,133 @@
+use super::angles::wrap_degrees;
+use super::io::Connection;
+use super::operator::MyCobotOperator;
+use anyhow::Result;
+use std::thread;
+use std::time::Duration;
+
+/// How `get_angles_filtered` and `get_coords_filtered` combine readings.
+#[derive(Clone, Debug)]
+pub struct FilterPolicy {
+    /// Number of readings taken.
+    pub samples: usize,
+    /// A reading is rejected when any angle is further than this from the
+    /// per-axis median, in degrees.
+    pub angle_threshold: f64,
+    /// Same for the x, y, z positions of coordinates, in millimetres.
+    pub position_threshold: f64,
+    /// Fewest accepted readings needed for a result.
+    pub min_inliers: usize,
+    /// Pause between readings.
+    pub interval: Duration,
+}
+
+impl Default for FilterPolicy {
+    fn default() -> FilterPolicy {
+        FilterPolicy {
+            samples: 5,
+            angle_threshold: 2.0,
+            position_threshold: 5.0,
+            min_inliers: 3,
+            interval: Duration::from_millis(5),
+        }
+    }
+}
+
+fn median(values: &mut [f64]) -> f64 {
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let n = values.len();
+    if n % 2 == 1 {
+        values[n / 2]
+    } else {
+        (values[n / 2 - 1] + values[n / 2]) / 2.0
+    }
+}
+
+/// Per-axis median of the readings within threshold of the median of all readings.
+/// Axes flagged in `wrap` are rotations compared across the ±180° seam.
+fn filter(
+    readings: &[[f64; 6]],
+    thresholds: &[f64; 6],
+    wrap: &[bool; 6],
+    min_inliers: usize,
+) -> Result<[f64; 6]> {
+    if readings.is_empty() {
+        return Err(anyhow::anyhow!("No readings received"));
+    }
+    // Unwrap rotations relative to the first reading so medians don't straddle the seam.
+    let unwrapped: Vec<[f64; 6]> = readings
+        .iter()
+        .map(|r| {
+            let mut u = *r;
+            for i in 0..6 {
+                if wrap[i] {
+                    u[i] = readings[0][i] + wrap_degrees(r[i] - readings[0][i]);
+                }
+            }
+            u
+        })
+        .collect();
+    let axis_median = |set: &[&[f64; 6]], i: usize| {
+        let mut values: Vec<f64> = set.iter().map(|r| r[i]).collect();
+        median(&mut values)
+    };
+    let all: Vec<&[f64; 6]> = unwrapped.iter().collect();
+    let center: Vec<f64> = (0..6).map(|i| axis_median(&all, i)).collect();
+    let inliers: Vec<&[f64; 6]> = unwrapped
+        .iter()
+        .filter(|r| (0..6).all(|i| (r[i] - center[i]).abs() <= thresholds[i]))
+        .collect();
+    if inliers.len() < min_inliers.max(1) {
+        return Err(anyhow::anyhow!(
+            "Only {} of {} readings agree",
+            inliers.len(),
+            readings.len()
+        ));
+    }
+    let mut out = [0.0; 6];
+    for (i, o) in out.iter_mut().enumerate() {
+        let m = axis_median(&inliers, i);
+        *o = if wrap[i] { wrap_degrees(m) } else { m };
+    }
+    Ok(out)
+}
+
+impl<T: Connection> MyCobotOperator<T> {
+    fn read_samples<F>(&mut self, policy: &FilterPolicy, mut read: F) -> Vec<[f64; 6]>
+    where
+        F: FnMut(&mut MyCobotOperator<T>) -> Result<[f64; 6]>,
+    {
+        let mut readings = Vec::with_capacity(policy.samples);
+        for i in 0..policy.samples {
+            if i > 0 {
+                thread::sleep(policy.interval);
+            }
+            // Garbled frames fail to decode; they count as rejected readings.
+            if let Ok(reading) = read(self) {
+                readings.push(reading);
+            }
+        }
+        readings
+    }
+    /// Median of several `get_angles` readings with outliers rejected per `policy`.
+    pub fn get_angles_filtered(&mut self, policy: &FilterPolicy) -> Result<[f64; 6]> {
+        let readings = self.read_samples(policy, |op| op.get_angles());
+        filter(
+            &readings,
+            &[policy.angle_threshold; 6],
+            &[false; 6],
+            policy.min_inliers,
+        )
+    }
+    /// Median of several `get_coords` readings with outliers rejected per `policy`.
+    pub fn get_coords_filtered(&mut self, policy: &FilterPolicy) -> Result<[f64; 6]> {
+        let readings = self.read_samples(policy, |op| op.get_coords());
+        let (p, a) = (policy.position_threshold, policy.angle_threshold);
+        filter(
+            &readings,
+            &[p, p, p, a, a, a],
+            &[false, false, false, true, true, true],
+            policy.min_inliers,
+        )
+    }
+}
@@ -1,31 +1,528 @@
+use super::common::{Command, CommandClass};
+use super::tap::{Frame, Tap};
 use anyhow::Result;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::sync::mpsc::Receiver;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 pub trait Connection {
     fn write(&mut self, command: &[u8]) -> Result<()>;
     fn read(&mut self) -> Result<Vec<u8>>;
     fn write_and_read(&mut self, command: &[u8]) -> Result<Vec<u8>>;
+    fn is_healthy(&mut self) -> bool {
+        true
+    }
+    /// Changes the timeout that ends each reply. Connections without one ignore it.
+    fn set_read_timeout(&mut self, _timeout: Duration) -> Result<()> {
+        Ok(())
+    }
+    /// Discards bytes received but not yet read, such as a late reply to an
+    /// earlier request, so they aren't parsed as the answer to the next one.
+    fn clear_input_buffer(&mut self) -> Result<()> {
+        Ok(())
+    }
+    /// Blocks until every written byte has been handed to the device.
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+    /// Wraps the connection so every frame sent or received is also delivered on the returned channel.
+    fn tap(self) -> (Tap<Self>, Receiver<Frame>)
+    where
+        Self: Sized,
+    {
+        Tap::new(self)
+    }
+}
+
+impl<C: Connection + ?Sized> Connection for Box<C> {
+    fn write(&mut self, command: &[u8]) -> Result<()> {
+        (**self).write(command)
+    }
+    fn read(&mut self) -> Result<Vec<u8>> {
+        (**self).read()
+    }
+    fn write_and_read(&mut self, command: &[u8]) -> Result<Vec<u8>> {
+        (**self).write_and_read(command)
+    }
+    fn is_healthy(&mut self) -> bool {
+        (**self).is_healthy()
+    }
+    fn set_read_timeout(&mut self, timeout: Duration) -> Result<()> {
+        (**self).set_read_timeout(timeout)
+    }
+    fn clear_input_buffer(&mut self) -> Result<()> {
+        (**self).clear_input_buffer()
+    }
+    fn flush(&mut self) -> Result<()> {
+        (**self).flush()
+    }
 }
 
 pub struct Serial {
     port: Box<dyn serialport::SerialPort>,
+    throttle: Option<Throttle>,
+    reply_settle: Duration,
+    _lock: Option<PortLock>,
+}
+
+/// Minimum spacing between written frames; the firmware drops commands that
+/// arrive too close together, and needs longer after a motion command.
+#[derive(Clone, Debug)]
+pub struct Throttle {
+    pub min_interval: Duration,
+    /// Spacing after a genre for which `Command::starts_motion` holds.
+    pub motion_interval: Duration,
+    last: Option<(Instant, bool)>,
+}
+
+impl Default for Throttle {
+    fn default() -> Throttle {
+        Throttle::new(Duration::from_millis(5), Duration::from_millis(20))
+    }
+}
+
+impl Throttle {
+    pub fn new(min_interval: Duration, motion_interval: Duration) -> Throttle {
+        Throttle {
+            min_interval,
+            motion_interval,
+            last: None,
+        }
+    }
+    /// Sleeps until `frame` may be written, then records it as written.
+    pub fn wait(&mut self, frame: &[u8]) {
+        if let Some((at, motion)) = self.last {
+            let interval = if motion {
+                self.motion_interval
+            } else {
+                self.min_interval
+            };
+            let elapsed = at.elapsed();
+            if elapsed < interval {
+                thread::sleep(interval - elapsed);
+            }
+        }
+        let motion = frame
+            .get(3)
+            .copied()
+            .and_then(Command::from_u8)
+            .is_some_and(Command::starts_motion);
+        self.last = Some((Instant::now(), motion));
+    }
+}
+
+pub use serialport::{DataBits, FlowControl, Parity, SerialPortInfo, StopBits};
+
+/// Serial ports reported by the OS (SetupAPI on Windows, udev on Linux).
+pub fn available_ports() -> Result<Vec<SerialPortInfo>> {
+    Ok(serialport::available_ports()?)
+}
+
+/// Windows only opens `COM10` and above through the device namespace, so
+/// bare `COMn` names are rewritten to `\\.\COMn`. Other names are unchanged.
+pub fn normalize_port_name(port: &str) -> String {
+    let is_com = port.len() > 3
+        && port.get(..3).is_some_and(|p| p.eq_ignore_ascii_case("com"))
+        && port[3..].chars().all(|c| c.is_ascii_digit());
+    if cfg!(windows) && is_com {
+        format!("\\\\.\\{}", port.to_ascii_uppercase())
+    } else {
+        port.to_string()
+    }
+}
+
+/// Returned (inside `anyhow::Error`) when another process holds the port,
+/// either through the OS exclusive open or a lock file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PortInUse {
+    pub port: String,
+    /// Owner recorded in the lock file, when known.
+    pub pid: Option<u32>,
+    /// The lock file names a process that no longer exists.
+    pub stale: bool,
+}
+
+impl std::fmt::Display for PortInUse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Port {} is in use by another process", self.port)?;
+        if let Some(pid) = self.pid {
+            write!(f, " (pid {})", pid)?;
+        }
+        if self.stale {
+            write!(
+                f,
+                "; the lock is stale, see SerialConfig::take_over_stale_lock"
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for PortInUse {}
+
+/// UUCP-style lock file (`/var/lock/LCK..ttyUSB0`) removed when the last
+/// `Serial` of this process holding it is dropped.
+struct PortLock {
+    path: std::path::PathBuf,
+}
+
+/// Lock files this process holds, with how many open ports share each.
+fn held_locks() -> &'static std::sync::Mutex<HashMap<std::path::PathBuf, usize>> {
+    static HELD: std::sync::OnceLock<std::sync::Mutex<HashMap<std::path::PathBuf, usize>>> =
+        std::sync::OnceLock::new();
+    HELD.get_or_init(Default::default)
+}
+
+impl PortLock {
+    const DIR: &'static str = "/var/lock";
+
+    /// Lock file name for `port`, built from the whole device path so that
+    /// `/dev/pts/0` and `/dev/0` don't collide: `/dev/ttyUSB0` gives
+    /// `LCK..ttyUSB0` and `/dev/pts/0` gives `LCK..pts_0`.
+    fn file_name(port: &str) -> Option<String> {
+        let name = port.strip_prefix("/dev/").unwrap_or(port);
+        let name = name.trim_start_matches('/').replace('/', "_");
+        if name.is_empty() {
+            None
+        } else {
+            Some(format!("LCK..{}", name))
+        }
+    }
+
+    /// Takes the lock for `port`. Returns `Ok(None)` when lock files are not
+    /// usable here (non-Linux, or the lock directory is missing or read-only).
+    /// A lock already held by this process is shared rather than refused.
+    fn acquire(port: &str, take_over_stale: bool) -> Result<Option<PortLock>> {
+        if !cfg!(target_os = "linux") {
+            return Ok(None);
+        }
+        let name = match PortLock::file_name(port) {
+            Some(name) => name,
+            None => return Ok(None),
+        };
+        let path = std::path::Path::new(PortLock::DIR).join(name);
+        let mut held = held_locks().lock().unwrap_or_else(|e| e.into_inner());
+        for _ in 0..2 {
+            match Self::create(&path) {
+                Ok(()) => {
+                    held.insert(path.clone(), 1);
+                    return Ok(Some(PortLock { path }));
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+                Err(_) => return Ok(None),
+            }
+            let pid = std::fs::read_to_string(&path)
+                .ok()
+                .and_then(|s| s.trim().parse::<u32>().ok());
+            if pid == Some(std::process::id()) {
+                *held.entry(path.clone()).or_insert(0) += 1;
+                return Ok(Some(PortLock { path }));
+            }
+            let stale =
+                pid.is_some_and(|pid| !std::path::Path::new(&format!("/proc/{}", pid)).exists());
+            if !(stale && take_over_stale) {
+                return Err(PortInUse {
+                    port: port.to_string(),
+                    pid,
+                    stale,
+                }
+                .into());
+            }
+            std::fs::remove_file(&path)?;
+        }
+        Err(anyhow::anyhow!(
+            "Cannot take over the lock file {}",
+            path.display()
+        ))
+    }
+    fn create(path: &std::path::Path) -> std::io::Result<()> {
+        use std::io::Write;
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)?;
+        writeln!(file, "{:>10}", std::process::id())
+    }
+}
+
+impl Drop for PortLock {
+    fn drop(&mut self) {
+        let mut held = held_locks().lock().unwrap_or_else(|e| e.into_inner());
+        let remaining = match held.get_mut(&self.path) {
+            Some(count) => {
+                *count = count.saturating_sub(1);
+                *count
+            }
+            None => 0,
+        };
+        if remaining == 0 {
+            held.remove(&self.path);
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+fn open_error(port: &str, err: serialport::Error) -> anyhow::Error {
+    match err.kind() {
+        // On Unix serialport reports EBUSY from the exclusive open, and a
+        // held flock, as NoDevice. On Windows NoDevice covers missing ports.
+        serialport::ErrorKind::NoDevice if cfg!(unix) => PortInUse {
+            port: port.to_string(),
+            pid: None,
+            stale: false,
+        }
+        .into(),
+        serialport::ErrorKind::Io(std::io::ErrorKind::PermissionDenied) => {
+            anyhow::anyhow!("Cannot open {}: access was denied ({})", port, err)
+        }
+        serialport::ErrorKind::NoDevice
+        | serialport::ErrorKind::Io(std::io::ErrorKind::NotFound) => {
+            let ports = available_ports()
+                .map(|ports| {
+                    ports
+                        .into_iter()
+                        .map(|p| p.port_name)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                })
+                .unwrap_or_default();
+            anyhow::anyhow!("Port {} not found; available ports: [{}]", port, ports)
+        }
+        _ => anyhow::anyhow!("Cannot open {}: {}", port, err),
+    }
+}
+
+/// Baud rates supported by myCobot firmware and common USB-serial bridges.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BaudRate {
+    B9600,
+    B19200,
+    B38400,
+    B57600,
+    B115200,
+    B230400,
+    B460800,
+    B921600,
+    B1000000,
+}
+
+impl BaudRate {
+    pub const ALL: &'static [BaudRate] = &[
+        BaudRate::B9600,
+        BaudRate::B19200,
+        BaudRate::B38400,
+        BaudRate::B57600,
+        BaudRate::B115200,
+        BaudRate::B230400,
+        BaudRate::B460800,
+        BaudRate::B921600,
+        BaudRate::B1000000,
+    ];
+
+    pub fn as_u32(self) -> u32 {
+        match self {
+            BaudRate::B9600 => 9600,
+            BaudRate::B19200 => 19200,
+            BaudRate::B38400 => 38400,
+            BaudRate::B57600 => 57600,
+            BaudRate::B115200 => 115200,
+            BaudRate::B230400 => 230400,
+            BaudRate::B460800 => 460800,
+            BaudRate::B921600 => 921600,
+            BaudRate::B1000000 => 1000000,
+        }
+    }
+}
+
+impl TryFrom<u32> for BaudRate {
+    type Error = anyhow::Error;
+    fn try_from(baud: u32) -> Result<BaudRate> {
+        BaudRate::ALL
+            .iter()
+            .copied()
+            .find(|b| b.as_u32() == baud)
+            .ok_or_else(|| anyhow::anyhow!("Unsupported baud rate: {}", baud))
+    }
+}
+
+/// Serial port settings; defaults match the myCobot USB connection (115200 8N1, no flow control).
+#[derive(Clone, Debug)]
+pub struct SerialConfig {
+    port: String,
+    baud: u32,
+    data_bits: DataBits,
+    parity: Parity,
+    stop_bits: StopBits,
+    flow_control: FlowControl,
+    timeout: Duration,
+    throttle: Option<Throttle>,
+    reply_settle: Duration,
+    lock_file: bool,
+    take_over_stale_lock: bool,
+}
+
+impl SerialConfig {
+    pub fn new(port: &str) -> SerialConfig {
+        SerialConfig {
+            port: port.to_string(),
+            baud: BaudRate::B115200.as_u32(),
+            data_bits: DataBits::Eight,
+            parity: Parity::None,
+            stop_bits: StopBits::One,
+            flow_control: FlowControl::None,
+            timeout: Duration::from_millis(10),
+            throttle: Some(Throttle::default()),
+            reply_settle: Duration::from_millis(5),
+            lock_file: true,
+            take_over_stale_lock: true,
+        }
+    }
+    pub fn baud_rate(mut self, baud_rate: BaudRate) -> SerialConfig {
+        self.baud = baud_rate.as_u32();
+        self
+    }
+    pub fn data_bits(mut self, data_bits: DataBits) -> SerialConfig {
+        self.data_bits = data_bits;
+        self
+    }
+    pub fn parity(mut self, parity: Parity) -> SerialConfig {
+        self.parity = parity;
+        self
+    }
+    pub fn stop_bits(mut self, stop_bits: StopBits) -> SerialConfig {
+        self.stop_bits = stop_bits;
+        self
+    }
+    pub fn flow_control(mut self, flow_control: FlowControl) -> SerialConfig {
+        self.flow_control = flow_control;
+        self
+    }
+    /// Read timeout that ends each reply.
+    pub fn timeout(mut self, timeout: Duration) -> SerialConfig {
+        self.timeout = timeout;
+        self
+    }
+    /// Spacing enforced between written frames; `None` writes them back to
+    /// back, for callers that pace frames themselves.
+    pub fn throttle(mut self, throttle: Option<Throttle>) -> SerialConfig {
+        self.throttle = throttle;
+        self
+    }
+    /// Pause between writing a request and starting to read its reply, so
+    /// the first reply byte only has to arrive within settle plus timeout.
+    /// `Throttle` only spaces writes and doesn't cover this.
+    pub fn reply_settle(mut self, settle: Duration) -> SerialConfig {
+        self.reply_settle = settle;
+        self
+    }
+    /// On Linux, also take a `/var/lock` lock file so other lock-aware
+    /// programs see the port as busy. Enabled by default; skipped silently
+    /// when the lock directory is not writable.
+    pub fn lock_file(mut self, lock_file: bool) -> SerialConfig {
+        self.lock_file = lock_file;
+        self
+    }
+    /// Replace a lock file whose owning process has exited, as left behind
+    /// by a killed run, instead of failing with `PortInUse`. Enabled by default.
+    pub fn take_over_stale_lock(mut self, take_over: bool) -> SerialConfig {
+        self.take_over_stale_lock = take_over;
+        self
+    }
+    /// Opens the port exclusively. Fails with `PortInUse` when another process
+    /// already holds it.
+    pub fn open(&self) -> Result<Serial> {
+        let lock = if self.lock_file {
+            PortLock::acquire(&self.port, self.take_over_stale_lock)?
+        } else {
+            None
+        };
+        let port = serialport::new(normalize_port_name(&self.port), self.baud)
+            .data_bits(self.data_bits)
+            .parity(self.parity)
+            .stop_bits(self.stop_bits)
+            .flow_control(self.flow_control)
+            .timeout(self.timeout)
+            .open()
+            .map_err(|e| open_error(&self.port, e))?;
+        Ok(Serial {
+            port,
+            throttle: self.throttle.clone(),
+            reply_settle: self.reply_settle,
+            _lock: lock,
+        })
+    }
+}
+
+/// Read timeouts chosen per genre, falling back to the genre's class and then
+/// to `default`. Applied by the operator before each query.
+#[derive(Clone, Debug)]
+pub struct ReadTimeouts {
+    default: Duration,
+    classes: HashMap<CommandClass, Duration>,
+    genres: HashMap<Command, Duration>,
+}
+
+impl ReadTimeouts {
+    pub fn new(default: Duration) -> ReadTimeouts {
+        ReadTimeouts {
+            default,
+            classes: HashMap::new(),
+            genres: HashMap::new(),
+        }
+    }
+    pub fn class(mut self, class: CommandClass, timeout: Duration) -> ReadTimeouts {
+        self.classes.insert(class, timeout);
+        self
+    }
+    pub fn genre(mut self, genre: Command, timeout: Duration) -> ReadTimeouts {
+        self.genres.insert(genre, timeout);
+        self
+    }
+    pub fn timeout_for(&self, genre: Command) -> Duration {
+        self.genres
+            .get(&genre)
+            .or_else(|| self.classes.get(&genre.class()))
+            .copied()
+            .unwrap_or(self.default)
+    }
+}
+
+impl Default for ReadTimeouts {
+    /// The `SerialConfig` default for every genre.
+    fn default() -> ReadTimeouts {
+        ReadTimeouts::new(Duration::from_millis(10))
+    }
 }
 
 impl Serial {
+    pub(crate) fn port_mut(&mut self) -> &mut dyn serialport::SerialPort {
+        self.port.as_mut()
+    }
+    pub fn set_throttle(&mut self, throttle: Option<Throttle>) {
+        self.throttle = throttle;
+    }
     pub fn new(port: &str, baudrate: u32) -> Serial {
-        let port = serialport::new(port, baudrate)
-            .timeout(Duration::from_millis(10))
-            .open()
-            .expect("Failed to open port.");
-        Serial { port }
+        Serial::open(port, baudrate).expect("Failed to open port.")
+    }
+    /// Opens with default settings; `baudrate` is passed through unvalidated.
+    /// Use `SerialConfig` for other parameters.
+    pub fn open(port: &str, baudrate: u32) -> Result<Serial> {
+        let config = SerialConfig {
+            baud: baudrate,
+            ..SerialConfig::new(port)
+        };
+        config.open()
     }
 }
 
 impl Connection for Serial {
     fn write(&mut self, command: &[u8]) -> Result<()> {
+        if let Some(throttle) = &mut self.throttle {
+            throttle.wait(command);
+        }
         self.port.write_all(command)?;
-        thread::sleep(Duration::from_millis(5));
         Ok(())
     }
     fn read(&mut self) -> Result<Vec<u8>> {
@@ -41,7 +538,22 @@ impl Connection for Serial {
     }
     fn write_and_read(&mut self, command: &[u8]) -> Result<Vec<u8>> {
         self.write(command)?;
+        if !self.reply_settle.is_zero() {
+            thread::sleep(self.reply_settle);
+        }
         let res = self.read()?;
         Ok(res)
     }
+    fn is_healthy(&mut self) -> bool {
+        self.port.bytes_to_read().is_ok()
+    }
+    fn set_read_timeout(&mut self, timeout: Duration) -> Result<()> {
+        Ok(self.port.set_timeout(timeout)?)
+    }
+    fn clear_input_buffer(&mut self) -> Result<()> {
+        Ok(self.port.clear(serialport::ClearBuffer::Input)?)
+    }
+    fn flush(&mut self) -> Result<()> {
+        Ok(self.port.flush()?)
+    }
 }
@@ -0,0 +1,269 @@
+use super::common::{Angle, Coord, Direction};
+use super::io::Connection;
+use super::kinematics::{damped_least_squares, KinematicModel};
+use super::operator::MyCobotOperator;
+use super::servoing::{JointStreamConfig, JointVelocityStreamer, Twist};
+use super::transform::{add, cross, mat_vec, sub, Pose};
+use anyhow::Result;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Software speed ramp for jogging, applied by re-issuing jog commands.
+#[derive(Clone, Debug)]
+pub struct JogProfile {
+    /// Time to go from `min_speed` to the requested speed.
+    pub ramp_up: Duration,
+    /// Time to go from the current speed back down to a stop.
+    pub ramp_down: Duration,
+    /// Interval between re-issued jog commands while ramping.
+    pub interval: Duration,
+    /// Speed issued at the very start and end of a ramp.
+    pub min_speed: u8,
+}
+
+impl Default for JogProfile {
+    fn default() -> JogProfile {
+        JogProfile {
+            ramp_up: Duration::from_millis(500),
+            ramp_down: Duration::from_millis(300),
+            interval: Duration::from_millis(50),
+            min_speed: 5,
+        }
+    }
+}
+
+impl JogProfile {
+    /// Speed `elapsed` into the ramp-up towards `target`.
+    pub fn ramp_up_speed(&self, elapsed: Duration, target: u8) -> u8 {
+        self.ramp(elapsed, self.ramp_up, self.min_speed, target)
+    }
+    /// Speed `elapsed` into the ramp-down from `from`, or `None` once stopped.
+    pub fn ramp_down_speed(&self, elapsed: Duration, from: u8) -> Option<u8> {
+        if elapsed >= self.ramp_down {
+            return None;
+        }
+        Some(self.ramp(elapsed, self.ramp_down, from, self.min_speed))
+    }
+    fn ramp(&self, elapsed: Duration, span: Duration, from: u8, to: u8) -> u8 {
+        if span.is_zero() || elapsed >= span {
+            return to;
+        }
+        let s = elapsed.as_secs_f64() / span.as_secs_f64();
+        (from as f64 + (to as f64 - from as f64) * s).round() as u8
+    }
+}
+
+/// Axis and direction of a jog.
+#[derive(Clone)]
+pub enum JogTarget {
+    Angle(Angle, Direction),
+    Coord(Coord, Direction),
+}
+
+enum Phase {
+    Idle,
+    Up { since: Instant, target: u8 },
+    Down { since: Instant, from: u8 },
+}
+
+/// Non-blocking ramped jog for teleoperation loops: `start` and `stop` set the
+/// goal and `update` re-issues the jog command at the profile interval.
+pub struct RampedJog {
+    profile: JogProfile,
+    target: Option<JogTarget>,
+    phase: Phase,
+    speed: u8,
+    last_sent: Option<Instant>,
+}
+
+impl RampedJog {
+    pub fn new(profile: JogProfile) -> RampedJog {
+        RampedJog {
+            profile,
+            target: None,
+            phase: Phase::Idle,
+            speed: 0,
+            last_sent: None,
+        }
+    }
+    pub fn profile(&self) -> &JogProfile {
+        &self.profile
+    }
+    /// Begins ramping up towards `speed` on `target`.
+    pub fn start(&mut self, target: JogTarget, speed: u8) {
+        self.target = Some(target);
+        self.phase = Phase::Up {
+            since: Instant::now(),
+            target: speed,
+        };
+        self.last_sent = None;
+    }
+    /// Begins ramping down to a stop.
+    pub fn stop(&mut self) {
+        if let Phase::Up { .. } = self.phase {
+            if self.last_sent.is_none() && self.speed == 0 {
+                self.phase = Phase::Idle;
+                return;
+            }
+            self.phase = Phase::Down {
+                since: Instant::now(),
+                from: self.speed,
+            };
+            self.last_sent = None;
+        }
+    }
+    pub fn is_active(&self) -> bool {
+        !matches!(self.phase, Phase::Idle)
+    }
+    /// Speed of the last issued jog command.
+    pub fn speed(&self) -> u8 {
+        self.speed
+    }
+    /// Issues the jog command when due; returns whether the jog is still active.
+    pub fn update<T: Connection>(&mut self, operator: &mut MyCobotOperator<T>) -> Result<bool> {
+        let now = Instant::now();
+        let due = self
+            .last_sent
+            .is_none_or(|sent| now.duration_since(sent) >= self.profile.interval);
+        let speed = match self.phase {
+            Phase::Idle => return Ok(false),
+            Phase::Up { since, target } => {
+                let speed = self
+                    .profile
+                    .ramp_up_speed(now.duration_since(since), target);
+                // Once at full speed the firmware keeps jogging without re-issues.
+                if !due || (speed == self.speed && self.last_sent.is_some()) {
+                    return Ok(true);
+                }
+                speed
+            }
+            Phase::Down { since, from } => {
+                match self
+                    .profile
+                    .ramp_down_speed(now.duration_since(since), from)
+                {
+                    Some(speed) if due => speed,
+                    Some(_) => return Ok(true),
+                    None => {
+                        self.phase = Phase::Idle;
+                        self.speed = 0;
+                        operator.jog_stop()?;
+                        return Ok(false);
+                    }
+                }
+            }
+        };
+        match self.target.clone() {
+            Some(JogTarget::Angle(id, direction)) => operator.jog_angle(id, direction, speed)?,
+            Some(JogTarget::Coord(id, direction)) => operator.jog_coord(id, direction, speed)?,
+            None => return Ok(false),
+        }
+        self.speed = speed;
+        self.last_sent = Some(now);
+        Ok(true)
+    }
+    fn run_until<T: Connection>(
+        &mut self,
+        operator: &mut MyCobotOperator<T>,
+        until: Option<Instant>,
+    ) -> Result<()> {
+        loop {
+            if until.is_some_and(|until| Instant::now() >= until) {
+                self.stop();
+                return self.run_until(operator, None);
+            }
+            if !self.update(operator)? {
+                return Ok(());
+            }
+            thread::sleep(self.profile.interval / 4);
+        }
+    }
+}
+
+impl<T: Connection> MyCobotOperator<T> {
+    /// Jogs a joint for `duration` (including the ramp-up), ramping the speed
+    /// up and down with `profile` instead of starting at full speed.
+    pub fn jog_angle_ramped(
+        &mut self,
+        id: Angle,
+        direction: Direction,
+        speed: u8,
+        duration: Duration,
+        profile: &JogProfile,
+    ) -> Result<()> {
+        let mut jog = RampedJog::new(profile.clone());
+        jog.start(JogTarget::Angle(id, direction), speed);
+        jog.run_until(self, Some(Instant::now() + duration))
+    }
+    /// Coordinate counterpart of `jog_angle_ramped`.
+    pub fn jog_coord_ramped(
+        &mut self,
+        id: Coord,
+        direction: Direction,
+        speed: u8,
+        duration: Duration,
+        profile: &JogProfile,
+    ) -> Result<()> {
+        let mut jog = RampedJog::new(profile.clone());
+        jog.start(JogTarget::Coord(id, direction), speed);
+        jog.run_until(self, Some(Instant::now() + duration))
+    }
+}
+
+/// Damping of the least-squares joint velocity solve, keeping rates bounded near singularities.
+const TOOL_JOG_DAMPING: f64 = 0.05;
+
+/// Joint velocities in deg/s moving the tool center point with `twist`,
+/// given in the tool's own frame, from `angles`. `tool` is the tool pose in
+/// the flange frame, `None` for the flange itself.
+pub fn tool_twist_to_joint_velocities(
+    model: &KinematicModel,
+    angles: &[f64; 6],
+    tool: Option<&Pose>,
+    twist: &Twist,
+) -> Option<[f64; 6]> {
+    let flange = model.forward(angles);
+    let tcp = match tool {
+        Some(tool) => flange.compose(tool),
+        None => flange,
+    };
+    let linear = mat_vec(&tcp.rotation, &twist.linear);
+    let angular = mat_vec(&tcp.rotation, &twist.angular.map(f64::to_radians));
+    // The flange moves with the tool point minus the lever arm of the rotation.
+    let lever = sub(&tcp.position, &flange.position);
+    let linear = add(&linear, &cross(&lever, &angular));
+    let e = [
+        linear[0], linear[1], linear[2], angular[0], angular[1], angular[2],
+    ];
+    let rates = damped_least_squares(&model.jacobian(angles), &e, TOOL_JOG_DAMPING)?;
+    Some(rates.map(f64::to_degrees))
+}
+
+impl<T: Connection> MyCobotOperator<T> {
+    /// Jogs along the tool's own axes, which firmware jogging cannot do: each
+    /// cycle the twist from `next_twist` (mm/s and deg/s in the tool frame) is
+    /// turned into joint velocities through the Jacobian of `model` and
+    /// streamed with `send_angles`, until `next_twist` returns `None`.
+    pub fn jog_tool<F>(
+        &mut self,
+        model: &KinematicModel,
+        config: JointStreamConfig,
+        mut next_twist: F,
+    ) -> Result<()>
+    where
+        F: FnMut() -> Option<Twist>,
+    {
+        let tool = self.tool_pose().copied();
+        let mut streamer = JointVelocityStreamer::new(self, config)?;
+        while let Some(twist) = next_twist() {
+            let velocities =
+                tool_twist_to_joint_velocities(model, streamer.target(), tool.as_ref(), &twist)
+                    .unwrap_or([0.0; 6]);
+            streamer.stream_joint_velocities(&velocities);
+            if !streamer.spin(self)? {
+                return Ok(());
+            }
+        }
+        self.stop()
+    }
+}
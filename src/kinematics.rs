@@ -0,0 +1,414 @@
+//! Forward/inverse kinematics from Denavit-Hartenberg parameters.
+//! Joint angles are in degrees, poses follow the [`transform`](crate::transform) conventions.
+
+use super::angles::{wrap_degrees, JointAngles};
+use super::transform::*;
+
+const IK_MAX_ITERATIONS: usize = 200;
+const IK_POSITION_TOLERANCE: f64 = 0.5;
+const IK_ROTATION_TOLERANCE: f64 = 0.5;
+/// Weight applied to rotation errors (rad) so they are comparable with millimeters.
+const ROTATION_WEIGHT: f64 = 100.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DhParam {
+    pub d: f64,
+    pub a: f64,
+    pub alpha: f64,
+    pub offset: f64,
+}
+
+impl DhParam {
+    const fn new(d: f64, a: f64, alpha: f64, offset: f64) -> DhParam {
+        DhParam {
+            d,
+            a,
+            alpha,
+            offset,
+        }
+    }
+    fn transform(&self, theta: f64) -> Pose {
+        let (st, ct) = (theta + self.offset).sin_cos();
+        let (sa, ca) = self.alpha.sin_cos();
+        Pose::new(
+            [self.a * ct, self.a * st, self.d],
+            [
+                [ct, -st * ca, st * sa],
+                [st, ct * ca, -ct * sa],
+                [0.0, sa, ca],
+            ],
+        )
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct KinematicModel {
+    pub dh: [DhParam; 6],
+    pub joint_limits: [(f64, f64); 6],
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReachabilityReport {
+    pub reachable: bool,
+    /// Distance from the shoulder to the target position, in mm.
+    pub distance: f64,
+    pub max_reach: f64,
+    pub solution: Option<[f64; 6]>,
+    pub position_error: f64,
+    pub rotation_error: f64,
+    /// Zero-based indices of joints whose solution falls outside the joint limits.
+    pub violated_joints: Vec<usize>,
+}
+
+impl KinematicModel {
+    pub fn mycobot_280() -> KinematicModel {
+        use std::f64::consts::FRAC_PI_2;
+        KinematicModel {
+            dh: [
+                DhParam::new(131.22, 0.0, FRAC_PI_2, 0.0),
+                DhParam::new(0.0, -110.4, 0.0, -FRAC_PI_2),
+                DhParam::new(0.0, -96.0, 0.0, 0.0),
+                DhParam::new(63.4, 0.0, FRAC_PI_2, -FRAC_PI_2),
+                DhParam::new(75.05, 0.0, -FRAC_PI_2, FRAC_PI_2),
+                DhParam::new(45.6, 0.0, 0.0, 0.0),
+            ],
+            joint_limits: [
+                (-168.0, 168.0),
+                (-135.0, 135.0),
+                (-150.0, 150.0),
+                (-145.0, 145.0),
+                (-165.0, 165.0),
+                (-180.0, 180.0),
+            ],
+        }
+    }
+    pub fn mycobot_320() -> KinematicModel {
+        use std::f64::consts::FRAC_PI_2;
+        KinematicModel {
+            dh: [
+                DhParam::new(173.9, 0.0, FRAC_PI_2, 0.0),
+                DhParam::new(0.0, -135.0, 0.0, -FRAC_PI_2),
+                DhParam::new(0.0, -120.0, 0.0, 0.0),
+                DhParam::new(88.78, 0.0, FRAC_PI_2, -FRAC_PI_2),
+                DhParam::new(95.0, 0.0, -FRAC_PI_2, FRAC_PI_2),
+                DhParam::new(65.5, 0.0, 0.0, 0.0),
+            ],
+            joint_limits: [
+                (-170.0, 170.0),
+                (-137.0, 137.0),
+                (-151.0, 142.0),
+                (-148.0, 148.0),
+                (-169.0, 169.0),
+                (-180.0, 180.0),
+            ],
+        }
+    }
+    /// Flange pose for the given joint angles.
+    pub fn forward(&self, angles: &[f64; 6]) -> Pose {
+        self.dh
+            .iter()
+            .zip(angles.iter())
+            .fold(Pose::identity(), |pose, (dh, deg)| {
+                pose.compose(&dh.transform(deg.to_radians()))
+            })
+    }
+    /// Upper bound of the distance between the shoulder and the flange, in mm.
+    pub fn max_reach(&self) -> f64 {
+        self.dh[1..].iter().map(|dh| dh.a.abs() + dh.d.abs()).sum()
+    }
+    fn shoulder(&self) -> Vector3 {
+        [0.0, 0.0, self.dh[0].d]
+    }
+    pub fn violated_joints(&self, angles: &[f64; 6]) -> Vec<usize> {
+        angles
+            .iter()
+            .zip(self.joint_limits.iter())
+            .enumerate()
+            .filter(|(_, (v, (min, max)))| *v < min || *v > max)
+            .map(|(i, _)| i)
+            .collect()
+    }
+    pub fn within_limits(&self, angles: &[f64; 6]) -> bool {
+        self.violated_joints(angles).is_empty()
+    }
+    fn pose_error(current: &Pose, target: &Pose) -> [f64; 6] {
+        let dp = sub(&target.position, &current.position);
+        let dr = mat_mul(&target.rotation, &transpose(&current.rotation));
+        let (angle, axis) = matrix_to_axis_angle(&dr);
+        let w = axis.map(|v| v * angle * ROTATION_WEIGHT);
+        [dp[0], dp[1], dp[2], w[0], w[1], w[2]]
+    }
+    /// Geometric Jacobian of the flange in the base frame: rows are linear
+    /// velocity (mm) then angular velocity (rad), columns joint rates in radians.
+    pub fn jacobian(&self, angles: &[f64; 6]) -> [[f64; 6]; 6] {
+        let mut axes = [[0.0; 3]; 6];
+        let mut origins = [[0.0; 3]; 6];
+        let mut pose = Pose::identity();
+        for (j, (dh, deg)) in self.dh.iter().zip(angles.iter()).enumerate() {
+            axes[j] = [0, 1, 2].map(|r| pose.rotation[r][2]);
+            origins[j] = pose.position;
+            pose = pose.compose(&dh.transform(deg.to_radians()));
+        }
+        let mut jac = [[0.0; 6]; 6];
+        for j in 0..6 {
+            let v = cross(&axes[j], &sub(&pose.position, &origins[j]));
+            for i in 0..3 {
+                jac[i][j] = v[i];
+                jac[i + 3][j] = axes[j][i];
+            }
+        }
+        jac
+    }
+    /// Yoshikawa's measure `sqrt(det(J Jᵀ))`, falling to 0 at singular
+    /// configurations. Only comparable between poses of the same model.
+    pub fn manipulability(&self, angles: &[f64; 6]) -> f64 {
+        let jac = self.jacobian(angles);
+        let mut a = [[0.0; 6]; 6];
+        for i in 0..6 {
+            for j in 0..6 {
+                a[i][j] = (0..6).map(|k| jac[i][k] * jac[j][k]).sum::<f64>();
+            }
+        }
+        determinant(a).max(0.0).sqrt()
+    }
+    /// Numerical Jacobian of the weighted pose error with respect to joint angles in radians.
+    pub(crate) fn numerical_jacobian(&self, angles: &[f64; 6]) -> [[f64; 6]; 6] {
+        let eps = 1e-6_f64;
+        let base = self.forward(angles);
+        let mut jac = [[0.0; 6]; 6];
+        for j in 0..6 {
+            let mut moved = *angles;
+            moved[j] += eps.to_degrees();
+            let d = KinematicModel::pose_error(&base, &self.forward(&moved));
+            for i in 0..6 {
+                jac[i][j] = d[i] / eps;
+            }
+        }
+        jac
+    }
+    fn solve_from(&self, target: &Pose, seed: &[f64; 6]) -> ([f64; 6], f64, f64) {
+        let mut q = *seed;
+        let mut lambda = 1.0;
+        for _ in 0..IK_MAX_ITERATIONS {
+            let current = self.forward(&q);
+            let (pos_err, rot_err) = current.distance(target);
+            if pos_err < IK_POSITION_TOLERANCE && rot_err < IK_ROTATION_TOLERANCE {
+                break;
+            }
+            let e = KinematicModel::pose_error(&current, target);
+            let jac = self.numerical_jacobian(&q);
+            let step = match damped_least_squares(&jac, &e, lambda) {
+                Some(step) => step,
+                None => break,
+            };
+            let mut next = q;
+            for (v, s) in next.iter_mut().zip(step.iter()) {
+                *v = wrap_degrees(*v + s.to_degrees());
+            }
+            let (next_pos, next_rot) = self.forward(&next).distance(target);
+            if next_pos + next_rot < pos_err + rot_err {
+                q = next;
+                lambda = (lambda * 0.5).max(1e-3);
+            } else {
+                lambda *= 4.0;
+            }
+        }
+        let (pos_err, rot_err) = self.forward(&q).distance(target);
+        (q, pos_err, rot_err)
+    }
+    /// Joint angles reaching `target`, searched from `seed` and a few fallback seeds.
+    pub fn inverse(&self, target: &Pose, seed: &[f64; 6]) -> Option<[f64; 6]> {
+        let report = self.solve(target, seed);
+        if report.position_error < IK_POSITION_TOLERANCE
+            && report.rotation_error < IK_ROTATION_TOLERANCE
+        {
+            Some(report.angles)
+        } else {
+            None
+        }
+    }
+    fn solve(&self, target: &Pose, seed: &[f64; 6]) -> IkCandidate {
+        let seeds = [
+            *seed,
+            [0.0; 6],
+            [0.0, -45.0, -45.0, 0.0, 90.0, 0.0],
+            [90.0, 30.0, -90.0, 30.0, -90.0, 0.0],
+            [-90.0, -30.0, 90.0, -30.0, 90.0, 0.0],
+        ];
+        let mut best: Option<IkCandidate> = None;
+        for s in seeds.iter() {
+            let (angles, position_error, rotation_error) = self.solve_from(target, s);
+            let candidate = IkCandidate {
+                violations: self.violated_joints(&angles).len(),
+                angles,
+                position_error,
+                rotation_error,
+            };
+            let converged = candidate.converged();
+            if best.as_ref().is_none_or(|b| candidate.better_than(b)) {
+                best = Some(candidate);
+            }
+            if converged && best.as_ref().is_some_and(|b| b.violations == 0) {
+                break;
+            }
+        }
+        best.unwrap()
+    }
+    pub fn is_reachable(&self, target: &Pose) -> ReachabilityReport {
+        let distance = norm(&sub(&target.position, &self.shoulder()));
+        let max_reach = self.max_reach();
+        if distance > max_reach {
+            return ReachabilityReport {
+                reachable: false,
+                distance,
+                max_reach,
+                solution: None,
+                position_error: distance - max_reach,
+                rotation_error: 0.0,
+                violated_joints: Vec::new(),
+            };
+        }
+        let candidate = self.solve(target, &[0.0; 6]);
+        let converged = candidate.converged();
+        let violated_joints = if converged {
+            self.violated_joints(&candidate.angles)
+        } else {
+            Vec::new()
+        };
+        ReachabilityReport {
+            reachable: converged && violated_joints.is_empty(),
+            distance,
+            max_reach,
+            solution: if converged {
+                Some(candidate.angles)
+            } else {
+                None
+            },
+            position_error: candidate.position_error,
+            rotation_error: candidate.rotation_error,
+            violated_joints,
+        }
+    }
+}
+
+impl Default for KinematicModel {
+    fn default() -> KinematicModel {
+        KinematicModel::mycobot_280()
+    }
+}
+
+struct IkCandidate {
+    angles: [f64; 6],
+    position_error: f64,
+    rotation_error: f64,
+    violations: usize,
+}
+
+impl IkCandidate {
+    fn converged(&self) -> bool {
+        self.position_error < IK_POSITION_TOLERANCE && self.rotation_error < IK_ROTATION_TOLERANCE
+    }
+    fn better_than(&self, other: &IkCandidate) -> bool {
+        match (self.converged(), other.converged()) {
+            (true, false) => true,
+            (false, true) => false,
+            (true, true) => self.violations < other.violations,
+            (false, false) => {
+                self.position_error + self.rotation_error
+                    < other.position_error + other.rotation_error
+            }
+        }
+    }
+}
+
+/// Jacobian of the myCobot 280 model, see [`KinematicModel::jacobian`].
+pub fn jacobian(angles: &JointAngles) -> [[f64; 6]; 6] {
+    KinematicModel::mycobot_280().jacobian(&angles.0)
+}
+
+/// Manipulability of the myCobot 280 model, see [`KinematicModel::manipulability`].
+pub fn manipulability(angles: &JointAngles) -> f64 {
+    KinematicModel::mycobot_280().manipulability(&angles.0)
+}
+
+/// Checks a firmware pose against the myCobot 280 model.
+pub fn is_reachable(target: &Pose) -> ReachabilityReport {
+    KinematicModel::mycobot_280().is_reachable(target)
+}
+
+/// Solves `J^T (J J^T + λ²I)^-1 e`.
+pub(crate) fn damped_least_squares(
+    jac: &[[f64; 6]; 6],
+    e: &[f64; 6],
+    lambda: f64,
+) -> Option<[f64; 6]> {
+    let mut a = [[0.0; 6]; 6];
+    for i in 0..6 {
+        for j in 0..6 {
+            a[i][j] = (0..6).map(|k| jac[i][k] * jac[j][k]).sum::<f64>();
+        }
+        a[i][i] += lambda * lambda;
+    }
+    let y = solve_linear(a, *e)?;
+    let mut x = [0.0; 6];
+    for (i, xi) in x.iter_mut().enumerate() {
+        *xi = (0..6).map(|k| jac[k][i] * y[k]).sum();
+    }
+    Some(x)
+}
+
+/// Determinant by Gaussian elimination with partial pivoting.
+pub(crate) fn determinant<const N: usize>(mut a: [[f64; N]; N]) -> f64 {
+    let mut det = 1.0;
+    for col in 0..N {
+        let pivot = match (col..N).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs())) {
+            Some(pivot) => pivot,
+            None => return 0.0,
+        };
+        if a[pivot][col] == 0.0 {
+            return 0.0;
+        }
+        if pivot != col {
+            a.swap(col, pivot);
+            det = -det;
+        }
+        det *= a[col][col];
+        for row in (col + 1)..N {
+            let factor = a[row][col] / a[col][col];
+            let pivot_row = a[col];
+            for (v, p) in a[row].iter_mut().zip(pivot_row.iter()).skip(col) {
+                *v -= factor * p;
+            }
+        }
+    }
+    det
+}
+
+/// Gaussian elimination with partial pivoting.
+pub(crate) fn solve_linear<const N: usize>(
+    mut a: [[f64; N]; N],
+    mut b: [f64; N],
+) -> Option<[f64; N]> {
+    for col in 0..N {
+        let pivot = (col..N).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+        if a[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+        for row in (col + 1)..N {
+            let factor = a[row][col] / a[col][col];
+            let pivot_row = a[col];
+            for (v, p) in a[row].iter_mut().zip(pivot_row.iter()).skip(col) {
+                *v -= factor * p;
+            }
+            b[row] -= factor * b[col];
+        }
+    }
+    let mut x = [0.0; N];
+    for row in (0..N).rev() {
+        let s: f64 = ((row + 1)..N).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - s) / a[row][row];
+    }
+    Some(x)
+}
@@ -0,0 +1,191 @@
+use super::io::Connection;
+use super::operator::MyCobotOperator;
+use anyhow::Result;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+const TICK: Duration = Duration::from_millis(50);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Rgb {
+    pub const OFF: Rgb = Rgb::new(0, 0, 0);
+    pub const RED: Rgb = Rgb::new(255, 0, 0);
+    pub const GREEN: Rgb = Rgb::new(0, 255, 0);
+    pub const BLUE: Rgb = Rgb::new(0, 0, 255);
+    pub const YELLOW: Rgb = Rgb::new(255, 255, 0);
+    pub const WHITE: Rgb = Rgb::new(255, 255, 255);
+
+    pub const fn new(r: u8, g: u8, b: u8) -> Rgb {
+        Rgb { r, g, b }
+    }
+    pub fn lerp(&self, other: &Rgb, t: f64) -> Rgb {
+        let t = t.clamp(0.0, 1.0);
+        let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+        Rgb::new(
+            mix(self.r, other.r),
+            mix(self.g, other.g),
+            mix(self.b, other.b),
+        )
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LedPattern {
+    Solid(Rgb),
+    Blink {
+        on: Rgb,
+        off: Rgb,
+        period: Duration,
+    },
+    /// Fades from `from` to `to` and back over `period`.
+    Ramp {
+        from: Rgb,
+        to: Rgb,
+        period: Duration,
+    },
+    /// Cycles through colors, each held for its duration.
+    Sequence(Vec<(Rgb, Duration)>),
+}
+
+impl LedPattern {
+    pub fn color_at(&self, elapsed: Duration) -> Rgb {
+        let phase = |period: &Duration| {
+            if period.is_zero() {
+                0.0
+            } else {
+                (elapsed.as_secs_f64() % period.as_secs_f64()) / period.as_secs_f64()
+            }
+        };
+        match self {
+            LedPattern::Solid(color) => *color,
+            LedPattern::Blink { on, off, period } => {
+                if phase(period) < 0.5 {
+                    *on
+                } else {
+                    *off
+                }
+            }
+            LedPattern::Ramp { from, to, period } => {
+                let p = phase(period);
+                from.lerp(to, 1.0 - (2.0 * p - 1.0).abs())
+            }
+            LedPattern::Sequence(steps) => {
+                let total: Duration = steps.iter().map(|(_, d)| *d).sum();
+                if total.is_zero() {
+                    return steps.first().map_or(Rgb::OFF, |(c, _)| *c);
+                }
+                let mut t = Duration::from_secs_f64(phase(&total) * total.as_secs_f64());
+                for (color, duration) in steps.iter() {
+                    if t < *duration {
+                        return *color;
+                    }
+                    t -= *duration;
+                }
+                steps.last().map_or(Rgb::OFF, |(c, _)| *c)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LedState {
+    Idle,
+    Moving,
+    Error,
+}
+
+impl LedState {
+    pub fn pattern(&self) -> LedPattern {
+        match self {
+            LedState::Idle => LedPattern::Solid(Rgb::GREEN),
+            LedState::Moving => LedPattern::Ramp {
+                from: Rgb::OFF,
+                to: Rgb::BLUE,
+                period: Duration::from_secs(2),
+            },
+            LedState::Error => LedPattern::Blink {
+                on: Rgb::RED,
+                off: Rgb::OFF,
+                period: Duration::from_millis(500),
+            },
+        }
+    }
+}
+
+/// Drives the ATOM LED from a background thread.
+pub struct StatusLed {
+    sender: Option<Sender<LedPattern>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl StatusLed {
+    /// Spawns the LED thread; `set_color` is called whenever the displayed color changes.
+    pub fn spawn<F>(mut set_color: F) -> StatusLed
+    where
+        F: FnMut(Rgb) -> Result<()> + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel::<LedPattern>();
+        let thread = thread::spawn(move || {
+            let mut pattern: Option<LedPattern> = None;
+            let mut start = Instant::now();
+            let mut shown: Option<Rgb> = None;
+            loop {
+                match receiver.recv_timeout(TICK) {
+                    Ok(next) => {
+                        pattern = Some(next);
+                        start = Instant::now();
+                        shown = None;
+                    }
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+                if let Some(pattern) = pattern.as_ref() {
+                    let color = pattern.color_at(start.elapsed());
+                    if shown != Some(color) && set_color(color).is_ok() {
+                        shown = Some(color);
+                    }
+                }
+            }
+        });
+        StatusLed {
+            sender: Some(sender),
+            thread: Some(thread),
+        }
+    }
+    pub fn for_operator<T>(operator: Arc<Mutex<MyCobotOperator<T>>>) -> StatusLed
+    where
+        T: Connection + Send + 'static,
+    {
+        StatusLed::spawn(move |color| {
+            let mut operator = operator
+                .lock()
+                .map_err(|_| anyhow::anyhow!("Operator mutex poisoned"))?;
+            operator.set_color(color.r, color.g, color.b)
+        })
+    }
+    pub fn set_pattern(&self, pattern: LedPattern) {
+        if let Some(sender) = self.sender.as_ref() {
+            let _ = sender.send(pattern);
+        }
+    }
+    pub fn set_state(&self, state: LedState) {
+        self.set_pattern(state.pattern());
+    }
+}
+
+impl Drop for StatusLed {
+    fn drop(&mut self) {
+        self.sender.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
@@ -1,9 +1,130 @@
+mod angles;
+mod audit;
+mod backlash;
+mod batch;
+mod blackbox;
+#[cfg(all(feature = "bluetooth", target_os = "linux"))]
+mod bluetooth;
+mod bootloader;
+pub mod calibration;
 mod common;
+pub mod compat;
+#[cfg(feature = "toml")]
+mod config;
+mod conversion;
+mod conveyor;
+#[cfg(feature = "svg")]
+mod drawing;
+mod dry_run;
+mod end_effector;
+mod errors;
+mod estop;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod filter;
 mod io;
+mod jog;
+pub mod kinematics;
+mod led;
+mod limits;
+mod look_at;
+mod monitor;
+mod motion;
+mod motion_ui;
+mod mounting;
+mod multi;
+mod notifications;
 mod operator;
+mod payload;
+mod planner;
+mod poses;
+mod profile;
+mod program;
+pub mod protocol;
+mod rail;
+#[cfg(feature = "rand")]
+mod random;
+mod reconnect;
+mod robot_api;
+#[cfg(feature = "scripting")]
+mod scripting;
+mod servoing;
+mod shared;
+mod shutdown;
+mod soft_float;
+mod stats;
+#[cfg(feature = "async")]
+mod stream;
+mod sysid;
+mod tap;
+pub mod teleop;
+pub mod tests;
+mod thermal;
+mod tool;
+pub mod transform;
 mod utils;
+#[cfg(feature = "viz")]
+mod viz;
+mod watchdog;
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+mod webserial;
 
-pub use anyhow::Result;
+pub use self::angles::*;
+pub use self::audit::*;
+pub use self::backlash::*;
+pub use self::batch::*;
+pub use self::blackbox::*;
+#[cfg(all(feature = "bluetooth", target_os = "linux"))]
+pub use self::bluetooth::*;
+pub use self::bootloader::*;
 pub use self::common::*;
+#[cfg(feature = "toml")]
+pub use self::config::*;
+pub use self::conversion::*;
+pub use self::conveyor::*;
+#[cfg(feature = "svg")]
+pub use self::drawing::*;
+pub use self::dry_run::*;
+pub use self::end_effector::*;
+pub use self::errors::*;
+pub use self::estop::*;
+pub use self::filter::*;
 pub use self::io::*;
+pub use self::jog::*;
+pub use self::led::*;
+pub use self::limits::*;
+pub use self::monitor::*;
+pub use self::motion::*;
+pub use self::motion_ui::*;
+pub use self::mounting::*;
+pub use self::multi::*;
 pub use self::operator::*;
+pub use self::payload::*;
+pub use self::planner::*;
+pub use self::poses::*;
+pub use self::profile::*;
+pub use self::program::*;
+pub use self::rail::*;
+#[cfg(feature = "rand")]
+pub use self::random::*;
+pub use self::reconnect::*;
+pub use self::robot_api::*;
+#[cfg(feature = "scripting")]
+pub use self::scripting::*;
+pub use self::servoing::*;
+pub use self::shared::*;
+pub use self::shutdown::*;
+pub use self::soft_float::*;
+pub use self::stats::*;
+#[cfg(feature = "async")]
+pub use self::stream::*;
+pub use self::sysid::*;
+pub use self::tap::*;
+pub use self::thermal::*;
+pub use self::tool::*;
+#[cfg(feature = "viz")]
+pub use self::viz::*;
+pub use self::watchdog::*;
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub use self::webserial::*;
+pub use anyhow::Result;
@@ -0,0 +1,117 @@
+use super::kinematics::KinematicModel;
+use anyhow::Result;
+
+/// Per-joint soft limits in degrees, checked before angle commands are sent.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct JointLimits {
+    pub min: [f64; 6],
+    pub max: [f64; 6],
+}
+
+impl JointLimits {
+    pub fn new(min: [f64; 6], max: [f64; 6]) -> JointLimits {
+        JointLimits { min, max }
+    }
+    pub fn from_model(model: &KinematicModel) -> JointLimits {
+        JointLimits {
+            min: model.joint_limits.map(|(min, _)| min),
+            max: model.joint_limits.map(|(_, max)| max),
+        }
+    }
+    /// Narrows joint `index` (0-based) to the intersection with `min..=max`; limits are never widened.
+    pub fn tighten(&mut self, index: usize, min: f64, max: f64) {
+        self.min[index] = self.min[index].max(min);
+        self.max[index] = self.max[index].min(max);
+    }
+    pub fn contains(&self, index: usize, degree: f64) -> bool {
+        self.min[index] <= degree && degree <= self.max[index]
+    }
+    /// 0-based indices of joints outside their limits.
+    pub fn violations(&self, degrees: &[f64; 6]) -> Vec<usize> {
+        (0..6).filter(|&i| !self.contains(i, degrees[i])).collect()
+    }
+    pub fn clamp(&self, degrees: &[f64; 6]) -> [f64; 6] {
+        let mut out = *degrees;
+        for (i, d) in out.iter_mut().enumerate() {
+            *d = d.clamp(self.min[i], self.max[i]);
+        }
+        out
+    }
+}
+
+/// What happens to Cartesian targets outside a `Workspace`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WorkspacePolicy {
+    /// Fail the command.
+    Reject,
+    /// Move the position to the nearest point of the envelope, keeping the orientation.
+    Clamp,
+}
+
+/// Envelope of flange positions in the base frame, in mm: a cylinder about
+/// the base z axis. Checked before coord commands are sent.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Workspace {
+    pub max_radius: f64,
+    pub min_z: f64,
+    pub max_z: f64,
+    pub policy: WorkspacePolicy,
+}
+
+impl Workspace {
+    pub fn new(max_radius: f64, min_z: f64, max_z: f64) -> Workspace {
+        Workspace {
+            max_radius,
+            min_z,
+            max_z,
+            policy: WorkspacePolicy::Reject,
+        }
+    }
+    pub fn mycobot_280() -> Workspace {
+        Workspace::new(281.45, -70.0, 412.67)
+    }
+    pub fn mycobot_320() -> Workspace {
+        Workspace::new(350.0, -41.0, 523.9)
+    }
+    pub fn mycobot_pro_630() -> Workspace {
+        Workspace::new(630.0, -425.0, 835.0)
+    }
+    pub fn mypalletizer_260() -> Workspace {
+        Workspace::new(260.0, -15.0, 357.58)
+    }
+    pub fn with_policy(mut self, policy: WorkspacePolicy) -> Workspace {
+        self.policy = policy;
+        self
+    }
+    pub fn contains(&self, coords: &[f64; 6]) -> bool {
+        coords[0].hypot(coords[1]) <= self.max_radius
+            && self.min_z <= coords[2]
+            && coords[2] <= self.max_z
+    }
+    pub fn clamp(&self, coords: &[f64; 6]) -> [f64; 6] {
+        let mut out = *coords;
+        let radius = coords[0].hypot(coords[1]);
+        if radius > self.max_radius {
+            out[0] *= self.max_radius / radius;
+            out[1] *= self.max_radius / radius;
+        }
+        out[2] = out[2].clamp(self.min_z, self.max_z);
+        out
+    }
+    /// `coords` as they should be sent under the policy, or an error when rejected.
+    pub fn apply(&self, coords: &[f64; 6]) -> Result<[f64; 6]> {
+        if self.contains(coords) {
+            return Ok(*coords);
+        }
+        match self.policy {
+            WorkspacePolicy::Clamp => Ok(self.clamp(coords)),
+            WorkspacePolicy::Reject => Err(anyhow::anyhow!(
+                "Target ({}, {}, {}) is outside the workspace",
+                coords[0],
+                coords[1],
+                coords[2]
+            )),
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use super::io::Connection;
+use super::kinematics::KinematicModel;
+use super::operator::MyCobotOperator;
+use anyhow::Result;
+
+impl<T: Connection> MyCobotOperator<T> {
+    /// Points the tool's z axis at `target` (mm, base frame) from `standoff`
+    /// mm away, approaching along the line from the current tool position, as
+    /// a camera on the wrist needs for scanning and inspection. The pose is
+    /// solved with `model` from the current joint angles and sent as a joint
+    /// move; returns the angles sent.
+    pub fn look_at(
+        &mut self,
+        model: &KinematicModel,
+        target: &[f64; 3],
+        standoff: f64,
+        speed: u8,
+    ) -> Result<[f64; 6]> {
+        let angles = self.get_angles()?;
+        let tool = self.tool_pose().copied();
+        let flange = model.forward(&angles);
+        let current = match &tool {
+            Some(tool) => flange.compose(tool),
+            None => flange,
+        };
+        let view = current
+            .look_at(target, standoff)
+            .ok_or_else(|| anyhow::anyhow!("The tool is already at the look-at target"))?;
+        let flange_target = match &tool {
+            Some(tool) => view.compose(&tool.inverse()),
+            None => view,
+        };
+        let solution = model.inverse(&flange_target, &angles).ok_or_else(|| {
+            anyhow::anyhow!(
+                "No joint solution looks at {:?} from {} mm",
+                target,
+                standoff
+            )
+        })?;
+        self.send_angles(&solution, speed)?;
+        Ok(solution)
+    }
+}
@@ -0,0 +1,335 @@
+use super::io::Connection;
+use super::operator::MyCobotOperator;
+use anyhow::{Error, Result};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snapshot {
+    pub angles: [f64; 6],
+    pub coords: [f64; 6],
+    pub moving: bool,
+    pub timestamp: SystemTime,
+}
+
+impl Snapshot {
+    pub fn read<T: Connection>(operator: &mut MyCobotOperator<T>) -> Result<Snapshot> {
+        let angles = operator.get_angles()?;
+        let coords = operator.get_coords()?;
+        let moving = operator.is_moving()? == 1;
+        Ok(Snapshot {
+            angles,
+            coords,
+            moving,
+            timestamp: SystemTime::now(),
+        })
+    }
+    /// Whether no angle moved more than `angle_deadband` degrees, no coord
+    /// more than `coord_deadband`, and the moving flag is unchanged.
+    fn same_state(&self, other: &Snapshot, angle_deadband: f64, coord_deadband: f64) -> bool {
+        let within = |a: &[f64; 6], b: &[f64; 6], deadband: f64| {
+            a.iter()
+                .zip(b.iter())
+                .all(|(x, y)| (x - y).abs() <= deadband)
+        };
+        within(&self.angles, &other.angles, angle_deadband)
+            && within(&self.coords, &other.coords, coord_deadband)
+            && self.moving == other.moving
+    }
+}
+
+const CSV_HEADER: &str = "unix_time,elapsed,j1,j2,j3,j4,j5,j6,x,y,z,rx,ry,rz,moving";
+
+/// When `record_to_csv` starts a new file. The first file is `path` itself,
+/// later ones insert `-1`, `-2`, ... before the extension.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CsvRotation {
+    pub max_bytes: Option<u64>,
+    pub max_duration: Option<Duration>,
+}
+
+struct RotatingCsv {
+    path: PathBuf,
+    rotation: CsvRotation,
+    index: usize,
+    writer: BufWriter<File>,
+    written: u64,
+    opened: Instant,
+}
+
+impl RotatingCsv {
+    fn create(path: &Path, rotation: CsvRotation) -> Result<RotatingCsv> {
+        let mut csv = RotatingCsv {
+            path: path.to_path_buf(),
+            rotation,
+            index: 0,
+            writer: BufWriter::new(File::create(path)?),
+            written: 0,
+            opened: Instant::now(),
+        };
+        csv.write_line(CSV_HEADER)?;
+        Ok(csv)
+    }
+    fn file_path(&self) -> PathBuf {
+        if self.index == 0 {
+            return self.path.clone();
+        }
+        let stem = self
+            .path
+            .file_stem()
+            .map_or_else(Default::default, |s| s.to_string_lossy());
+        let name = match self.path.extension() {
+            Some(ext) => format!("{}-{}.{}", stem, self.index, ext.to_string_lossy()),
+            None => format!("{}-{}", stem, self.index),
+        };
+        self.path.with_file_name(name)
+    }
+    fn write_line(&mut self, line: &str) -> Result<()> {
+        writeln!(self.writer, "{}", line)?;
+        self.written += line.len() as u64 + 1;
+        Ok(())
+    }
+    fn write_row(&mut self, row: &str) -> Result<()> {
+        let full = self
+            .rotation
+            .max_bytes
+            .is_some_and(|max| self.written >= max)
+            || self
+                .rotation
+                .max_duration
+                .is_some_and(|max| self.opened.elapsed() >= max);
+        if full {
+            self.writer.flush()?;
+            self.index += 1;
+            self.writer = BufWriter::new(File::create(self.file_path())?);
+            self.written = 0;
+            self.opened = Instant::now();
+            self.write_line(CSV_HEADER)?;
+        }
+        self.write_line(row)
+    }
+}
+
+fn csv_row(snapshot: &Snapshot, elapsed: Duration) -> String {
+    let unix_time = snapshot
+        .timestamp
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |d| d.as_secs_f64());
+    let values = snapshot
+        .angles
+        .iter()
+        .chain(snapshot.coords.iter())
+        .map(|v| format!("{:.2}", v))
+        .collect::<Vec<_>>()
+        .join(",");
+    format!(
+        "{:.6},{:.6},{},{}",
+        unix_time,
+        elapsed.as_secs_f64(),
+        values,
+        snapshot.moving as u8
+    )
+}
+
+type SnapshotCallback = Box<dyn FnMut(&Snapshot) + Send>;
+type ChangeCallback = Box<dyn FnMut(&Snapshot, Option<&Snapshot>) + Send>;
+type ErrorCallback = Box<dyn FnMut(&Error) + Send>;
+
+/// Polls the robot state and fires observers on changes.
+pub struct StateMonitor {
+    last: Option<Snapshot>,
+    /// The snapshot last passed to `on_state_change`, which deadbands are measured from.
+    reported: Option<Snapshot>,
+    angle_deadband: f64,
+    coord_deadband: f64,
+    history: VecDeque<(Instant, [f64; 6])>,
+    history_span: Duration,
+    motion_complete: Vec<SnapshotCallback>,
+    state_change: Vec<ChangeCallback>,
+    error: Vec<ErrorCallback>,
+}
+
+impl Default for StateMonitor {
+    fn default() -> StateMonitor {
+        StateMonitor {
+            last: None,
+            reported: None,
+            angle_deadband: 0.0,
+            coord_deadband: 0.0,
+            history: VecDeque::new(),
+            history_span: Duration::from_secs(5),
+            motion_complete: Vec::new(),
+            state_change: Vec::new(),
+            error: Vec::new(),
+        }
+    }
+}
+
+impl StateMonitor {
+    pub fn new() -> StateMonitor {
+        StateMonitor::default()
+    }
+    /// Readback noise to ignore: `on_state_change` fires only once an angle
+    /// moves more than `angles` degrees or a coord more than `coords` from
+    /// the last reported snapshot, or the moving flag changes. Both default to 0.
+    pub fn set_deadbands(&mut self, angles: f64, coords: f64) -> &mut StateMonitor {
+        self.angle_deadband = angles.max(0.0);
+        self.coord_deadband = coords.max(0.0);
+        self
+    }
+    /// How long angle readings are kept for `has_settled`, 5 s by default.
+    pub fn set_history_span(&mut self, span: Duration) -> &mut StateMonitor {
+        self.history_span = span;
+        self
+    }
+    /// Whether every joint stayed within a `tolerance` degree band for the
+    /// last `window`, e.g. to tell that oscillation after a move has died
+    /// out. `false` until polling has covered `window`, which is limited by
+    /// the history span.
+    pub fn has_settled(&self, window: Duration, tolerance: f64) -> bool {
+        let now = Instant::now();
+        let covered = self
+            .history
+            .front()
+            .is_some_and(|(t, _)| now.duration_since(*t) >= window);
+        if !covered {
+            return false;
+        }
+        let recent: Vec<&[f64; 6]> = self
+            .history
+            .iter()
+            .filter(|(t, _)| now.duration_since(*t) <= window)
+            .map(|(_, angles)| angles)
+            .collect();
+        (0..6).all(|j| {
+            let (min, max) = recent.iter().fold((f64::MAX, f64::MIN), |(lo, hi), a| {
+                (lo.min(a[j]), hi.max(a[j]))
+            });
+            recent.is_empty() || max - min <= tolerance
+        })
+    }
+    /// Called when the robot goes from moving to idle.
+    pub fn on_motion_complete<F>(&mut self, callback: F) -> &mut StateMonitor
+    where
+        F: FnMut(&Snapshot) + Send + 'static,
+    {
+        self.motion_complete.push(Box::new(callback));
+        self
+    }
+    /// Called with the new and the previous snapshot whenever the state differs.
+    pub fn on_state_change<F>(&mut self, callback: F) -> &mut StateMonitor
+    where
+        F: FnMut(&Snapshot, Option<&Snapshot>) + Send + 'static,
+    {
+        self.state_change.push(Box::new(callback));
+        self
+    }
+    pub fn on_error<F>(&mut self, callback: F) -> &mut StateMonitor
+    where
+        F: FnMut(&Error) + Send + 'static,
+    {
+        self.error.push(Box::new(callback));
+        self
+    }
+    pub fn last(&self) -> Option<&Snapshot> {
+        self.last.as_ref()
+    }
+    pub fn poll<T: Connection>(&mut self, operator: &mut MyCobotOperator<T>) -> Result<Snapshot> {
+        match Snapshot::read(operator) {
+            Ok(snapshot) => {
+                self.process(snapshot.clone());
+                Ok(snapshot)
+            }
+            Err(e) => {
+                for callback in self.error.iter_mut() {
+                    callback(&e);
+                }
+                Err(e)
+            }
+        }
+    }
+    fn process(&mut self, snapshot: Snapshot) {
+        let now = Instant::now();
+        self.history.push_back((now, snapshot.angles));
+        while self
+            .history
+            .front()
+            .is_some_and(|(t, _)| now.duration_since(*t) > self.history_span)
+        {
+            self.history.pop_front();
+        }
+        let previous = self.last.take();
+        let (angle_deadband, coord_deadband) = (self.angle_deadband, self.coord_deadband);
+        if self
+            .reported
+            .as_ref()
+            .is_none_or(|r| !r.same_state(&snapshot, angle_deadband, coord_deadband))
+        {
+            for callback in self.state_change.iter_mut() {
+                callback(&snapshot, previous.as_ref());
+            }
+            self.reported = Some(snapshot.clone());
+        }
+        if previous.as_ref().is_some_and(|p| p.moving) && !snapshot.moving {
+            for callback in self.motion_complete.iter_mut() {
+                callback(&snapshot);
+            }
+        }
+        self.last = Some(snapshot);
+    }
+    /// Polls at `rate_hz` while `keep_running` returns `true`, writing each
+    /// snapshot as a CSV row of angles and coords. Rows carry both the wall
+    /// clock time of the reading and the monotonic time since recording
+    /// started, so logs line up with other sources and still plot evenly.
+    /// Observers fire as with `poll`; read errors skip the row.
+    pub fn record_to_csv<T, P, F>(
+        &mut self,
+        operator: &mut MyCobotOperator<T>,
+        path: P,
+        rate_hz: f64,
+        rotation: CsvRotation,
+        mut keep_running: F,
+    ) -> Result<()>
+    where
+        T: Connection,
+        P: AsRef<Path>,
+        F: FnMut() -> bool,
+    {
+        let period = Duration::from_secs_f64(1.0 / rate_hz.max(0.1));
+        let mut csv = RotatingCsv::create(path.as_ref(), rotation)?;
+        let start = Instant::now();
+        let mut next = start;
+        while keep_running() {
+            if let Ok(snapshot) = self.poll(operator) {
+                csv.write_row(&csv_row(&snapshot, start.elapsed()))?;
+            }
+            next += period;
+            match next.checked_duration_since(Instant::now()) {
+                Some(wait) => thread::sleep(wait),
+                None => next = Instant::now(),
+            }
+        }
+        csv.writer.flush()?;
+        Ok(())
+    }
+    /// Polls every `period` while `keep_running` returns `true`.
+    /// Read errors are reported to `on_error` observers and do not stop the loop.
+    pub fn run<T, F>(
+        &mut self,
+        operator: &mut MyCobotOperator<T>,
+        period: Duration,
+        mut keep_running: F,
+    ) where
+        T: Connection,
+        F: FnMut() -> bool,
+    {
+        while keep_running() {
+            let _ = self.poll(operator);
+            thread::sleep(period);
+        }
+    }
+}
@@ -0,0 +1,988 @@
+use super::angles::JointAngles;
+use super::common::{FreshMode, Mode};
+use super::io::Connection;
+use super::limits::JointLimits;
+use super::operator::MyCobotOperator;
+use super::servoing::stream_period;
+use super::transform::Pose;
+use anyhow::Result;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Clone)]
+pub enum Move {
+    Angles {
+        degrees: [f64; 6],
+        speed: u8,
+    },
+    Coords {
+        coords: [f64; 6],
+        speed: u8,
+        mode: Mode,
+    },
+    Wait(Duration),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MoveOutcome {
+    Completed,
+    TimedOut,
+    Cancelled,
+    Failed(String),
+}
+
+/// Measured state after `sync_send_angles`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AngleMoveResult {
+    pub angles: JointAngles,
+    /// Largest per-joint difference from the target in degrees.
+    pub error: f64,
+    /// Whether the robot reported the target in position before the timeout.
+    pub reached: bool,
+}
+
+/// Measured state after `sync_send_coords`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CoordMoveResult {
+    pub pose: Pose,
+    /// Distance from the target position in millimetres.
+    pub position_error: f64,
+    /// Rotation from the target orientation in degrees.
+    pub rotation_error: f64,
+    pub reached: bool,
+}
+
+enum Control {
+    Push(Move, Sender<MoveOutcome>),
+    Pause,
+    Resume,
+    CancelAll,
+    Shutdown,
+}
+
+struct Worker<T: Connection> {
+    operator: MyCobotOperator<T>,
+    receiver: Receiver<Control>,
+    pending: VecDeque<(Move, Sender<MoveOutcome>)>,
+    move_timeout: Duration,
+    /// Distance in mm from a coords target at which the next queued coords move takes over.
+    blend_radius: f64,
+    paused: bool,
+    shutdown: bool,
+}
+
+enum Interrupt {
+    None,
+    Cancelled,
+}
+
+impl<T: Connection> Worker<T> {
+    fn cancel_pending(&mut self) {
+        for (_, done) in self.pending.drain(..) {
+            let _ = done.send(MoveOutcome::Cancelled);
+        }
+    }
+    fn handle(&mut self, control: Control) -> Result<Interrupt> {
+        match control {
+            Control::Push(m, done) => self.pending.push_back((m, done)),
+            Control::Pause if !self.paused => {
+                self.paused = true;
+                self.operator.pause()?;
+            }
+            Control::Resume if self.paused => {
+                self.paused = false;
+                self.operator.resume()?;
+            }
+            Control::CancelAll => {
+                self.paused = false;
+                self.operator.stop()?;
+                self.cancel_pending();
+                return Ok(Interrupt::Cancelled);
+            }
+            Control::Shutdown => {
+                self.shutdown = true;
+                self.operator.stop()?;
+                self.cancel_pending();
+                return Ok(Interrupt::Cancelled);
+            }
+            _ => {}
+        }
+        Ok(Interrupt::None)
+    }
+    /// Whether the move in flight may hand over to the next one before arriving.
+    fn blends_into_next(&self) -> bool {
+        self.blend_radius > 0.0 && matches!(self.pending.front(), Some((Move::Coords { .. }, _)))
+    }
+    fn is_done(&mut self, m: &Move, active: Duration) -> Result<bool> {
+        Ok(match m {
+            Move::Coords { coords, .. } if self.blends_into_next() => {
+                let current = self.operator.get_coords()?;
+                let (mm, _) = Pose::from_coords(&current).distance(&Pose::from_coords(coords));
+                mm <= self.blend_radius || self.operator.is_in_coord_position(coords)? == 1
+            }
+            Move::Angles { degrees, .. } => self.operator.is_in_angle_position(degrees)? == 1,
+            Move::Coords { coords, .. } => self.operator.is_in_coord_position(coords)? == 1,
+            Move::Wait(duration) => active >= *duration,
+        })
+    }
+    fn execute(&mut self, m: &Move) -> Result<MoveOutcome> {
+        match m {
+            Move::Angles { degrees, speed } => self.operator.send_angles(degrees, *speed)?,
+            Move::Coords {
+                coords,
+                speed,
+                mode,
+            } => self.operator.send_coords(coords, *speed, *mode)?,
+            Move::Wait(_) => {}
+        }
+        let mut active = Duration::ZERO;
+        let mut last = Instant::now();
+        loop {
+            match self.receiver.recv_timeout(POLL_INTERVAL) {
+                Ok(control) => {
+                    if let Interrupt::Cancelled = self.handle(control)? {
+                        return Ok(MoveOutcome::Cancelled);
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => {
+                    self.handle(Control::Shutdown)?;
+                    return Ok(MoveOutcome::Cancelled);
+                }
+            }
+            let now = Instant::now();
+            if !self.paused {
+                active += now.duration_since(last);
+                if self.is_done(m, active)? {
+                    return Ok(MoveOutcome::Completed);
+                }
+                if active > self.move_timeout {
+                    return Ok(MoveOutcome::TimedOut);
+                }
+            }
+            last = now;
+        }
+    }
+    fn run(mut self) -> MyCobotOperator<T> {
+        // In interrupt mode a new target replaces the current one mid-motion,
+        // which is what lets a blended move continue without stopping. The
+        // previous mode is put back when the worker exits; if it cannot be
+        // read, blending is turned off rather than leaving the mode changed.
+        let mut restore_mode = None;
+        if self.blend_radius > 0.0 {
+            match self.operator.get_fresh_mode() {
+                Ok(previous) if self.operator.set_fresh_mode(FreshMode::Interrupt).is_ok() => {
+                    restore_mode = Some(previous);
+                }
+                _ => self.blend_radius = 0.0,
+            }
+        }
+        while !self.shutdown {
+            if self.paused || self.pending.is_empty() {
+                let res = match self.receiver.recv() {
+                    Ok(control) => self.handle(control),
+                    Err(_) => self.handle(Control::Shutdown),
+                };
+                if res.is_err() {
+                    self.cancel_pending();
+                }
+                continue;
+            }
+            let (m, done) = self.pending.pop_front().unwrap();
+            let outcome = self
+                .execute(&m)
+                .unwrap_or_else(|e| MoveOutcome::Failed(e.to_string()));
+            let _ = done.send(outcome);
+        }
+        if let Some(mode) = restore_mode {
+            let _ = self.operator.set_fresh_mode(mode);
+        }
+        self.operator
+    }
+}
+
+/// Executes moves one after another on a worker thread that owns the operator.
+pub struct MotionQueue<T: Connection> {
+    sender: Sender<Control>,
+    worker: Option<JoinHandle<MyCobotOperator<T>>>,
+}
+
+impl<T: Connection + Send + 'static> MotionQueue<T> {
+    pub fn spawn(operator: MyCobotOperator<T>, move_timeout: Duration) -> MotionQueue<T> {
+        MotionQueue::spawn_blended(operator, move_timeout, 0.0)
+    }
+    /// Like `spawn`, but a coords move followed by another queued coords move
+    /// counts as completed once the tool is within `blend_radius` mm of its
+    /// target, and the next one is sent right away in fresh (interrupt) mode,
+    /// rounding the corner instead of stopping. The radius should exceed the
+    /// distance travelled in one 100 ms poll.
+    pub fn spawn_blended(
+        operator: MyCobotOperator<T>,
+        move_timeout: Duration,
+        blend_radius: f64,
+    ) -> MotionQueue<T> {
+        let (sender, receiver) = mpsc::channel();
+        let worker = Worker {
+            operator,
+            receiver,
+            pending: VecDeque::new(),
+            move_timeout,
+            blend_radius,
+            paused: false,
+            shutdown: false,
+        };
+        MotionQueue {
+            sender,
+            worker: Some(thread::spawn(move || worker.run())),
+        }
+    }
+    /// Queues a move; the returned receiver yields its outcome once it finishes.
+    pub fn push(&self, m: Move) -> Receiver<MoveOutcome> {
+        let (done, outcome) = mpsc::channel();
+        if let Err(mpsc::SendError(Control::Push(_, done))) =
+            self.sender.send(Control::Push(m, done))
+        {
+            let _ = done.send(MoveOutcome::Cancelled);
+        }
+        outcome
+    }
+    /// Pauses the move in flight (firmware `pause`) and holds back queued moves.
+    pub fn pause(&self) {
+        let _ = self.sender.send(Control::Pause);
+    }
+    pub fn resume(&self) {
+        let _ = self.sender.send(Control::Resume);
+    }
+    /// Stops the current move and cancels every queued one.
+    pub fn cancel_all(&self) {
+        let _ = self.sender.send(Control::CancelAll);
+    }
+    /// Cancels remaining moves and returns the operator.
+    pub fn shutdown(mut self) -> Result<MyCobotOperator<T>> {
+        let _ = self.sender.send(Control::Shutdown);
+        self.worker
+            .take()
+            .unwrap()
+            .join()
+            .map_err(|_| anyhow::anyhow!("Motion worker panicked"))
+    }
+}
+
+impl<T: Connection> Drop for MotionQueue<T> {
+    fn drop(&mut self) {
+        if let Some(worker) = self.worker.take() {
+            let _ = self.sender.send(Control::Shutdown);
+            let _ = worker.join();
+        }
+    }
+}
+
+/// A joint-space point reached `time` after the start of a trajectory.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Waypoint {
+    pub time: Duration,
+    pub angles: [f64; 6],
+    /// Slide rail position in millimetres, treated as a 7th axis.
+    pub rail: Option<f64>,
+}
+
+/// Timed joint waypoints, sampled with linear interpolation and optional
+/// parabolic blends at the corners.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Trajectory {
+    pub waypoints: Vec<Waypoint>,
+}
+
+impl Trajectory {
+    pub fn new() -> Trajectory {
+        Trajectory::default()
+    }
+    /// Builds a trajectory from samples recorded every `period`, e.g. drag-teach.
+    pub fn from_samples(period: Duration, samples: &[[f64; 6]]) -> Trajectory {
+        Trajectory {
+            waypoints: samples
+                .iter()
+                .enumerate()
+                .map(|(i, angles)| Waypoint {
+                    time: period * i as u32,
+                    angles: *angles,
+                    rail: None,
+                })
+                .collect(),
+        }
+    }
+    /// Appends a waypoint; times must be increasing.
+    pub fn push(&mut self, time: Duration, angles: [f64; 6]) {
+        self.waypoints.push(Waypoint {
+            time,
+            angles,
+            rail: None,
+        });
+    }
+    /// Appends a waypoint that also positions the slide rail.
+    pub fn push_with_rail(&mut self, time: Duration, angles: [f64; 6], rail: f64) {
+        self.waypoints.push(Waypoint {
+            time,
+            angles,
+            rail: Some(rail),
+        });
+    }
+    /// Rail position at time `t`, linearly interpolated between the waypoints
+    /// that set one; `None` when no waypoint does.
+    pub fn sample_rail(&self, t: Duration) -> Option<f64> {
+        let mut points = self
+            .waypoints
+            .iter()
+            .filter_map(|w| w.rail.map(|rail| (w.time, rail)));
+        let (mut prev_time, mut prev_rail) = points.next()?;
+        if t <= prev_time {
+            return Some(prev_rail);
+        }
+        for (time, rail) in points {
+            if t <= time {
+                let span = (time - prev_time).as_secs_f64();
+                if span <= 0.0 {
+                    return Some(rail);
+                }
+                let s = (t - prev_time).as_secs_f64() / span;
+                return Some(prev_rail + (rail - prev_rail) * s);
+            }
+            prev_time = time;
+            prev_rail = rail;
+        }
+        Some(prev_rail)
+    }
+    pub fn duration(&self) -> Duration {
+        self.waypoints.last().map_or(Duration::ZERO, |w| w.time)
+    }
+    /// Rewrites each waypoint as the previous one plus `JointAngles::shortest_delta`,
+    /// so recorded angles that wrapped past ±180° are not replayed as a full
+    /// sweep the other way.
+    pub fn unwrapped(&self, limits: &JointLimits) -> Trajectory {
+        let mut out = self.waypoints.clone();
+        for i in 1..out.len() {
+            let prev = JointAngles(out[i - 1].angles);
+            out[i].angles = prev
+                .lerp_shortest(&JointAngles(out[i].angles), 1.0, limits)
+                .0;
+        }
+        Trajectory { waypoints: out }
+    }
+    /// Zero-phase low-pass filter with time constant `tau` seconds, applied
+    /// forward then backward so the path is not delayed. Endpoints are kept.
+    pub fn smoothed(&self, tau: f64) -> Trajectory {
+        let n = self.waypoints.len();
+        if n < 3 || tau <= 0.0 {
+            return self.clone();
+        }
+        let alpha = |a: &Waypoint, b: &Waypoint| {
+            let dt = (b.time.max(a.time) - b.time.min(a.time)).as_secs_f64();
+            dt / (tau + dt)
+        };
+        let mut out = self.waypoints.clone();
+        for i in 1..n {
+            let a = alpha(&out[i - 1], &out[i]);
+            out[i].angles = lerp(&out[i - 1].angles, &self.waypoints[i].angles, a);
+        }
+        out[n - 1].angles = self.waypoints[n - 1].angles;
+        for i in (1..n - 1).rev() {
+            let a = alpha(&out[i + 1], &out[i]);
+            out[i].angles = lerp(&out[i + 1].angles, &out[i].angles, a);
+        }
+        out[0].angles = self.waypoints[0].angles;
+        Trajectory { waypoints: out }
+    }
+    /// Angles at time `t`. Interior corners are replaced by parabolic blends of
+    /// up to `blend` duration, shortened where segments are too short to fit them.
+    pub fn sample(&self, t: Duration, blend: Duration) -> [f64; 6] {
+        let w = &self.waypoints;
+        match w.len() {
+            0 => return [0.0; 6],
+            1 => return w[0].angles,
+            _ => {}
+        }
+        if t <= w[0].time {
+            return w[0].angles;
+        }
+        if t >= self.duration() {
+            return w[w.len() - 1].angles;
+        }
+        let t = t.as_secs_f64();
+        let time = |i: usize| w[i].time.as_secs_f64();
+        let velocity = |i: usize| {
+            let dt = (time(i + 1) - time(i)).max(1e-9);
+            let mut v = [0.0; 6];
+            for (j, v) in v.iter_mut().enumerate() {
+                *v = (w[i + 1].angles[j] - w[i].angles[j]) / dt;
+            }
+            v
+        };
+        let blend_at = |k: usize| {
+            (blend.as_secs_f64())
+                .min(time(k) - time(k - 1))
+                .min(time(k + 1) - time(k))
+        };
+        // Nearest interior waypoint whose blend window contains t.
+        let seg = (1..w.len()).find(|&i| time(i) > t).unwrap_or(w.len() - 1) - 1;
+        for k in [seg, seg + 1] {
+            if k == 0 || k + 1 >= w.len() {
+                continue;
+            }
+            let tb = blend_at(k);
+            let dt = t - time(k);
+            if tb > 0.0 && dt.abs() < tb / 2.0 {
+                let (v_in, v_out) = (velocity(k - 1), velocity(k));
+                let mut q = [0.0; 6];
+                for j in 0..6 {
+                    let s = dt + tb / 2.0;
+                    q[j] =
+                        w[k].angles[j] + v_in[j] * dt + (v_out[j] - v_in[j]) * s * s / (2.0 * tb);
+                }
+                return q;
+            }
+        }
+        let v = velocity(seg);
+        let dt = t - time(seg);
+        let mut q = w[seg].angles;
+        for (q, v) in q.iter_mut().zip(v.iter()) {
+            *q += v * dt;
+        }
+        q
+    }
+}
+
+/// Per-joint caps in degrees per second and degrees per second squared.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JointMotionLimits {
+    pub max_velocity: [f64; 6],
+    pub max_acceleration: [f64; 6],
+}
+
+impl JointMotionLimits {
+    pub fn uniform(max_velocity: f64, max_acceleration: f64) -> JointMotionLimits {
+        JointMotionLimits {
+            max_velocity: [max_velocity; 6],
+            max_acceleration: [max_acceleration; 6],
+        }
+    }
+    /// Every cap must be finite and positive.
+    pub fn validate(&self) -> Result<()> {
+        let mut caps = self.max_velocity.iter().chain(self.max_acceleration.iter());
+        if caps.all(|&cap| cap.is_finite() && cap > 0.0) {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!(
+                "Joint motion limits must be finite and positive: {:?}",
+                self
+            ))
+        }
+    }
+}
+
+impl Trajectory {
+    /// Stretches segment durations so that no joint exceeds `limits`, never
+    /// shortening a segment. Velocity is checked per segment; acceleration at
+    /// each waypoint from the change between neighbouring segment velocities,
+    /// starting and ending at rest. Fails when `limits` does not validate.
+    pub fn time_scaled(&self, limits: &JointMotionLimits) -> Result<Trajectory> {
+        limits.validate()?;
+        let w = &self.waypoints;
+        if w.len() < 2 {
+            return Ok(self.clone());
+        }
+        let mut dts: Vec<f64> = w
+            .windows(2)
+            .map(|p| {
+                let dt = (p[1].time.max(p[0].time) - p[0].time).as_secs_f64();
+                let min_dt = (0..6)
+                    .map(|j| (p[1].angles[j] - p[0].angles[j]).abs() / limits.max_velocity[j])
+                    .fold(0.0, f64::max);
+                dt.max(min_dt)
+            })
+            .collect();
+        let delta = |i: usize, j: usize| w[i + 1].angles[j] - w[i].angles[j];
+        for _ in 0..100 {
+            let mut changed = false;
+            for k in 0..w.len() {
+                let (before, after) = (k.checked_sub(1), (k < dts.len()).then_some(k));
+                let span = before.map_or(0.0, |i| dts[i]) + after.map_or(0.0, |i| dts[i]);
+                if span <= 0.0 {
+                    continue;
+                }
+                let ratio = (0..6)
+                    .map(|j| {
+                        let v_in = before.map_or(0.0, |i| delta(i, j) / dts[i].max(1e-9));
+                        let v_out = after.map_or(0.0, |i| delta(i, j) / dts[i].max(1e-9));
+                        (v_out - v_in).abs() / (span / 2.0) / limits.max_acceleration[j]
+                    })
+                    .fold(0.0, f64::max);
+                if ratio > 1.0 + 1e-6 {
+                    // Scaling time by s scales acceleration by 1 / s^2.
+                    let s = ratio.sqrt();
+                    for i in [before, after].iter().flatten().copied() {
+                        dts[i] *= s;
+                    }
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+        let mut time = w[0].time;
+        let mut waypoints = vec![w[0].clone()];
+        for (i, dt) in dts.iter().enumerate() {
+            time += Duration::try_from_secs_f64(*dt)
+                .map_err(|e| anyhow::anyhow!("Segment {} cannot be time scaled: {}", i, e))?;
+            waypoints.push(Waypoint {
+                time,
+                ..w[i + 1].clone()
+            });
+        }
+        Ok(Trajectory { waypoints })
+    }
+}
+
+/// Fraction of `duration` covered at `t`; an empty trajectory is already done.
+fn progress(t: Duration, duration: Duration) -> f64 {
+    if duration.is_zero() {
+        1.0
+    } else {
+        (t.as_secs_f64() / duration.as_secs_f64()).min(1.0)
+    }
+}
+
+fn lerp(from: &[f64; 6], to: &[f64; 6], alpha: f64) -> [f64; 6] {
+    JointAngles(*from).lerp(&JointAngles(*to), alpha).0
+}
+
+#[derive(Clone, Debug)]
+pub struct TrajectoryOptions {
+    /// Streaming rate, clamped to 20-50 Hz.
+    pub rate_hz: f64,
+    pub speed: u8,
+    /// Low-pass time constant in seconds applied before replay.
+    pub smoothing: Option<f64>,
+    /// Parabolic blend duration at each interior waypoint.
+    pub blend: Option<Duration>,
+    /// Per-joint caps applied by time-scaling the trajectory before replay.
+    pub limits: Option<JointMotionLimits>,
+    /// Speed for rail moves when waypoints carry a rail position.
+    pub rail_speed: u8,
+    /// Smallest rail change in millimetres worth re-issuing a rail command for.
+    pub rail_resolution: f64,
+}
+
+impl Default for TrajectoryOptions {
+    fn default() -> TrajectoryOptions {
+        TrajectoryOptions {
+            rate_hz: 30.0,
+            speed: 80,
+            smoothing: None,
+            blend: None,
+            limits: None,
+            rail_speed: 80,
+            rail_resolution: 1.0,
+        }
+    }
+}
+
+impl<T: Connection> MyCobotOperator<T> {
+    /// Streams the trajectory with `send_angles` in real time, moving the slide
+    /// rail alongside when waypoints carry a rail position.
+    pub fn execute_trajectory(
+        &mut self,
+        trajectory: &Trajectory,
+        options: &TrajectoryOptions,
+    ) -> Result<()> {
+        self.execute_trajectory_with_control(trajectory, options, &TrajectoryControl::new())?;
+        Ok(())
+    }
+    /// Like `execute_trajectory`, but honours `control`: while paused the
+    /// trajectory clock is frozen and the last target held, so resuming
+    /// continues from the same point of the same segment.
+    pub fn execute_trajectory_with_control(
+        &mut self,
+        trajectory: &Trajectory,
+        options: &TrajectoryOptions,
+        control: &TrajectoryControl,
+    ) -> Result<MoveOutcome> {
+        self.motion_feedback_start();
+        let result = self.stream_trajectory(trajectory, options, control);
+        self.motion_feedback_finish(&result);
+        result
+    }
+    fn stream_trajectory(
+        &mut self,
+        trajectory: &Trajectory,
+        options: &TrajectoryOptions,
+        control: &TrajectoryControl,
+    ) -> Result<MoveOutcome> {
+        let unwrapped;
+        let trajectory = match self.joint_limits() {
+            Some(limits) => {
+                unwrapped = trajectory.unwrapped(limits);
+                &unwrapped
+            }
+            None => trajectory,
+        };
+        let smoothed;
+        let trajectory = match options.smoothing {
+            Some(tau) => {
+                smoothed = trajectory.smoothed(tau);
+                &smoothed
+            }
+            None => trajectory,
+        };
+        let scaled;
+        let trajectory = match &options.limits {
+            Some(limits) => {
+                scaled = trajectory.time_scaled(limits)?;
+                &scaled
+            }
+            None => trajectory,
+        };
+        let blend = options.blend.unwrap_or(Duration::ZERO);
+        let period = stream_period(options.rate_hz);
+        let mut t = Duration::ZERO;
+        let mut last = Instant::now();
+        let mut rail_sent: Option<f64> = None;
+        loop {
+            let tick = Instant::now();
+            if control.is_cancelled() {
+                self.stop()?;
+                return Ok(MoveOutcome::Cancelled);
+            }
+            if !control.is_paused() {
+                t += tick.duration_since(last);
+                let finished = t >= trajectory.duration();
+                if let Some(rail) = trajectory.sample_rail(t) {
+                    let due = rail_sent.is_none_or(|sent| {
+                        (rail - sent).abs() >= options.rail_resolution || (finished && rail != sent)
+                    });
+                    if due {
+                        self.set_rail_position(rail, options.rail_speed)?;
+                        rail_sent = Some(rail);
+                    }
+                }
+                self.send_angles(&trajectory.sample(t, blend), options.speed)?;
+                self.motion_feedback_progress(progress(t, trajectory.duration()));
+                if finished {
+                    return Ok(MoveOutcome::Completed);
+                }
+            }
+            last = tick;
+            let elapsed = tick.elapsed();
+            if elapsed < period {
+                thread::sleep(period - elapsed);
+            }
+        }
+    }
+}
+
+/// Shared pause/resume/cancel flags for a streamed trajectory, cloneable
+/// into other threads (e.g. a UI) while the executor runs.
+#[derive(Clone, Debug, Default)]
+pub struct TrajectoryControl {
+    paused: Arc<AtomicBool>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl TrajectoryControl {
+    pub fn new() -> TrajectoryControl {
+        TrajectoryControl::default()
+    }
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::SeqCst);
+    }
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::SeqCst);
+    }
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+/// Tool speed limits: mm/s along the path and deg/s about the rotation axis.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CartesianSpeed {
+    pub linear: f64,
+    pub angular: f64,
+}
+
+impl Default for CartesianSpeed {
+    fn default() -> CartesianSpeed {
+        CartesianSpeed {
+            linear: 50.0,
+            angular: 30.0,
+        }
+    }
+}
+
+impl CartesianSpeed {
+    /// Time needed to move between two coords without exceeding either limit.
+    pub fn segment_duration(&self, from: &[f64; 6], to: &[f64; 6]) -> Duration {
+        let (mm, deg) = Pose::from_coords(from).distance(&Pose::from_coords(to));
+        let secs = (mm / self.linear).max(deg / self.angular);
+        Duration::from_secs_f64(if secs.is_finite() { secs } else { 0.0 })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CartesianWaypoint {
+    pub time: Duration,
+    pub coords: [f64; 6],
+}
+
+/// Straight-line Cartesian segments with times derived from a `CartesianSpeed`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CartesianPath {
+    pub waypoints: Vec<CartesianWaypoint>,
+}
+
+impl CartesianPath {
+    pub fn plan(points: &[[f64; 6]], speed: &CartesianSpeed) -> Result<CartesianPath> {
+        let mut path = CartesianPath::default();
+        for coords in points {
+            path.push(*coords, speed)?;
+        }
+        Ok(path)
+    }
+    /// Appends a waypoint reached from the previous one at `speed`.
+    pub fn push(&mut self, coords: [f64; 6], speed: &CartesianSpeed) -> Result<()> {
+        if !(speed.linear > 0.0 && speed.angular > 0.0) {
+            return Err(anyhow::anyhow!("Cartesian speeds must be positive"));
+        }
+        let time = match self.waypoints.last() {
+            Some(last) => last.time + speed.segment_duration(&last.coords, &coords),
+            None => Duration::ZERO,
+        };
+        self.waypoints.push(CartesianWaypoint { time, coords });
+        Ok(())
+    }
+    pub fn duration(&self) -> Duration {
+        self.waypoints.last().map_or(Duration::ZERO, |w| w.time)
+    }
+    /// Coords at time `t`, interpolating position linearly and rotation about a fixed axis.
+    pub fn sample(&self, t: Duration) -> Option<[f64; 6]> {
+        let w = &self.waypoints;
+        let first = w.first()?;
+        if t <= first.time {
+            return Some(first.coords);
+        }
+        let i = match w.iter().position(|p| p.time >= t) {
+            Some(i) => i,
+            None => return w.last().map(|p| p.coords),
+        };
+        let (a, b) = (&w[i - 1], &w[i]);
+        let span = (b.time - a.time).as_secs_f64();
+        let s = if span > 0.0 {
+            (t - a.time).as_secs_f64() / span
+        } else {
+            1.0
+        };
+        Some(
+            Pose::from_coords(&a.coords)
+                .interpolate(&Pose::from_coords(&b.coords), s)
+                .to_coords(),
+        )
+    }
+    /// Interpolation targets spaced one streaming period apart, ending on the last waypoint.
+    pub fn steps(&self, rate_hz: f64) -> Vec<[f64; 6]> {
+        let period = stream_period(rate_hz);
+        let count = (self.duration().as_secs_f64() / period.as_secs_f64()).ceil() as u32;
+        (1..=count.max(1))
+            .filter_map(|i| self.sample((period * i).min(self.duration())))
+            .collect()
+    }
+}
+
+/// How a recorded Cartesian path is cleaned up before replay.
+#[derive(Clone, Debug)]
+pub struct ResampleOptions {
+    /// Path length between resampled points, in mm of travel or degrees of
+    /// rotation, whichever is reached first.
+    pub spacing_mm: f64,
+    pub spacing_deg: f64,
+    /// Samples within both of these of the last kept sample are dropped,
+    /// removing the pauses in a recording.
+    pub stationary_mm: f64,
+    pub stationary_deg: f64,
+}
+
+impl Default for ResampleOptions {
+    fn default() -> ResampleOptions {
+        ResampleOptions {
+            spacing_mm: 5.0,
+            spacing_deg: 5.0,
+            stationary_mm: 0.5,
+            stationary_deg: 0.5,
+        }
+    }
+}
+
+impl CartesianPath {
+    /// Plans a path through coords sampled by `record_coords`, dropping
+    /// stationary periods and respacing the points evenly along the path so
+    /// the replay speed is set by `speed` rather than by the recording.
+    pub fn from_recording(
+        samples: &[[f64; 6]],
+        options: &ResampleOptions,
+        speed: &CartesianSpeed,
+    ) -> Result<CartesianPath> {
+        if !(options.spacing_mm > 0.0 && options.spacing_deg > 0.0) {
+            return Err(anyhow::anyhow!("Resample spacing must be positive"));
+        }
+        let mut kept: Vec<Pose> = Vec::new();
+        for coords in samples {
+            let pose = Pose::from_coords(coords);
+            let moved = kept.last().is_none_or(|last| {
+                let (mm, deg) = last.distance(&pose);
+                mm > options.stationary_mm || deg > options.stationary_deg
+            });
+            if moved {
+                kept.push(pose);
+            }
+        }
+        // Path parameter in units of spacing, so resampled points are one apart.
+        let mut length = vec![0.0];
+        for pair in kept.windows(2) {
+            let (mm, deg) = pair[0].distance(&pair[1]);
+            let step = (mm / options.spacing_mm).max(deg / options.spacing_deg);
+            length.push(length[length.len() - 1] + step);
+        }
+        let total = length.last().copied().unwrap_or(0.0);
+        let mut points = Vec::new();
+        let mut segment = 0;
+        let mut s = 0.0;
+        while s < total {
+            while length[segment + 1] < s {
+                segment += 1;
+            }
+            let span = length[segment + 1] - length[segment];
+            let f = if span > 0.0 {
+                (s - length[segment]) / span
+            } else {
+                0.0
+            };
+            points.push(kept[segment].interpolate(&kept[segment + 1], f).to_coords());
+            s += 1.0;
+        }
+        if let Some(last) = kept.last() {
+            points.push(last.to_coords());
+        }
+        CartesianPath::plan(&points, speed)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CartesianMoveOptions {
+    /// Streaming rate, clamped to 20-50 Hz.
+    pub rate_hz: f64,
+    /// Firmware speed byte for each streamed step; high so the arm keeps up with the timing.
+    pub speed: u8,
+}
+
+impl Default for CartesianMoveOptions {
+    fn default() -> CartesianMoveOptions {
+        CartesianMoveOptions {
+            rate_hz: 30.0,
+            speed: 100,
+        }
+    }
+}
+
+impl<T: Connection> MyCobotOperator<T> {
+    /// Streams the path's interpolation steps with linear `send_coords`, one per period.
+    pub fn execute_cartesian_path(
+        &mut self,
+        path: &CartesianPath,
+        options: &CartesianMoveOptions,
+    ) -> Result<()> {
+        self.execute_cartesian_path_with_control(path, options, &TrajectoryControl::new())?;
+        Ok(())
+    }
+    /// Like `execute_cartesian_path`; pausing holds the current step and resuming continues from it.
+    pub fn execute_cartesian_path_with_control(
+        &mut self,
+        path: &CartesianPath,
+        options: &CartesianMoveOptions,
+        control: &TrajectoryControl,
+    ) -> Result<MoveOutcome> {
+        self.motion_feedback_start();
+        let result = self.stream_cartesian_path(path, options, control);
+        self.motion_feedback_finish(&result);
+        result
+    }
+    fn stream_cartesian_path(
+        &mut self,
+        path: &CartesianPath,
+        options: &CartesianMoveOptions,
+        control: &TrajectoryControl,
+    ) -> Result<MoveOutcome> {
+        let period = stream_period(options.rate_hz);
+        let steps = path.steps(options.rate_hz);
+        let mut next = 0;
+        while next < steps.len() {
+            let tick = Instant::now();
+            if control.is_cancelled() {
+                self.stop()?;
+                return Ok(MoveOutcome::Cancelled);
+            }
+            if !control.is_paused() {
+                self.send_coords(&steps[next], options.speed, Mode::Linear)?;
+                next += 1;
+                self.motion_feedback_progress(next as f64 / steps.len() as f64);
+            }
+            let elapsed = tick.elapsed();
+            if elapsed < period {
+                thread::sleep(period - elapsed);
+            }
+        }
+        Ok(MoveOutcome::Completed)
+    }
+    /// Samples `get_coords` every `period` until `control` is cancelled,
+    /// skipping samples while it is paused. Pair with
+    /// `CartesianPath::from_recording` for replay.
+    pub fn record_coords(
+        &mut self,
+        period: Duration,
+        control: &TrajectoryControl,
+    ) -> Result<Vec<[f64; 6]>> {
+        let mut samples = Vec::new();
+        while !control.is_cancelled() {
+            let tick = Instant::now();
+            if !control.is_paused() {
+                samples.push(self.get_coords()?);
+            }
+            let elapsed = tick.elapsed();
+            if elapsed < period {
+                thread::sleep(period - elapsed);
+            }
+        }
+        Ok(samples)
+    }
+    /// Moves in a straight line from the current coords to `target` at the given tool speed.
+    pub fn move_linear(
+        &mut self,
+        target: &[f64; 6],
+        speed: &CartesianSpeed,
+        options: &CartesianMoveOptions,
+    ) -> Result<()> {
+        let start = self.get_coords()?;
+        let path = CartesianPath::plan(&[start, *target], speed)?;
+        self.execute_cartesian_path(&path, options)
+    }
+}
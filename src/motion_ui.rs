@@ -0,0 +1,144 @@
+//! Progress and outcome feedback on the ATOM while the streaming executors
+//! run. A `MotionUiFeedback` set on the operator is told when a trajectory
+//! or Cartesian path starts, how far it has got, and how it ended, and draws
+//! that on the ATOM through `AtomDisplay`.
+
+use super::io::Connection;
+use super::led::Rgb;
+use super::motion::MoveOutcome;
+use super::operator::MyCobotOperator;
+use anyhow::Result;
+
+/// What feedback may draw on: the ATOM LED color.
+pub trait AtomDisplay {
+    fn show_color(&mut self, color: Rgb) -> Result<()>;
+}
+
+impl<T: Connection> AtomDisplay for MyCobotOperator<T> {
+    fn show_color(&mut self, color: Rgb) -> Result<()> {
+        self.set_color(color.r, color.g, color.b)
+    }
+}
+
+/// Called by `execute_trajectory` and `execute_cartesian_path` (and their
+/// `_with_control` variants). `on_progress` runs on every streamed tick, so
+/// implementations should only write when what they show changes. Errors
+/// returned from any hook are ignored so they cannot stop or fail the motion.
+pub trait MotionUiFeedback: Send {
+    fn on_start(&mut self, display: &mut dyn AtomDisplay) -> Result<()> {
+        let _ = display;
+        Ok(())
+    }
+    /// `fraction` runs from 0 to 1 and does not advance while paused.
+    fn on_progress(&mut self, display: &mut dyn AtomDisplay, fraction: f64) -> Result<()>;
+    /// Also called when the executor failed, with `MoveOutcome::Failed`.
+    fn on_finish(&mut self, display: &mut dyn AtomDisplay, outcome: &MoveOutcome) -> Result<()>;
+}
+
+/// Splits the motion into `segments` steps and shows each one as a color
+/// between `from` and `to`, then a solid color for the outcome.
+#[derive(Clone, Debug)]
+pub struct ProgressLed {
+    pub segments: u8,
+    pub from: Rgb,
+    pub to: Rgb,
+    pub completed: Rgb,
+    pub cancelled: Rgb,
+    pub failed: Rgb,
+    shown: Option<u8>,
+}
+
+impl Default for ProgressLed {
+    fn default() -> ProgressLed {
+        ProgressLed {
+            segments: 10,
+            from: Rgb::BLUE,
+            to: Rgb::GREEN,
+            completed: Rgb::GREEN,
+            cancelled: Rgb::YELLOW,
+            failed: Rgb::RED,
+            shown: None,
+        }
+    }
+}
+
+impl ProgressLed {
+    pub fn new(segments: u8) -> ProgressLed {
+        ProgressLed {
+            segments: segments.max(1),
+            ..ProgressLed::default()
+        }
+    }
+    pub fn colors(mut self, from: Rgb, to: Rgb) -> ProgressLed {
+        self.from = from;
+        self.to = to;
+        self
+    }
+    fn segment(&self, fraction: f64) -> u8 {
+        let segments = self.segments.max(1);
+        ((fraction.clamp(0.0, 1.0) * segments as f64).floor() as u8).min(segments)
+    }
+}
+
+impl MotionUiFeedback for ProgressLed {
+    fn on_start(&mut self, display: &mut dyn AtomDisplay) -> Result<()> {
+        self.shown = None;
+        self.on_progress(display, 0.0)
+    }
+    fn on_progress(&mut self, display: &mut dyn AtomDisplay, fraction: f64) -> Result<()> {
+        let segment = self.segment(fraction);
+        if self.shown == Some(segment) {
+            return Ok(());
+        }
+        let t = segment as f64 / self.segments.max(1) as f64;
+        let color = self.from.lerp(&self.to, t);
+        display.show_color(color)?;
+        self.shown = Some(segment);
+        Ok(())
+    }
+    fn on_finish(&mut self, display: &mut dyn AtomDisplay, outcome: &MoveOutcome) -> Result<()> {
+        self.shown = None;
+        let color = match outcome {
+            MoveOutcome::Completed => self.completed,
+            MoveOutcome::Cancelled => self.cancelled,
+            MoveOutcome::TimedOut | MoveOutcome::Failed(_) => self.failed,
+        };
+        display.show_color(color)
+    }
+}
+
+impl<T: Connection> MyCobotOperator<T> {
+    /// Feedback for the streaming executors; `None` turns it off.
+    pub fn set_motion_feedback(&mut self, feedback: Option<Box<dyn MotionUiFeedback>>) {
+        self.motion_feedback = feedback;
+    }
+    /// Feedback errors are ignored here and in the other hooks: a lost LED
+    /// frame must never abort a motion partway through.
+    pub(crate) fn motion_feedback_start(&mut self) {
+        let _ = self.with_motion_feedback(|feedback, display| feedback.on_start(display));
+    }
+    pub(crate) fn motion_feedback_progress(&mut self, fraction: f64) {
+        let _ =
+            self.with_motion_feedback(|feedback, display| feedback.on_progress(display, fraction));
+    }
+    pub(crate) fn motion_feedback_finish(&mut self, result: &Result<MoveOutcome>) {
+        let outcome = match result {
+            Ok(outcome) => outcome.clone(),
+            Err(e) => MoveOutcome::Failed(e.to_string()),
+        };
+        let _ =
+            self.with_motion_feedback(|feedback, display| feedback.on_finish(display, &outcome));
+    }
+    fn with_motion_feedback<F>(&mut self, f: F) -> Result<()>
+    where
+        F: FnOnce(&mut dyn MotionUiFeedback, &mut dyn AtomDisplay) -> Result<()>,
+    {
+        let mut feedback = match self.motion_feedback.take() {
+            Some(feedback) => feedback,
+            None => return Ok(()),
+        };
+        let result = f(feedback.as_mut(), self);
+        self.motion_feedback = Some(feedback);
+        result
+    }
+}
@@ -0,0 +1,111 @@
+use super::io::Connection;
+use super::operator::MyCobotOperator;
+use super::transform::{mat_mul, mat_vec, transpose, Matrix3, Pose};
+use anyhow::Result;
+
+/// How the arm is mounted relative to the frame the application works in.
+///
+/// `axes[i]` names the robot base axis that logical axis `i` (x, y, z) reads
+/// from, 1-based and negative when flipped: `[1, -2, -3]` is an arm hanging
+/// upside down from the ceiling. Rotations are remapped the same way, so tool
+/// orientations stay in the logical frame too. Negated joints report and
+/// accept angles with the opposite sign.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MountingProfile {
+    axes: [i8; 3],
+    negate_joints: [bool; 6],
+}
+
+impl Default for MountingProfile {
+    fn default() -> MountingProfile {
+        MountingProfile::upright()
+    }
+}
+
+impl MountingProfile {
+    pub fn new(axes: [i8; 3], negate_joints: [bool; 6]) -> Result<MountingProfile> {
+        let mut seen = [false; 3];
+        for &a in &axes {
+            let index = (a.unsigned_abs() as usize).wrapping_sub(1);
+            if index >= 3 || seen[index] {
+                return Err(anyhow::anyhow!(
+                    "Mounting axes {:?} are not a signed permutation of 1, 2, 3",
+                    axes
+                ));
+            }
+            seen[index] = true;
+        }
+        Ok(MountingProfile {
+            axes,
+            negate_joints,
+        })
+    }
+    pub fn upright() -> MountingProfile {
+        MountingProfile {
+            axes: [1, 2, 3],
+            negate_joints: [false; 6],
+        }
+    }
+    /// Hanging from the ceiling, rotated half a turn about the base x axis.
+    pub fn inverted() -> MountingProfile {
+        MountingProfile {
+            axes: [1, -2, -3],
+            negate_joints: [false; 6],
+        }
+    }
+    pub fn axes(&self) -> [i8; 3] {
+        self.axes
+    }
+    pub fn negate_joints(&self) -> [bool; 6] {
+        self.negate_joints
+    }
+    /// Maps robot base vectors to logical ones.
+    fn matrix(&self) -> Matrix3 {
+        let mut m = [[0.0; 3]; 3];
+        for (row, &a) in m.iter_mut().zip(self.axes.iter()) {
+            row[a.unsigned_abs() as usize - 1] = a.signum() as f64;
+        }
+        m
+    }
+    /// Joint negation is its own inverse, so this also maps logical angles to robot ones.
+    pub fn map_angles(&self, degrees: &[f64; 6]) -> [f64; 6] {
+        let mut out = *degrees;
+        for (d, &negate) in out.iter_mut().zip(self.negate_joints.iter()) {
+            if negate {
+                *d = -*d;
+            }
+        }
+        out
+    }
+    pub fn pose_to_logical(&self, pose: &Pose) -> Pose {
+        let m = self.matrix();
+        Pose::new(
+            mat_vec(&m, &pose.position),
+            mat_mul(&mat_mul(&m, &pose.rotation), &transpose(&m)),
+        )
+    }
+    pub fn pose_to_robot(&self, pose: &Pose) -> Pose {
+        let m = transpose(&self.matrix());
+        Pose::new(
+            mat_vec(&m, &pose.position),
+            mat_mul(&mat_mul(&m, &pose.rotation), &transpose(&m)),
+        )
+    }
+}
+
+impl<T: Connection> MyCobotOperator<T> {
+    pub fn mounting(&self) -> Option<&MountingProfile> {
+        self.mounting.as_ref()
+    }
+    /// Once set, angle and coord commands and readbacks are in the logical
+    /// frame of `mounting`. Raw APIs and joint limits stay in the robot's frame.
+    pub fn set_mounting(&mut self, mounting: Option<MountingProfile>) {
+        self.mounting = mounting;
+    }
+    pub(crate) fn map_mounted_angles(&self, degrees: &[f64; 6]) -> [f64; 6] {
+        match &self.mounting {
+            Some(mounting) => mounting.map_angles(degrees),
+            None => *degrees,
+        }
+    }
+}
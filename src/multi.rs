@@ -0,0 +1,88 @@
+use super::io::{Connection, Serial};
+use super::operator::MyCobotOperator;
+use anyhow::Result;
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub struct MultiRobot<T: Connection> {
+    robots: Vec<MyCobotOperator<T>>,
+}
+
+impl<T: Connection + Send> MultiRobot<T> {
+    pub fn from_operators(robots: Vec<MyCobotOperator<T>>) -> MultiRobot<T> {
+        MultiRobot { robots }
+    }
+    pub fn add(&mut self, robot: MyCobotOperator<T>) -> usize {
+        self.robots.push(robot);
+        self.robots.len() - 1
+    }
+    pub fn len(&self) -> usize {
+        self.robots.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.robots.is_empty()
+    }
+    pub fn robot(&mut self, index: usize) -> Option<&mut MyCobotOperator<T>> {
+        self.robots.get_mut(index)
+    }
+    pub fn for_each<R, F>(&mut self, f: F) -> Vec<Result<R>>
+    where
+        R: Send,
+        F: Fn(usize, &mut MyCobotOperator<T>) -> Result<R> + Sync,
+    {
+        let f = &f;
+        thread::scope(|s| {
+            let handles = self
+                .robots
+                .iter_mut()
+                .enumerate()
+                .map(|(i, robot)| s.spawn(move || f(i, robot)))
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .map(|h| {
+                    h.join()
+                        .unwrap_or_else(|_| Err(anyhow::anyhow!("Robot thread panicked")))
+                })
+                .collect()
+        })
+    }
+    pub fn send_angles_all(&mut self, degrees: &[[f64; 6]], speed: u8) -> Result<Vec<Result<()>>> {
+        if degrees.len() != self.robots.len() {
+            return Err(anyhow::anyhow!(
+                "Expected {} targets, got {}",
+                self.robots.len(),
+                degrees.len()
+            ));
+        }
+        Ok(self.for_each(|i, robot| robot.send_angles(&degrees[i], speed)))
+    }
+    pub fn stop_all(&mut self) -> Vec<Result<()>> {
+        self.for_each(|_, robot| robot.stop())
+    }
+    pub fn wait_all_idle(&mut self, timeout_secs: f64) -> Vec<Result<bool>> {
+        let start = Instant::now();
+        self.for_each(|_, robot| {
+            while start.elapsed().as_secs_f64() < timeout_secs {
+                if robot.is_moving()? == 0 {
+                    return Ok(true);
+                }
+                thread::sleep(Duration::from_millis(100));
+            }
+            Ok(false)
+        })
+    }
+}
+
+pub type MultiRobotSerial = MultiRobot<Serial>;
+
+impl MultiRobotSerial {
+    /// Opens every port, failing on the first one that cannot be opened.
+    pub fn new(ports: &[&str], baudrate: u32) -> Result<MultiRobotSerial> {
+        let robots = ports
+            .iter()
+            .map(|port| Serial::open(port, baudrate).map(MyCobotOperator::from_connection))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(MultiRobot::from_operators(robots))
+    }
+}
@@ -0,0 +1,66 @@
+//! Frames the firmware sends on its own, such as ATOM button presses and
+//! error reports. Once subscribed, frames that don't answer the pending
+//! request are forwarded to the subscriber instead of being discarded with
+//! the stale input before each query.
+
+use super::common::Command;
+use super::io::Connection;
+use super::operator::MyCobotOperator;
+use super::tap::{Frame, FrameDirection};
+use anyhow::Result;
+use std::sync::mpsc::{self, Receiver};
+
+impl<T: Connection> MyCobotOperator<T> {
+    /// Routes unsolicited frames to the returned channel, replacing any
+    /// earlier subscriber. While subscribed, each query first reads pending
+    /// input instead of purging it, which costs up to one read timeout.
+    pub fn subscribe_unsolicited(&mut self) -> Receiver<Frame> {
+        let (sender, receiver) = mpsc::channel();
+        self.unsolicited = Some(sender);
+        receiver
+    }
+    pub fn unsubscribe_unsolicited(&mut self) {
+        self.unsolicited = None;
+    }
+    /// Reads whatever arrived since the last request and forwards its frames
+    /// to the subscriber; returns how many were forwarded. Call it while idle
+    /// so notifications aren't held until the next query.
+    pub fn poll_unsolicited(&mut self) -> Result<usize> {
+        let pending = self.connection_mut().read()?;
+        Ok(self.route_unsolicited(&pending, None))
+    }
+    /// Clears input left over from earlier requests, forwarding it first when subscribed.
+    pub(crate) fn purge_input(&mut self) -> Result<()> {
+        if self.unsolicited.is_some() {
+            self.poll_unsolicited()?;
+            Ok(())
+        } else {
+            self.connection_mut().clear_input_buffer()
+        }
+    }
+    /// Forwards the frames in `data` except the first reply to `answered`.
+    pub(crate) fn route_unsolicited(&mut self, data: &[u8], answered: Option<Command>) -> usize {
+        let sender = match &self.unsolicited {
+            Some(sender) => sender,
+            None => return 0,
+        };
+        let mut answer = answered.map(Command::as_u8);
+        let mut forwarded = 0;
+        let mut disconnected = false;
+        for frame in Frame::parse(FrameDirection::Received, data) {
+            if answer == Some(frame.genre) {
+                answer = None;
+                continue;
+            }
+            if sender.send(frame).is_err() {
+                disconnected = true;
+                break;
+            }
+            forwarded += 1;
+        }
+        if disconnected {
+            self.unsolicited = None;
+        }
+        forwarded
+    }
+}
@@ -1,14 +1,52 @@
+use super::angles::JointAngles;
+use super::backlash::BacklashCompensation;
 use super::common::*;
-use super::io::{Connection, Serial};
+use super::conversion::*;
+use super::dry_run::DryRun;
+use super::errors::RobotErrorReport;
+use super::estop::EmergencyStop;
+use super::io::{Connection, ReadTimeouts, Serial};
+use super::kinematics::KinematicModel;
+use super::limits::{JointLimits, Workspace};
+use super::motion::{AngleMoveResult, CoordMoveResult};
+use super::motion_ui::MotionUiFeedback;
+use super::mounting::MountingProfile;
+use super::poses::PoseLibrary;
+use super::profile::{ProtocolProfile, RobotInfo};
+use super::protocol::Parser;
+use super::rail::RailAxis;
+use super::stats::OperatorStats;
+use super::tap::Frame;
+use super::thermal::ThermalGuard;
+use super::transform::{EulerOrder, Pose, Quaternion};
 use super::utils::*;
 use anyhow::{Error, Result};
-use std::convert::TryInto;
+use std::collections::BTreeMap;
+use std::convert::{TryFrom, TryInto};
 use std::marker::PhantomData;
+use std::sync::mpsc::Sender;
 use std::thread;
 use std::time::{Duration, Instant};
 
 pub struct MyCobotOperator<T: Connection> {
     connection: T,
+    profile: ProtocolProfile,
+    poses: PoseLibrary,
+    tool: Option<Pose>,
+    dry_run: Option<DryRun>,
+    stats: OperatorStats,
+    joint_limits: Option<JointLimits>,
+    batch: Option<Vec<u8>>,
+    read_timeouts: Option<ReadTimeouts>,
+    read_timeout: Option<Duration>,
+    pub(crate) estop: EmergencyStop,
+    pub(crate) mounting: Option<MountingProfile>,
+    workspace: Option<Workspace>,
+    pub(crate) unsolicited: Option<Sender<Frame>>,
+    pub(crate) backlash: Option<BacklashCompensation>,
+    pub(crate) thermal: Option<ThermalGuard>,
+    pub(crate) motion_feedback: Option<Box<dyn MotionUiFeedback>>,
+    pub(crate) rail: Option<Box<dyn RailAxis>>,
     _marker: PhantomData<fn() -> T>,
 }
 
@@ -16,326 +54,1165 @@ impl<T: Connection> MyCobotOperator<T> {
     pub fn from_connection(connection: T) -> MyCobotOperator<T> {
         MyCobotOperator {
             connection,
+            profile: ProtocolProfile::default(),
+            poses: PoseLibrary::new(),
+            tool: None,
+            dry_run: None,
+            stats: OperatorStats::default(),
+            joint_limits: None,
+            batch: None,
+            read_timeouts: None,
+            read_timeout: None,
+            estop: EmergencyStop::default(),
+            mounting: None,
+            workspace: None,
+            unsolicited: None,
+            backlash: None,
+            thermal: None,
+            motion_feedback: None,
+            rail: None,
             _marker: PhantomData,
         }
     }
-    fn concat_message(genre: u8, command_data: &[u8]) -> Vec<u8> {
+    /// Creates an operator and configures it from the firmware's version replies.
+    /// Soft joint limits are loaded from the firmware when it reports them, and
+    /// the encoder resolution is detected when the servos answer.
+    pub fn connect(connection: T) -> Result<MyCobotOperator<T>> {
+        let mut operator = MyCobotOperator::from_connection(connection);
+        operator.negotiate_protocol()?;
+        if let Ok(limits) = operator.read_joint_limits() {
+            operator.joint_limits = Some(limits);
+        }
+        let _ = operator.detect_encoder_resolution();
+        Ok(operator)
+    }
+    fn concat_message(genre: Command, command_data: &[u8]) -> Vec<u8> {
         let len = 2 + command_data.len();
-        let header = [Command::HEADER, Command::HEADER, len as u8, genre];
+        let header = [Command::HEADER, Command::HEADER, len as u8, genre.as_u8()];
         [&header[..], command_data, &[Command::FOOTER]].concat()
     }
-    fn is_frame_header(data: &[u8], pos: usize) -> bool {
-        data[pos] == Command::HEADER && data[pos + 1] == Command::HEADER
-    }
-    fn process_received(data: &[u8], genre: u8) -> Vec<i16> {
-        if data.is_empty() {
-            return Vec::new();
-        }
-        let some_idx =
-            (0..(data.len() - 1)).position(|i| MyCobotOperator::<T>::is_frame_header(data, i));
-        if let Some(idx) = some_idx {
-            let data_len = (data[idx + 2] - 2) as usize;
-            let cmd_id = data[idx + 3];
-            if cmd_id != genre {
-                return Vec::new();
+    pub(crate) fn build_frame(&self, genre: Command, command_data: &[u8]) -> Result<Vec<u8>> {
+        if let Some(len) = genre.request_len() {
+            if len != command_data.len() {
+                return Err(anyhow::anyhow!(
+                    "{} expects {} bytes of data, got {}",
+                    genre,
+                    len,
+                    command_data.len()
+                ));
             }
-            let data_pos = idx + 4;
-            let valid_data = &data[data_pos..(data_pos + data_len)];
-            match data_len {
-                12 => decode_int16_vec(valid_data),
-                2 => {
-                    if genre == Command::IS_SERVO_ENABLE {
-                        [decode_int8(&valid_data[1..2]) as i16].to_vec()
-                    } else {
-                        [decode_int16(valid_data)].to_vec()
-                    }
+        }
+        if !self.profile.checksum {
+            return Ok(MyCobotOperator::<T>::concat_message(genre, command_data));
+        }
+        let len = 4 + command_data.len();
+        let crc = crc16(&[&[genre.as_u8()], command_data].concat());
+        let header = [Command::HEADER, Command::HEADER, len as u8, genre.as_u8()];
+        Ok([
+            &header[..],
+            command_data,
+            &crc.to_be_bytes()[..],
+            &[Command::FOOTER],
+        ]
+        .concat())
+    }
+    /// Decodes the first frame of `genre` in `data`, skipping noise and
+    /// stale replies to other genres. With `checksum`, frames whose CRC does
+    /// not match are skipped too.
+    fn process_received(
+        data: &[u8],
+        genre: Command,
+        checksum: bool,
+        joint_count: usize,
+    ) -> Vec<i16> {
+        let per_joint = matches!(
+            genre,
+            Command::GetAngles | Command::GetCoords | Command::GetEncoders
+        );
+        let frames = Parser::new().push_bytes(data);
+        frames
+            .iter()
+            .filter(|frame| frame.genre == genre.as_u8())
+            .find_map(|frame| {
+                if checksum {
+                    frame.checked_data()
+                } else {
+                    Some(&frame.payload[..])
                 }
-                _ => [decode_int8(valid_data) as i16].to_vec(),
-            }
-        } else {
-            Vec::new()
+            })
+            .map_or_else(Vec::new, |payload| {
+                if per_joint && joint_count < 6 {
+                    decode_joint_values(payload, joint_count)
+                } else {
+                    decode_layout(genre.response(), payload)
+                }
+            })
+    }
+    /// The underlying connection.
+    pub fn connection(&self) -> &T {
+        &self.connection
+    }
+    /// Frames written directly to the connection bypass dry run and statistics.
+    pub fn connection_mut(&mut self) -> &mut T {
+        &mut self.connection
+    }
+    /// Swaps in a new transport, e.g. after the port re-enumerated, keeping
+    /// the profile, limits, tool, poses, e-stop latch and subscribers. Returns
+    /// the old connection.
+    pub fn replace_connection(&mut self, connection: T) -> T {
+        // The new connection has its own timeout, so re-apply ours on the next query.
+        self.read_timeout = None;
+        std::mem::replace(&mut self.connection, connection)
+    }
+    /// Moves the operator's state onto a connection of another type, such as
+    /// `Box<dyn Connection + Send>` so that later `replace_connection` calls
+    /// can switch between USB, TCP and Bluetooth.
+    pub fn map_connection<U, F>(self, f: F) -> MyCobotOperator<U>
+    where
+        U: Connection,
+        F: FnOnce(T) -> U,
+    {
+        MyCobotOperator {
+            connection: f(self.connection),
+            profile: self.profile,
+            poses: self.poses,
+            tool: self.tool,
+            dry_run: self.dry_run,
+            stats: self.stats,
+            joint_limits: self.joint_limits,
+            batch: self.batch,
+            read_timeouts: self.read_timeouts,
+            read_timeout: None,
+            estop: self.estop,
+            mounting: self.mounting,
+            workspace: self.workspace,
+            unsolicited: self.unsolicited,
+            backlash: self.backlash,
+            thermal: self.thermal,
+            motion_feedback: self.motion_feedback,
+            rail: self.rail,
+            _marker: PhantomData,
         }
     }
-    fn write_command(&mut self, genre: u8, command_data: &[u8]) -> Result<()> {
-        let command = MyCobotOperator::<T>::concat_message(genre, command_data);
+    /// Decodes the first frame of `genre` in `res` as its reply.
+    pub(crate) fn decode_reply(&self, genre: Command, res: &[u8]) -> Vec<i16> {
+        MyCobotOperator::<T>::process_received(
+            res,
+            genre,
+            self.profile.checksum,
+            self.profile.joint_count,
+        )
+    }
+    /// Starts collecting written frames instead of sending them.
+    pub(crate) fn begin_batch(&mut self) {
+        self.batch = Some(Vec::new());
+    }
+    /// Stops collecting and returns the frames collected since `begin_batch`.
+    pub(crate) fn end_batch(&mut self) -> Vec<u8> {
+        self.batch.take().unwrap_or_default()
+    }
+    pub(crate) fn write_command(&mut self, genre: Command, command_data: &[u8]) -> Result<()> {
+        self.check_estop(genre)?;
+        self.check_thermal_pause(genre)?;
+        self.track_backlash(genre);
+        let command = self.build_frame(genre, command_data)?;
+        if let Some(dry_run) = &mut self.dry_run {
+            return dry_run.write(genre, command_data);
+        }
+        self.stats.record_sent(genre);
+        if let Some(batch) = &mut self.batch {
+            batch.extend(command);
+            return Ok(());
+        }
         self.connection.write(&command)
     }
-    fn write_command_and_receive(&mut self, genre: u8, command_data: &[u8]) -> Result<Vec<i16>> {
-        let command = MyCobotOperator::<T>::concat_message(genre, command_data);
+    pub(crate) fn write_command_and_receive(
+        &mut self,
+        genre: Command,
+        command_data: &[u8],
+    ) -> Result<Vec<i16>> {
+        let command = self.build_frame(genre, command_data)?;
+        if self.batch.is_some() {
+            return Err(anyhow::anyhow!(
+                "{} expects a reply and cannot be batched",
+                genre
+            ));
+        }
+        if let Some(res) = self
+            .dry_run
+            .as_ref()
+            .and_then(|d| d.query(genre, command_data))
+        {
+            return Ok(res);
+        }
+        self.apply_read_timeout(genre)?;
+        self.purge_input()?;
+        self.stats.record_sent(genre);
+        let start = Instant::now();
         let res = self.connection.write_and_read(&command)?;
-        Ok(MyCobotOperator::<T>::process_received(&res, genre))
+        self.route_unsolicited(&res, Some(genre));
+        let decoded = self.decode_reply(genre, &res);
+        let replied = !res.is_empty() || genre.response().is_empty();
+        self.stats
+            .record_round_trip(genre, start.elapsed(), replied);
+        Ok(decoded)
+    }
+    /// Uses per-genre read timeouts for queries from now on; `None` leaves
+    /// the connection's timeout as it is.
+    pub fn set_read_timeouts(&mut self, timeouts: Option<ReadTimeouts>) {
+        self.read_timeouts = timeouts;
+        self.read_timeout = None;
+    }
+    pub fn read_timeouts(&self) -> Option<&ReadTimeouts> {
+        self.read_timeouts.as_ref()
+    }
+    fn apply_read_timeout(&mut self, genre: Command) -> Result<()> {
+        let timeout = match &self.read_timeouts {
+            Some(timeouts) => timeouts.timeout_for(genre),
+            None => return Ok(()),
+        };
+        if self.read_timeout != Some(timeout) {
+            self.connection.set_read_timeout(timeout)?;
+            self.read_timeout = Some(timeout);
+        }
+        Ok(())
+    }
+    /// Sends any genre with arguments encoded per its request layout and
+    /// returns the reply decoded per its response layout (empty for write-only genres).
+    pub fn call(&mut self, genre: Command, args: &[i32]) -> Result<Vec<i16>> {
+        let command_data = encode_layout(genre.request(), args)?;
+        if genre.response().is_empty() {
+            self.write_command(genre, &command_data)?;
+            Ok(Vec::new())
+        } else {
+            self.write_command_and_receive(genre, &command_data)
+        }
     }
     pub fn version(&mut self) -> Result<String> {
-        let command = MyCobotOperator::<T>::concat_message(Command::VERSION, &Vec::<u8>::new());
+        let command = self.build_frame(Command::Version, &[])?;
+        self.apply_read_timeout(Command::Version)?;
+        self.purge_input()?;
         let res = self.connection.write_and_read(&command)?;
         let version = res.iter().map(|&s| s as char).collect::<String>();
         Ok(version)
     }
+    /// Sends an inexpensive query and returns the round-trip time. Fails when
+    /// no reply arrives within the connection's read timeout, which tells a
+    /// robot that is gone apart from one that is merely busy moving.
+    pub fn ping(&mut self) -> Result<Duration> {
+        let start = Instant::now();
+        let res = self.write_command_and_receive(Command::GetRobotVersion, &[])?;
+        if res.is_empty() {
+            return Err(anyhow::anyhow!("No reply to ping"));
+        }
+        Ok(start.elapsed())
+    }
+    pub fn get_robot_version(&mut self) -> Result<Option<f64>> {
+        let res = self.write_command_and_receive(Command::GetRobotVersion, &[])?;
+        Ok(res.first().map(|&v| v as f64 / 10.0))
+    }
+    pub fn get_system_version(&mut self) -> Result<Option<f64>> {
+        let res = self.write_command_and_receive(Command::GetSystemVersion, &[])?;
+        Ok(res.first().map(|&v| v as f64 / 10.0))
+    }
+    pub fn get_robot_id(&mut self) -> Result<i32> {
+        let res = self.write_command_and_receive(Command::GetRobotId, &[])?;
+        Ok(if res.is_empty() { -1 } else { res[0] as i32 })
+    }
+    pub fn set_robot_id(&mut self, id: u8) -> Result<()> {
+        self.write_command(Command::SetRobotId, &[id])
+    }
+    pub fn get_robot_info(&mut self) -> Result<RobotInfo> {
+        let robot_id = self.get_robot_id()?;
+        Ok(RobotInfo {
+            robot_version: self.get_robot_version()?,
+            system_version: self.get_system_version()?,
+            atom_version: self.get_atom_version()?,
+            robot_id: if robot_id == -1 { None } else { Some(robot_id) },
+        })
+    }
+    pub fn get_atom_version(&mut self) -> Result<Option<f64>> {
+        let res = self.write_command_and_receive(Command::GetAtomVersion, &[])?;
+        Ok(res.first().map(|&v| v as f64 / 10.0))
+    }
+    pub fn get_basic_version(&mut self) -> Result<Option<f64>> {
+        let res = self.write_command_and_receive(Command::GetBasicVersion, &[])?;
+        Ok(res.first().map(|&v| v as f64 / 10.0))
+    }
+    pub fn negotiate_protocol(&mut self) -> Result<&ProtocolProfile> {
+        self.profile = ProtocolProfile::default();
+        let version = self.version()?;
+        let atom_version = self.get_atom_version()?;
+        let basic_version = self.get_basic_version()?;
+        self.profile = ProtocolProfile::detect(version.trim(), atom_version, basic_version);
+        Ok(&self.profile)
+    }
+    pub fn protocol_profile(&self) -> &ProtocolProfile {
+        &self.profile
+    }
+    pub fn set_protocol_profile(&mut self, profile: ProtocolProfile) {
+        self.profile = profile;
+    }
+    pub fn pose_library(&self) -> &PoseLibrary {
+        &self.poses
+    }
+    pub fn pose_library_mut(&mut self) -> &mut PoseLibrary {
+        &mut self.poses
+    }
+    pub fn set_pose_library(&mut self, poses: PoseLibrary) {
+        self.poses = poses;
+    }
+    /// Tool center point in the flange frame, or `None` when coords refer to the flange.
+    pub fn tool_pose(&self) -> Option<&Pose> {
+        self.tool.as_ref()
+    }
+    /// Once set, `get_coords`, `send_coord(s)` and `is_in_coord_position` use
+    /// the tool center point instead of the flange. Raw coord APIs are unaffected.
+    pub fn set_tool_pose(&mut self, tool: Option<Pose>) {
+        self.tool = tool;
+    }
+    fn tool_to_flange(&self, coords: &[f64; 6]) -> [f64; 6] {
+        if self.tool.is_none() && self.mounting.is_none() {
+            return *coords;
+        }
+        self.pose_to_flange(&Pose::from_coords(coords)).to_coords()
+    }
+    pub(crate) fn flange_to_tool(&self, coords: &[f64; 6]) -> [f64; 6] {
+        if self.tool.is_none() && self.mounting.is_none() {
+            return *coords;
+        }
+        self.flange_to_pose(&Pose::from_coords(coords)).to_coords()
+    }
+    /// Robot-frame flange pose to the logical-frame tool pose.
+    fn flange_to_pose(&self, flange: &Pose) -> Pose {
+        let pose = match &self.tool {
+            Some(tool) => flange.compose(tool),
+            None => *flange,
+        };
+        match &self.mounting {
+            Some(mounting) => mounting.pose_to_logical(&pose),
+            None => pose,
+        }
+    }
+    fn pose_to_flange(&self, pose: &Pose) -> Pose {
+        let pose = match &self.mounting {
+            Some(mounting) => mounting.pose_to_robot(pose),
+            None => *pose,
+        };
+        match &self.tool {
+            Some(tool) => pose.compose(&tool.inverse()),
+            None => pose,
+        }
+    }
+    /// Switches to dry-run mode: writes are validated against `model`, logged
+    /// and simulated instead of being sent. The simulation starts from the
+    /// robot's current angles when they can be read, otherwise from zero.
+    pub fn enable_dry_run(&mut self, model: KinematicModel) {
+        let angles = self.get_angles().unwrap_or([0.0; 6]);
+        self.dry_run = Some(DryRun::new(model, angles));
+    }
+    /// Leaves dry-run mode and returns the simulation with its log.
+    pub fn disable_dry_run(&mut self) -> Option<DryRun> {
+        self.dry_run.take()
+    }
+    pub fn dry_run(&self) -> Option<&DryRun> {
+        self.dry_run.as_ref()
+    }
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run.is_some()
+    }
+    /// Round-trip times and timeout/retry counts per genre since creation or `reset_stats`.
+    pub fn stats(&self) -> &OperatorStats {
+        &self.stats
+    }
+    pub fn reset_stats(&mut self) {
+        self.stats = OperatorStats::default();
+    }
+    /// Queries the firmware's min/max angle for every joint.
+    pub fn read_joint_limits(&mut self) -> Result<JointLimits> {
+        let mut limits = JointLimits::new([0.0; 6], [0.0; 6]);
+        for (i, id) in Angle::ALL.iter().enumerate() {
+            let min = self.get_joint_min_angle(*id)?;
+            let max = self.get_joint_max_angle(*id)?;
+            match (min.first(), max.first()) {
+                (Some(&min), Some(&max)) => {
+                    limits.min[i] = int_to_angle(min);
+                    limits.max[i] = int_to_angle(max);
+                }
+                _ => return Err(anyhow::anyhow!("No joint limits reported for J{}", i + 1)),
+            }
+        }
+        Ok(limits)
+    }
+    /// Soft limits enforced on angle commands, if any.
+    pub fn joint_limits(&self) -> Option<&JointLimits> {
+        self.joint_limits.as_ref()
+    }
+    pub fn set_joint_limits(&mut self, limits: Option<JointLimits>) {
+        self.joint_limits = limits;
+    }
+    /// Narrows the soft limits of one joint; has no effect until limits are set.
+    pub fn tighten_joint_limit(&mut self, id: Angle, min: f64, max: f64) {
+        if let Some(limits) = &mut self.joint_limits {
+            limits.tighten(id as usize - 1, min, max);
+        }
+    }
+    /// Envelope enforced on flange targets of coord commands, if any.
+    pub fn workspace(&self) -> Option<&Workspace> {
+        self.workspace.as_ref()
+    }
+    /// Raw coord APIs are unaffected. Targets clamped under
+    /// `WorkspacePolicy::Clamp` never report in position to `sync_send_coords`.
+    pub fn set_workspace(&mut self, workspace: Option<Workspace>) {
+        self.workspace = workspace;
+    }
+    fn check_workspace(&self, coords: &[f64; 6]) -> Result<[f64; 6]> {
+        match &self.workspace {
+            Some(workspace) => workspace.apply(coords),
+            None => Ok(*coords),
+        }
+    }
+    fn check_joint_limits(&self, degrees: &[f64; 6]) -> Result<()> {
+        let violations = match &self.joint_limits {
+            Some(limits) => limits.violations(degrees),
+            None => return Ok(()),
+        };
+        match violations.first() {
+            Some(&i) => Err(anyhow::anyhow!(
+                "J{} target {} is outside the soft limits",
+                i + 1,
+                degrees[i]
+            )),
+            None => Ok(()),
+        }
+    }
     pub fn power_on(&mut self) -> Result<()> {
-        self.write_command(Command::POWER_ON, &[])
+        self.write_command(Command::PowerOn, &[])
     }
     pub fn power_off(&mut self) -> Result<()> {
-        self.write_command(Command::POWER_OFF, &[])
+        self.write_command(Command::PowerOff, &[])
     }
     pub fn is_power_on(&mut self) -> Result<i32> {
-        let res = self.write_command_and_receive(Command::IS_POWER_ON, &[])?;
+        let res = self.write_command_and_receive(Command::IsPowerOn, &[])?;
         Ok(if res.is_empty() { -1 } else { res[0] as i32 })
     }
     pub fn release_all_servos(&mut self) -> Result<()> {
-        self.write_command(Command::RELEASE_ALL_SERVOS, &[])
+        self.write_command(Command::ReleaseAllServos, &[])
     }
     pub fn is_controller_connected(&mut self) -> Result<i32> {
-        let res = self.write_command_and_receive(Command::IS_CONTROLLER_CONNECTED, &[])?;
+        let res = self.write_command_and_receive(Command::IsControllerConnected, &[])?;
         Ok(if res.is_empty() { -1 } else { res[0] as i32 })
     }
+    /// `Interrupt` makes each motion command preempt the current one, which
+    /// streaming control needs; `Queue` executes commands in order.
+    pub fn set_fresh_mode(&mut self, mode: FreshMode) -> Result<()> {
+        let command_data = [mode as u8];
+        self.write_command(Command::SetFreshMode, &command_data)
+    }
+    pub fn get_fresh_mode(&mut self) -> Result<FreshMode> {
+        let res = self.write_command_and_receive(Command::GetFreshMode, &[])?;
+        match res.first() {
+            Some(0) => Ok(FreshMode::Queue),
+            Some(1) => Ok(FreshMode::Interrupt),
+            Some(v) => Err(anyhow::anyhow!("Unknown fresh mode: {}", v)),
+            None => Err(anyhow::anyhow!("No response to get_fresh_mode")),
+        }
+    }
+    /// Selects firmware-side joint or linear planning for `send_coords`.
+    pub fn set_movement_type(&mut self, movement_type: MovementType) -> Result<()> {
+        let command_data = [movement_type as u8];
+        self.write_command(Command::SetMovementType, &command_data)
+    }
+    pub fn get_movement_type(&mut self) -> Result<MovementType> {
+        let res = self.write_command_and_receive(Command::GetMovementType, &[])?;
+        match res.first() {
+            Some(0) => Ok(MovementType::MoveJ),
+            Some(1) => Ok(MovementType::MoveL),
+            Some(v) => Err(anyhow::anyhow!("Unknown movement type: {}", v)),
+            None => Err(anyhow::anyhow!("No response to get_movement_type")),
+        }
+    }
+    fn get_plan_setting(&mut self, genre: Command) -> Result<PlanSetting> {
+        // The firmware replies with the MoveL value first.
+        match self.write_command_and_receive(genre, &[])?[..] {
+            [move_l, move_j] => Ok(PlanSetting {
+                move_j: move_j as u8,
+                move_l: move_l as u8,
+            }),
+            _ => Err(anyhow::anyhow!("No response to {}", genre)),
+        }
+    }
+    fn set_plan_setting(
+        &mut self,
+        genre: Command,
+        value: u8,
+        movement_type: MovementType,
+    ) -> Result<()> {
+        if value > 100 {
+            return Err(anyhow::anyhow!(
+                "{} value must be 0-100, got {}",
+                genre,
+                value
+            ));
+        }
+        let is_linear = matches!(movement_type, MovementType::MoveL);
+        self.write_command(genre, &[value, is_linear as u8])
+    }
+    /// Planner speeds (0-100) used by the firmware for each movement type.
+    pub fn get_plan_speed(&mut self) -> Result<PlanSetting> {
+        self.get_plan_setting(Command::GetPlanSpeed)
+    }
+    pub fn set_plan_speed(&mut self, speed: u8, movement_type: MovementType) -> Result<()> {
+        self.set_plan_setting(Command::SetPlanSpeed, speed, movement_type)
+    }
+    /// Planner accelerations (0-100) used by the firmware for each movement type.
+    pub fn get_plan_acceleration(&mut self) -> Result<PlanSetting> {
+        self.get_plan_setting(Command::GetPlanAcceleration)
+    }
+    pub fn set_plan_acceleration(
+        &mut self,
+        acceleration: u8,
+        movement_type: MovementType,
+    ) -> Result<()> {
+        self.set_plan_setting(Command::SetPlanAcceleration, acceleration, movement_type)
+    }
+    pub fn get_error_information(&mut self) -> Result<RobotErrorReport> {
+        let res = self.write_command_and_receive(Command::GetErrorInformation, &[])?;
+        match res.first() {
+            Some(&code) => Ok(RobotErrorReport::from_code(code as i32)),
+            None => Err(anyhow::anyhow!("No response to get_error_information")),
+        }
+    }
+    pub fn clear_errors(&mut self) -> Result<()> {
+        self.write_command(Command::ClearErrorInformation, &[])
+    }
     pub fn get_angles(&mut self) -> Result<[f64; 6]> {
-        let res = self.write_command_and_receive(Command::GET_ANGLES, &[])?;
-        res.into_iter().map(int_to_angle).collect::<Vec<_>>()[..]
+        let res = self.write_command_and_receive(Command::GetAngles, &[])?;
+        let angles = res.into_iter().map(int_to_angle).collect::<Vec<_>>()[..]
             .try_into()
-            .map_err(Error::msg)
+            .map_err(Error::msg)?;
+        Ok(self.map_mounted_angles(&angles))
+    }
+    /// Current joint angles keyed by joint.
+    pub fn get_angles_map(&mut self) -> Result<BTreeMap<Angle, f64>> {
+        let angles = self.get_angles()?;
+        Ok(Angle::ALL.iter().copied().zip(angles).collect())
+    }
+    pub fn get_angles_raw(&mut self) -> Result<[i16; 6]> {
+        let res = self.write_command_and_receive(Command::GetAngles, &[])?;
+        res[..].try_into().map_err(Error::msg)
+    }
+    /// Sends angles already encoded in hundredths of a degree.
+    pub fn send_angles_raw(&mut self, angles: &[i16; 6], speed: u8) -> Result<()> {
+        if !check_degrees(&int_vec_to_angles(angles)) {
+            return Err(anyhow::anyhow!("Outbound degrees",));
+        }
+        self.check_joint_limits(&int_vec_to_angles(angles))?;
+        let command_data = [&encode_int16_vec(angles)[..], &[speed]].concat();
+        self.write_command(Command::SendAngles, &command_data)?;
+        // Sent without compensation, so the last compensated targets are stale.
+        if let Some(compensation) = &mut self.backlash {
+            compensation.invalidate();
+        }
+        Ok(())
     }
     pub fn send_angle(&mut self, id: Angle, degree: f64, speed: u8) -> Result<()> {
+        let speed = self.thermal_speed(speed)?;
+        let degree = match &self.mounting {
+            Some(m) if m.negate_joints()[id as usize - 1] => -degree,
+            _ => degree,
+        };
         if !check_degree(degree) {
             return Err(anyhow::anyhow!("Outbound degree",));
         }
+        if let Some(limits) = &self.joint_limits {
+            let index = id as usize - 1;
+            if !limits.contains(index, degree) {
+                return Err(anyhow::anyhow!(
+                    "J{} target {} is outside the soft limits",
+                    index + 1,
+                    degree
+                ));
+            }
+        }
+        let degree = self.compensate_backlash(id as usize - 1, degree);
         let command_data = [
             &[id as u8],
             &encode_int16(angle_to_int(degree))[..],
             &[speed],
         ]
         .concat();
-        self.write_command(Command::SEND_ANGLE, &command_data)
+        self.write_command(Command::SendAngle, &command_data)
     }
     pub fn send_angles(&mut self, degrees: &[f64; 6], speed: u8) -> Result<()> {
+        let speed = self.thermal_speed(speed)?;
+        let degrees = &self.map_mounted_angles(degrees);
         if !check_degrees(degrees) {
             return Err(anyhow::anyhow!("Outbound degrees",));
         }
+        self.check_joint_limits(degrees)?;
+        let mut degrees = *degrees;
+        for (i, d) in degrees.iter_mut().enumerate() {
+            *d = self.compensate_backlash(i, *d);
+        }
         let command_data = [
-            &encode_int16_vec(
-                &degrees
-                    .iter()
-                    .map(|deg| angle_to_int(*deg))
-                    .collect::<Vec<_>>()[..],
-            )[..],
+            &encode_int16_vec(&angles_to_int_vec(&degrees))[..],
             &[speed],
         ]
         .concat();
-        self.write_command(Command::SEND_ANGLES, &command_data)
+        self.write_command(Command::SendAngles, &command_data)
+    }
+    pub fn get_radians(&mut self) -> Result<[f64; 6]> {
+        Ok(self.get_angles()?.map(f64::to_radians))
+    }
+    pub fn send_radian(&mut self, id: Angle, radian: f64, speed: u8) -> Result<()> {
+        self.send_angle(id, radian.to_degrees(), speed)
+    }
+    pub fn send_radians(&mut self, radians: &[f64; 6], speed: u8) -> Result<()> {
+        self.send_angles(&radians.map(f64::to_degrees), speed)
     }
     pub fn get_coords(&mut self) -> Result<[f64; 6]> {
-        let res = self.write_command_and_receive(Command::GET_COORDS, &[])?;
-        int_vec_to_coords(&res)
+        let res = self.write_command_and_receive(Command::GetCoords, &[])?;
+        Ok(self.flange_to_tool(&int_vec_to_coords(&res)?))
+    }
+    /// Current tool coordinates keyed by axis.
+    pub fn get_coords_map(&mut self) -> Result<BTreeMap<Coord, f64>> {
+        let coords = self.get_coords()?;
+        Ok(Coord::ALL.iter().copied().zip(coords).collect())
+    }
+    /// Current tool pose, for reading the rotation as a matrix or quaternion.
+    /// Read with `get_coords`; the protocol has no documented pose genre.
+    pub fn get_pose(&mut self) -> Result<Pose> {
+        Ok(Pose::from_coords(&self.get_coords()?))
+    }
+    pub fn get_quaternion(&mut self) -> Result<Quaternion> {
+        Ok(self.get_pose()?.quaternion())
+    }
+    /// Moves the tool to `pose`, converted to Euler coords and sent with
+    /// `send_coords`, so limits, the workspace and thermal derating apply.
+    pub fn set_pose(&mut self, pose: &Pose, speed: u8, mode: Mode) -> Result<()> {
+        self.send_coords(&pose.to_coords(), speed, mode)
+    }
+    /// Like `get_coords`, with the rotation expressed in Euler `order`.
+    pub fn get_coords_with(&mut self, order: EulerOrder) -> Result<[f64; 6]> {
+        let pose = self.get_pose()?;
+        let [a, b, c] = pose.euler(order);
+        let [x, y, z] = pose.position;
+        Ok([x, y, z, a, b, c])
+    }
+    pub fn get_coords_raw(&mut self) -> Result<[i16; 6]> {
+        let res = self.write_command_and_receive(Command::GetCoords, &[])?;
+        res[..].try_into().map_err(Error::msg)
+    }
+    /// Sends coords already encoded in tenths of a millimeter and hundredths of a degree.
+    pub fn send_coords_raw(&mut self, coords: &[i16; 6], speed: u8, mode: Mode) -> Result<()> {
+        if !check_coords(&int_vec_to_coords(coords)?) {
+            return Err(anyhow::anyhow!("Outbound coords",));
+        }
+        let command_data = [&encode_int16_vec(coords)[..], &[speed], &[mode as u8]].concat();
+        self.write_command(Command::SendCoords, &command_data)
     }
     pub fn send_coord(&mut self, id: Coord, coord: f64, speed: u8) -> Result<()> {
-        if !check_coord(id.clone(), coord) {
+        if !check_coord(id, coord) {
             return Err(anyhow::anyhow!("Outbound coord",));
         }
+        if self.tool.is_some() || self.mounting.is_some() || self.workspace.is_some() {
+            let mut coords = self.get_coords()?;
+            coords[id as usize - 1] = coord;
+            return self.send_coords(&coords, speed, Mode::Normal);
+        }
+        let speed = self.thermal_speed(speed)?;
         let command_data = [
             &[id as u8 - 1],
             &encode_int16(coord_to_int(coord))[..],
             &[speed],
         ]
         .concat();
-        self.write_command(Command::SEND_COORD, &command_data)
+        self.write_command(Command::SendCoord, &command_data)
     }
     pub fn send_coords(&mut self, coords: &[f64; 6], speed: u8, mode: Mode) -> Result<()> {
+        let speed = self.thermal_speed(speed)?;
+        let coords = &self.tool_to_flange(coords);
         if !check_coords(coords) {
             return Err(anyhow::anyhow!("Outbound coords",));
         }
+        let coords = &self.check_workspace(coords)?;
         let command_data = [
             &encode_int16_vec(&coords_to_int_vec(coords))[..],
             &[speed],
             &[mode as u8],
         ]
         .concat();
-        self.write_command(Command::SEND_COORDS, &command_data)
+        self.write_command(Command::SendCoords, &command_data)
     }
     pub fn is_in_angle_position(&mut self, degrees: &[f64; 6]) -> Result<i32> {
-        let command_data = [
-            &encode_int16_vec(
-                &degrees
-                    .iter()
-                    .map(|deg| angle_to_int(*deg))
-                    .collect::<Vec<_>>()[..],
-            )[..],
-            &[0u8],
-        ]
-        .concat();
-        let res = self.write_command_and_receive(Command::IS_IN_POSITION, &command_data)?;
+        let degrees = &self.map_mounted_angles(degrees);
+        let command_data = [&encode_int16_vec(&angles_to_int_vec(degrees))[..], &[0u8]].concat();
+        let res = self.write_command_and_receive(Command::IsInPosition, &command_data)?;
         Ok(if res.is_empty() { -1 } else { res[0] as i32 })
     }
     pub fn is_in_coord_position(&mut self, coords: &[f64; 6]) -> Result<i32> {
+        let coords = &self.tool_to_flange(coords);
         let command_data = [&encode_int16_vec(&coords_to_int_vec(coords))[..], &[1u8]].concat();
-        let res = self.write_command_and_receive(Command::IS_IN_POSITION, &command_data)?;
+        let res = self.write_command_and_receive(Command::IsInPosition, &command_data)?;
         Ok(if res.is_empty() { -1 } else { res[0] as i32 })
     }
     pub fn is_moving(&mut self) -> Result<i32> {
-        let res = self.write_command_and_receive(Command::IS_MOVING, &[])?;
+        let res = self.write_command_and_receive(Command::IsMoving, &[])?;
         Ok(if res.is_empty() { -1 } else { res[0] as i32 })
     }
     pub fn jog_angle(&mut self, id: Angle, direction: Direction, speed: u8) -> Result<()> {
         let command_data = [id as u8, direction as u8, speed];
-        self.write_command(Command::JOG_ANGLE, &command_data)
+        self.write_command(Command::JogAngle, &command_data)
     }
     pub fn jog_coord(&mut self, id: Coord, direction: Direction, speed: u8) -> Result<()> {
         let command_data = [id as u8, direction as u8, speed];
-        self.write_command(Command::JOG_COORD, &command_data)
+        self.write_command(Command::JogCoord, &command_data)
+    }
+    pub fn jog_increment(&mut self, id: Angle, increment: f64, speed: u8) -> Result<()> {
+        let command_data = [
+            &[id as u8],
+            &encode_int16(angle_to_int(increment))[..],
+            &[speed],
+        ]
+        .concat();
+        self.write_command(Command::JogIncrement, &command_data)
     }
     pub fn jog_stop(&mut self) -> Result<()> {
-        self.write_command(Command::JOG_STOP, &[])
+        self.write_command(Command::JogStop, &[])
     }
     pub fn pause(&mut self) -> Result<()> {
-        self.write_command(Command::PAUSE, &[])
+        self.write_command(Command::Pause, &[])
     }
     pub fn is_paused(&mut self) -> Result<i32> {
-        let res = self.write_command_and_receive(Command::IS_PAUSED, &[])?;
+        let res = self.write_command_and_receive(Command::IsPaused, &[])?;
         Ok(if res.is_empty() { -1 } else { res[0] as i32 })
     }
     pub fn resume(&mut self) -> Result<()> {
-        self.write_command(Command::RESUME, &[])
+        self.write_command(Command::Resume, &[])
     }
     pub fn stop(&mut self) -> Result<()> {
-        self.write_command(Command::STOP, &[])
+        self.write_command(Command::Stop, &[])
     }
     pub fn set_encoder(&mut self, id: Angle, encoder: i16) -> Result<()> {
         let command_data = [&[id as u8], &encode_int16(encoder)[..]].concat();
-        self.write_command(Command::SET_ENCODER, &command_data)
+        self.write_command(Command::SetEncoder, &command_data)
     }
     pub fn get_encoder(&mut self, id: Angle) -> Result<i32> {
         let command_data = [id as u8];
-        let res = self.write_command_and_receive(Command::GET_ENCODER, &command_data)?;
+        let res = self.write_command_and_receive(Command::GetEncoder, &command_data)?;
         Ok(if res.is_empty() { -1 } else { res[0] as i32 })
     }
     pub fn set_encoders(&mut self, encoders: &[i16], sp: u8) -> Result<()> {
         let command_data = [&encode_int16_vec(encoders)[..], &[sp]].concat();
-        self.write_command(Command::SET_ENCODERS, &command_data)
+        self.write_command(Command::SetEncoders, &command_data)
     }
+    /// One value per joint the arm has (`ProtocolProfile::joint_count`).
     pub fn get_encoders(&mut self) -> Result<Vec<i16>> {
-        self.write_command_and_receive(Command::GET_ENCODERS, &[])
+        let mut encoders = self.write_command_and_receive(Command::GetEncoders, &[])?;
+        encoders.truncate(self.profile.joint_count);
+        Ok(encoders)
+    }
+    pub fn encoder_resolution(&self) -> u16 {
+        self.profile.encoder_resolution
+    }
+    /// Overrides the encoder resolution, e.g. for servos `detect_encoder_resolution` cannot identify.
+    pub fn set_encoder_resolution(&mut self, resolution: u16) -> Result<()> {
+        if resolution == 0 {
+            return Err(anyhow::anyhow!("Encoder resolution must be positive"));
+        }
+        self.profile.encoder_resolution = resolution;
+        Ok(())
+    }
+    /// Compares the encoder readings with the reported joint angles and keeps
+    /// the resolution in `ProtocolProfile::ENCODER_RESOLUTIONS` that explains
+    /// them, within `ProtocolProfile::ENCODER_DETECT_TOLERANCE` on every joint.
+    pub fn detect_encoder_resolution(&mut self) -> Result<u16> {
+        let angles = self.get_angles()?;
+        let encoders = self.get_encoders()?;
+        if encoders.len() < self.profile.joint_count.min(angles.len()) {
+            return Err(anyhow::anyhow!("No encoder reply"));
+        }
+        // Only joints the arm reports are compared; zip stops at the last encoder.
+        let error = |resolution: u16| {
+            angles
+                .iter()
+                .zip(encoders.iter())
+                .map(|(a, &e)| (encoder_to_angle(e, resolution) - a).abs())
+                .fold(0.0, f64::max)
+        };
+        let (resolution, error) = ProtocolProfile::ENCODER_RESOLUTIONS
+            .iter()
+            .map(|&r| (r, error(r)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap_or((self.profile.encoder_resolution, f64::INFINITY));
+        if error > ProtocolProfile::ENCODER_DETECT_TOLERANCE {
+            return Err(anyhow::anyhow!(
+                "Encoder readings match no known resolution (best {}, off by {:.1} degrees)",
+                resolution,
+                error
+            ));
+        }
+        self.profile.encoder_resolution = resolution;
+        Ok(resolution)
+    }
+    /// Joint angle in degrees computed from the raw encoder reading.
+    pub fn get_encoder_angle(&mut self, id: Angle) -> Result<f64> {
+        let encoder = self.get_encoder(id)?;
+        if encoder == -1 {
+            return Err(anyhow::anyhow!("No encoder reply for {}", id));
+        }
+        Ok(encoder_to_angle(
+            encoder as i16,
+            self.profile.encoder_resolution,
+        ))
+    }
+    pub fn set_encoder_angle(&mut self, id: Angle, degree: f64) -> Result<()> {
+        let encoder = angle_to_encoder(degree, self.profile.encoder_resolution);
+        self.set_encoder(id, encoder)
+    }
+    /// One angle per joint the arm reports; joints it lacks are absent
+    /// rather than read as 0 degrees.
+    pub fn get_encoder_angles(&mut self) -> Result<Vec<f64>> {
+        let resolution = self.profile.encoder_resolution;
+        let encoders = self.get_encoders()?;
+        if encoders.is_empty() {
+            return Err(anyhow::anyhow!("No encoder reply"));
+        }
+        Ok(encoders
+            .iter()
+            .map(|&e| encoder_to_angle(e, resolution))
+            .collect())
+    }
+    pub fn set_encoder_angles(&mut self, degrees: &[f64; 6], speed: u8) -> Result<()> {
+        let resolution = self.profile.encoder_resolution;
+        let encoders = degrees.map(|d| angle_to_encoder(d, resolution));
+        self.set_encoders(&encoders, speed)
+    }
+    /// Current encoder values keyed by joint.
+    pub fn get_encoders_map(&mut self) -> Result<BTreeMap<Angle, i16>> {
+        let encoders = self.get_encoders()?;
+        let expected = self.profile.joint_count.min(Angle::ALL.len());
+        if encoders.len() < expected {
+            return Err(anyhow::anyhow!(
+                "Expected {} encoder values, got {}",
+                expected,
+                encoders.len()
+            ));
+        }
+        Ok(Angle::ALL.iter().copied().zip(encoders).collect())
     }
     pub fn get_speed(&mut self) -> Result<Vec<i16>> {
-        self.write_command_and_receive(Command::GET_SPEED, &[])
+        self.write_command_and_receive(Command::GetSpeed, &[])
     }
     pub fn set_speed(&mut self, speed: u8) -> Result<()> {
         let command_data = [speed];
-        self.write_command(Command::SET_SPEED, &command_data)
+        self.write_command(Command::SetSpeed, &command_data)
     }
     pub fn get_joint_min_angle(&mut self, id: Angle) -> Result<Vec<i16>> {
         let command_data = [id as u8];
-        self.write_command_and_receive(Command::GET_JOINT_MIN_ANGLE, &command_data)
+        self.write_command_and_receive(Command::GetJointMinAngle, &command_data)
     }
     pub fn get_joint_max_angle(&mut self, id: Angle) -> Result<Vec<i16>> {
         let command_data = [id as u8];
-        self.write_command_and_receive(Command::GET_JOINT_MAX_ANGLE, &command_data)
+        self.write_command_and_receive(Command::GetJointMaxAngle, &command_data)
     }
     pub fn is_servo_enable(&mut self, id: Angle) -> Result<i32> {
         let command_data = [id as u8];
-        let res = self.write_command_and_receive(Command::IS_SERVO_ENABLE, &command_data)?;
-        Ok(if res.is_empty() { -1 } else { res[0] as i32 })
+        let res = self.write_command_and_receive(Command::IsServoEnable, &command_data)?;
+        Ok(res.last().map_or(-1, |&v| v as i32))
+    }
+    pub fn is_servo_enabled(&mut self, id: Angle) -> Result<bool> {
+        match self.is_servo_enable(id)? {
+            -1 => Err(anyhow::anyhow!("No response to is_servo_enable")),
+            state => Ok(state == 1),
+        }
+    }
+    /// Holds (`true`) or relaxes (`false`) a single joint while the others keep their state.
+    pub fn set_servo_enabled(&mut self, id: Angle, enabled: bool) -> Result<()> {
+        if enabled {
+            self.focus_servo(id)
+        } else {
+            self.release_servo(id)
+        }
     }
     pub fn is_all_servo_enable(&mut self) -> Result<i32> {
-        let res = self.write_command_and_receive(Command::IS_ALL_SERVO_ENABLE, &[])?;
+        let res = self.write_command_and_receive(Command::IsAllServoEnable, &[])?;
         Ok(if res.is_empty() { -1 } else { res[0] as i32 })
     }
     pub fn set_servo_data(&mut self, servo_no: u8, data_id: u8, value: u8) -> Result<()> {
         let command_data = [servo_no, data_id, value];
-        self.write_command(Command::SET_SERVO_DATA, &command_data)
+        self.write_command(Command::SetServoData, &command_data)
     }
     pub fn get_servo_data(&mut self, servo_no: u8, data_id: u8) -> Result<Vec<i16>> {
         let command_data = [servo_no, data_id];
-        self.write_command_and_receive(Command::GET_SERVO_DATA, &command_data)
+        self.write_command_and_receive(Command::GetServoData, &command_data)
+    }
+    pub fn set_servo_register(
+        &mut self,
+        id: Angle,
+        register: ServoRegister,
+        value: u8,
+    ) -> Result<()> {
+        if !register.range().contains(&value) {
+            return Err(anyhow::anyhow!(
+                "{} must be {}-{}, got {}",
+                register,
+                register.range().start(),
+                register.range().end(),
+                value
+            ));
+        }
+        self.set_servo_data(id as u8, register as u8, value)
+    }
+    pub fn get_servo_register(&mut self, id: Angle, register: ServoRegister) -> Result<u8> {
+        let res = self.get_servo_data(id as u8, register as u8)?;
+        match res.first() {
+            Some(&value) => Ok(value as u8),
+            None => Err(anyhow::anyhow!(
+                "No response reading {} of {}",
+                register,
+                id
+            )),
+        }
+    }
+    pub fn get_servo_pid(&mut self, id: Angle) -> Result<ServoPid> {
+        Ok(ServoPid {
+            p: self.get_servo_register(id, ServoRegister::PGain)?,
+            i: self.get_servo_register(id, ServoRegister::IGain)?,
+            d: self.get_servo_register(id, ServoRegister::DGain)?,
+        })
+    }
+    /// Writes the gains in the servo's RAM; they revert at power off.
+    pub fn set_servo_pid(&mut self, id: Angle, pid: &ServoPid) -> Result<()> {
+        self.set_servo_register(id, ServoRegister::PGain, pid.p)?;
+        self.set_servo_register(id, ServoRegister::IGain, pid.i)?;
+        self.set_servo_register(id, ServoRegister::DGain, pid.d)
+    }
+    /// Present current of each joint servo in mA, a proxy for joint load.
+    pub fn get_servo_currents(&mut self) -> Result<[i16; 6]> {
+        let res = self.write_command_and_receive(Command::GetServoCurrents, &[])?;
+        res[..].try_into().map_err(Error::msg)
+    }
+    /// Servo temperatures in whole °C, on firmware that reports them.
+    pub fn get_servo_temps(&mut self) -> Result<[i16; 6]> {
+        let res = self.write_command_and_receive(Command::GetServoTemps, &[])?;
+        res[..].try_into().map_err(Error::msg)
     }
     pub fn set_servo_calibration(&mut self) -> Result<()> {
-        self.write_command(Command::SET_SERVO_CALIBRATION, &[])
+        self.write_command(Command::SetServoCalibration, &[])
+    }
+    /// Stores the joint's current position as its zero.
+    pub fn set_servo_zero(&mut self, id: Angle) -> Result<()> {
+        let command_data = [id as u8];
+        self.write_command(Command::SetServoCalibration, &command_data)
     }
     pub fn release_servo(&mut self, servo_id: Angle) -> Result<()> {
         let command_data = [servo_id as u8];
-        self.write_command(Command::RELEASE_SERVO, &command_data)
+        self.write_command(Command::ReleaseServo, &command_data)
     }
     pub fn focus_servo(&mut self, servo_id: Angle) -> Result<()> {
         let command_data = [servo_id as u8];
-        self.write_command(Command::FOCUS_SERVO, &command_data)
+        self.write_command(Command::FocusServo, &command_data)
     }
     pub fn set_color(&mut self, r: u8, g: u8, b: u8) -> Result<()> {
         let command_data = [r, g, b];
-        self.write_command(Command::SET_COLOR, &command_data)
+        self.write_command(Command::SetColor, &command_data)
     }
     pub fn set_pin_mode(&mut self, pin_no: u8, pin_mode: PinMode) -> Result<()> {
         let command_data = [pin_no, pin_mode as u8];
-        self.write_command(Command::SET_PIN_MODE, &command_data)
+        self.write_command(Command::SetPinMode, &command_data)
     }
     pub fn set_digital_output(&mut self, pin_no: u8, pin_signal: bool) -> Result<()> {
         let command_data = [pin_no, pin_signal as u8];
-        self.write_command(Command::SET_DIGITAL_OUTPUT, &command_data)
+        self.write_command(Command::SetDigitalOutput, &command_data)
     }
     pub fn get_digital_intput(&mut self, pin_no: u8) -> Result<i32> {
         let command_data = [pin_no];
-        let res = self.write_command_and_receive(Command::GET_DIGITAL_INPUT, &command_data)?;
+        let res = self.write_command_and_receive(Command::GetDigitalInput, &command_data)?;
         Ok(if res.is_empty() { -1 } else { res[0] as i32 })
     }
     pub fn set_pwm_output(&mut self, channel: u8, frequency: i16, pin_val: u8) -> Result<()> {
         let command_data = [&[channel], &encode_int16(frequency)[..], &[pin_val]].concat();
-        self.write_command(Command::SET_PWM_OUTPUT, &command_data)
+        self.write_command(Command::SetPwmOutput, &command_data)
     }
     pub fn get_gripper_value(&mut self) -> Result<Vec<i16>> {
-        self.write_command_and_receive(Command::GET_DIGITAL_INPUT, &[])
+        self.write_command_and_receive(Command::GetGripperValue, &[])
     }
     pub fn set_gripper_state(&mut self, state: GripperState, speed: u8) -> Result<()> {
         let command_data = [state as u8, speed];
-        self.write_command(Command::SET_GRIPPER_STATE, &command_data)
+        self.write_command(Command::SetGripperState, &command_data)
     }
     pub fn set_gripper_value(&mut self, value: u8, speed: u8) -> Result<()> {
         let command_data = [value, speed];
-        self.write_command(Command::SET_GRIPPER_VALUE, &command_data)
+        self.write_command(Command::SetGripperValue, &command_data)
     }
     pub fn set_gripper_ini(&mut self) -> Result<()> {
-        self.write_command(Command::SET_GRIPPER_INI, &[])
+        self.write_command(Command::SetGripperIni, &[])
+    }
+    /// Sets the gripper torque limit in firmware units; lower values grip more
+    /// gently. The range is left for the firmware to enforce.
+    pub fn set_gripper_torque(&mut self, torque: u16) -> Result<()> {
+        let torque = i16::try_from(torque).map_err(|_| {
+            anyhow::anyhow!("Gripper torque {} does not fit the wire format", torque)
+        })?;
+        self.write_command(Command::SetGripperTorque, &encode_int16(torque))
+    }
+    pub fn get_gripper_torque(&mut self) -> Result<u16> {
+        let res = self.write_command_and_receive(Command::GetGripperTorque, &[])?;
+        match res.first() {
+            Some(&torque) => Ok(torque as u16),
+            None => Err(anyhow::anyhow!("No response to get_gripper_torque")),
+        }
+    }
+    /// Sets the current in mA (1-500) above which the gripper stops closing.
+    pub fn set_gripper_protect_current(&mut self, milliamps: u16) -> Result<()> {
+        if !GRIPPER_PROTECT_CURRENT_RANGE.contains(&milliamps) {
+            return Err(anyhow::anyhow!(
+                "Gripper protection current must be {}-{} mA, got {}",
+                GRIPPER_PROTECT_CURRENT_RANGE.start(),
+                GRIPPER_PROTECT_CURRENT_RANGE.end(),
+                milliamps
+            ));
+        }
+        self.write_command(
+            Command::SetGripperProtectCurrent,
+            &encode_int16(milliamps as i16),
+        )
+    }
+    /// Protection current in mA.
+    pub fn get_gripper_protect_current(&mut self) -> Result<u16> {
+        let res = self.write_command_and_receive(Command::GetGripperProtectCurrent, &[])?;
+        match res.first() {
+            Some(&current) => Ok(current as u16),
+            None => Err(anyhow::anyhow!(
+                "No response to get_gripper_protect_current"
+            )),
+        }
     }
     pub fn is_gripper_moving(&mut self) -> Result<i32> {
-        let res = self.write_command_and_receive(Command::IS_GRIPPER_MOVING, &[])?;
+        let res = self.write_command_and_receive(Command::IsGripperMoving, &[])?;
         Ok(if res.is_empty() { -1 } else { res[0] as i32 })
     }
     pub fn set_basic_output(&mut self, pin_no: u8, pin_signal: bool) -> Result<()> {
         let command_data = [pin_no, pin_signal as u8];
-        self.write_command(Command::SET_BASIC_OUTPUT, &command_data)
+        self.write_command(Command::SetBasicOutput, &command_data)
     }
     pub fn get_basic_input(&mut self, pin_no: u8) -> Result<i32> {
         let command_data = [pin_no];
-        let res = self.write_command_and_receive(Command::GET_BASIC_INPUT, &command_data)?;
+        let res = self.write_command_and_receive(Command::GetBasicInput, &command_data)?;
         Ok(if res.is_empty() { -1 } else { res[0] as i32 })
     }
+    /// Sends `degrees`, waits until the robot reports it in position or
+    /// `timeout_secs` passes, and returns the angles it ended at.
     pub fn sync_send_angles(
         &mut self,
         degrees: &[f64; 6],
         speed: u8,
         timeout_secs: f64,
-    ) -> Result<()> {
+    ) -> Result<AngleMoveResult> {
         let start = Instant::now();
         self.send_angles(degrees, speed)?;
+        let mut reached = false;
         while start.elapsed().as_secs_f64() < timeout_secs {
             if self.is_in_angle_position(degrees)? == 1 {
+                reached = true;
                 break;
             }
             thread::sleep(Duration::from_millis(100));
         }
-        Ok(())
+        let angles = JointAngles(self.get_angles()?);
+        Ok(AngleMoveResult {
+            error: angles.max_distance(&JointAngles(*degrees)),
+            angles,
+            reached,
+        })
     }
+    /// Like `sync_send_angles` for coords; returns the pose the tool ended at.
     pub fn sync_send_coords(
         &mut self,
         coords: &[f64; 6],
         speed: u8,
         mode: Mode,
         timeout_secs: f64,
-    ) -> Result<()> {
+    ) -> Result<CoordMoveResult> {
         let start = Instant::now();
         self.send_coords(coords, speed, mode)?;
+        let mut reached = false;
         while start.elapsed().as_secs_f64() < timeout_secs {
             if self.is_in_coord_position(coords)? == 1 {
+                reached = true;
                 break;
             }
             thread::sleep(Duration::from_millis(100));
         }
-        Ok(())
+        let pose = Pose::from_coords(&self.get_coords()?);
+        let (position_error, rotation_error) = pose.distance(&Pose::from_coords(coords));
+        Ok(CoordMoveResult {
+            pose,
+            position_error,
+            rotation_error,
+            reached,
+        })
+    }
+    /// Sends `degrees` and re-sends until the read-back angles are within `tolerance` degrees.
+    /// Returns the number of attempts that were needed.
+    pub fn send_angles_verified(
+        &mut self,
+        degrees: &[f64; 6],
+        speed: u8,
+        tolerance: f64,
+        max_attempts: usize,
+        timeout_secs: f64,
+    ) -> Result<usize> {
+        for attempt in 1..=max_attempts {
+            if attempt > 1 {
+                self.stats.record_retry(Command::SendAngles);
+            }
+            let start = Instant::now();
+            self.send_angles(degrees, speed)?;
+            while start.elapsed().as_secs_f64() < timeout_secs {
+                thread::sleep(Duration::from_millis(100));
+                if let Ok(angles) = self.get_angles() {
+                    if angles
+                        .iter()
+                        .zip(degrees.iter())
+                        .all(|(a, d)| (a - d).abs() <= tolerance)
+                    {
+                        return Ok(attempt);
+                    }
+                }
+            }
+        }
+        Err(anyhow::anyhow!(
+            "Angles were not reached after {} attempts",
+            max_attempts
+        ))
+    }
+    /// Sets the encoder and retries until `get_encoder` reports it within `tolerance`.
+    /// Returns the number of attempts that were needed.
+    pub fn set_encoder_verified(
+        &mut self,
+        id: Angle,
+        encoder: i16,
+        tolerance: i32,
+        max_attempts: usize,
+    ) -> Result<usize> {
+        for attempt in 1..=max_attempts {
+            if attempt > 1 {
+                self.stats.record_retry(Command::SetEncoder);
+            }
+            self.set_encoder(id, encoder)?;
+            thread::sleep(Duration::from_millis(100));
+            let value = self.get_encoder(id)?;
+            if value != -1 && (value - encoder as i32).abs() <= tolerance {
+                return Ok(attempt);
+            }
+        }
+        Err(anyhow::anyhow!(
+            "Encoder was not set after {} attempts",
+            max_attempts
+        ))
     }
     pub fn wait(timeout_secs: f64) {
         thread::sleep(Duration::from_secs_f64(timeout_secs));
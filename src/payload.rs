@@ -0,0 +1,145 @@
+//! Payload estimation from servo holding currents.
+//!
+//! Currents are measured at a fixed set of poses, first with no payload and
+//! then with a reference mass, giving a per-joint current-per-kg gain at each
+//! pose. An unknown payload is then the least-squares fit of the measured
+//! current increase onto those gains.
+
+use super::io::Connection;
+use super::operator::MyCobotOperator;
+use anyhow::Result;
+use std::thread;
+use std::time::Duration;
+
+const SAMPLES: usize = 5;
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PayloadEstimate {
+    /// Estimated payload in kg.
+    pub mass: f64,
+    pub rated_payload: f64,
+    pub exceeds_rating: bool,
+}
+
+pub struct PayloadEstimator {
+    pub poses: Vec<[f64; 6]>,
+    /// Rated payload of the arm in kg.
+    pub rated_payload: f64,
+    pub speed: u8,
+    pub move_timeout: f64,
+    /// Time to let the arm settle at each pose before sampling currents.
+    pub settle: Duration,
+    baseline: Vec<[f64; 6]>,
+    gains: Vec<[f64; 6]>,
+}
+
+impl PayloadEstimator {
+    pub fn new(poses: Vec<[f64; 6]>, rated_payload: f64) -> PayloadEstimator {
+        PayloadEstimator {
+            poses,
+            rated_payload,
+            speed: 30,
+            move_timeout: 15.0,
+            settle: Duration::from_secs(1),
+            baseline: Vec::new(),
+            gains: Vec::new(),
+        }
+    }
+    /// Three poses loading the shoulder and elbow differently, with the 250 g rating of the myCobot 280.
+    pub fn mycobot_280() -> PayloadEstimator {
+        PayloadEstimator::new(
+            vec![
+                [0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+                [0.0, -45.0, 0.0, 0.0, 0.0, 0.0],
+                [0.0, -45.0, -45.0, 0.0, 0.0, 0.0],
+            ],
+            0.25,
+        )
+    }
+    pub fn is_calibrated(&self) -> bool {
+        !self.gains.is_empty()
+    }
+    fn holding_currents<T: Connection>(
+        &self,
+        operator: &mut MyCobotOperator<T>,
+    ) -> Result<Vec<[f64; 6]>> {
+        let mut all = Vec::with_capacity(self.poses.len());
+        for pose in &self.poses {
+            operator.sync_send_angles(pose, self.speed, self.move_timeout)?;
+            thread::sleep(self.settle);
+            let mut sum = [0.0; 6];
+            for _ in 0..SAMPLES {
+                for (s, c) in sum.iter_mut().zip(operator.get_servo_currents()?.iter()) {
+                    *s += *c as f64 / SAMPLES as f64;
+                }
+                thread::sleep(SAMPLE_INTERVAL);
+            }
+            all.push(sum);
+        }
+        Ok(all)
+    }
+    /// Records holding currents with nothing attached to the flange.
+    pub fn calibrate_baseline<T: Connection>(
+        &mut self,
+        operator: &mut MyCobotOperator<T>,
+    ) -> Result<()> {
+        self.baseline = self.holding_currents(operator)?;
+        self.gains.clear();
+        Ok(())
+    }
+    /// Records holding currents with a known `mass` in kg attached, after `calibrate_baseline`.
+    pub fn calibrate_reference<T: Connection>(
+        &mut self,
+        operator: &mut MyCobotOperator<T>,
+        mass: f64,
+    ) -> Result<()> {
+        if self.baseline.len() != self.poses.len() {
+            return Err(anyhow::anyhow!("Baseline currents not calibrated"));
+        }
+        if mass <= 0.0 {
+            return Err(anyhow::anyhow!("Reference mass must be positive"));
+        }
+        let loaded = self.holding_currents(operator)?;
+        self.gains = loaded
+            .iter()
+            .zip(self.baseline.iter())
+            .map(|(l, b)| {
+                let mut gain = [0.0; 6];
+                for (g, (l, b)) in gain.iter_mut().zip(l.iter().zip(b.iter())) {
+                    *g = (l - b) / mass;
+                }
+                gain
+            })
+            .collect();
+        Ok(())
+    }
+    /// Measures the current payload. Check `exceeds_rating` before running heavy motions.
+    pub fn estimate_payload<T: Connection>(
+        &self,
+        operator: &mut MyCobotOperator<T>,
+    ) -> Result<PayloadEstimate> {
+        if !self.is_calibrated() {
+            return Err(anyhow::anyhow!("Payload estimator not calibrated"));
+        }
+        let measured = self.holding_currents(operator)?;
+        let (mut num, mut den) = (0.0, 0.0);
+        for ((m, b), g) in measured.iter().zip(&self.baseline).zip(&self.gains) {
+            for j in 0..6 {
+                num += g[j] * (m[j] - b[j]);
+                den += g[j] * g[j];
+            }
+        }
+        if den < 1e-9 {
+            return Err(anyhow::anyhow!(
+                "Reference mass produced no measurable current change"
+            ));
+        }
+        let mass = (num / den).max(0.0);
+        Ok(PayloadEstimate {
+            mass,
+            rated_payload: self.rated_payload,
+            exceeds_rating: mass > self.rated_payload,
+        })
+    }
+}
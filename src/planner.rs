@@ -0,0 +1,190 @@
+//! Offline motion preview: the same preprocessing `execute_trajectory` applies,
+//! sampled at the streaming rate without touching the robot.
+
+use super::io::Connection;
+use super::kinematics::KinematicModel;
+use super::limits::JointLimits;
+use super::motion::{CartesianPath, Trajectory, TrajectoryOptions};
+use super::operator::MyCobotOperator;
+use super::servoing::stream_period;
+use super::transform::Pose;
+use anyhow::Result;
+use std::time::Duration;
+
+/// Time, joint angles and rail position of one streamed target.
+type Sample = (Duration, [f64; 6], Option<f64>);
+
+#[derive(Clone, Debug)]
+pub enum MoveSpec {
+    /// A joint trajectory, as passed to `execute_trajectory`.
+    Trajectory(Trajectory),
+    /// A Cartesian path, solved step by step with inverse kinematics starting from `seed` angles.
+    Cartesian { path: CartesianPath, seed: [f64; 6] },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PlanViolation {
+    /// Joint `joint` (0-based) at `angle` degrees is outside its limits at `time`.
+    JointLimit {
+        time: Duration,
+        joint: usize,
+        angle: f64,
+    },
+    /// Joint `joint` peaks at `velocity` deg/s, above the configured cap.
+    Velocity {
+        joint: usize,
+        velocity: f64,
+        limit: f64,
+    },
+    /// No inverse kinematics solution for the Cartesian step at `time`.
+    Unreachable { time: Duration, coords: [f64; 6] },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlannedTrajectory {
+    /// Joint targets one streaming period apart, as they would be sent, with
+    /// the rail position wherever the source trajectory carries one.
+    pub trajectory: Trajectory,
+    pub duration: Duration,
+    /// Largest absolute velocity of each joint in deg/s.
+    pub peak_velocities: [f64; 6],
+    pub violations: Vec<PlanViolation>,
+}
+
+impl PlannedTrajectory {
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MotionPlanner {
+    pub model: KinematicModel,
+    pub joint_limits: JointLimits,
+    pub options: TrajectoryOptions,
+}
+
+impl MotionPlanner {
+    /// Checks against the model's joint limits with default trajectory options.
+    pub fn new(model: KinematicModel) -> MotionPlanner {
+        MotionPlanner {
+            joint_limits: JointLimits::from_model(&model),
+            model,
+            options: TrajectoryOptions::default(),
+        }
+    }
+    /// Fails when `options.limits` cannot time scale the trajectory.
+    pub fn plan(&self, spec: &MoveSpec) -> Result<PlannedTrajectory> {
+        let period = stream_period(self.options.rate_hz);
+        let mut violations = Vec::new();
+        let samples = match spec {
+            MoveSpec::Trajectory(trajectory) => self.sample_trajectory(trajectory, period)?,
+            MoveSpec::Cartesian { path, seed } => {
+                let mut angles = *seed;
+                let mut samples = vec![(Duration::ZERO, angles, None)];
+                for (i, coords) in path.steps(self.options.rate_hz).iter().enumerate() {
+                    let time = period * (i as u32 + 1);
+                    match self.model.inverse(&Pose::from_coords(coords), &angles) {
+                        Some(solution) => angles = solution,
+                        None => violations.push(PlanViolation::Unreachable {
+                            time,
+                            coords: *coords,
+                        }),
+                    }
+                    samples.push((time, angles, None));
+                }
+                samples
+            }
+        };
+        let mut trajectory = Trajectory::new();
+        for (time, angles, rail) in &samples {
+            for joint in self.joint_limits.violations(angles) {
+                violations.push(PlanViolation::JointLimit {
+                    time: *time,
+                    joint,
+                    angle: angles[joint],
+                });
+            }
+            match rail {
+                Some(rail) => trajectory.push_with_rail(*time, *angles, *rail),
+                None => trajectory.push(*time, *angles),
+            }
+        }
+        let mut peak_velocities = [0.0; 6];
+        for pair in samples.windows(2) {
+            let dt = (pair[1].0 - pair[0].0).as_secs_f64();
+            if dt <= 0.0 {
+                continue;
+            }
+            for (j, peak) in peak_velocities.iter_mut().enumerate() {
+                *peak = f64::max(*peak, (pair[1].1[j] - pair[0].1[j]).abs() / dt);
+            }
+        }
+        if let Some(limits) = &self.options.limits {
+            for (joint, (&velocity, &limit)) in peak_velocities
+                .iter()
+                .zip(limits.max_velocity.iter())
+                .enumerate()
+            {
+                // Allow for the rounding of sample times to the streaming period.
+                if velocity > limit * 1.01 {
+                    violations.push(PlanViolation::Velocity {
+                        joint,
+                        velocity,
+                        limit,
+                    });
+                }
+            }
+        }
+        Ok(PlannedTrajectory {
+            duration: trajectory.duration(),
+            trajectory,
+            peak_velocities,
+            violations,
+        })
+    }
+    /// Applies the options the way `execute_trajectory` does and samples the
+    /// angles and rail once per period.
+    fn sample_trajectory(&self, trajectory: &Trajectory, period: Duration) -> Result<Vec<Sample>> {
+        let mut trajectory = trajectory.unwrapped(&self.joint_limits);
+        if let Some(tau) = self.options.smoothing {
+            trajectory = trajectory.smoothed(tau);
+        }
+        if let Some(limits) = &self.options.limits {
+            trajectory = trajectory.time_scaled(limits)?;
+        }
+        let blend = self.options.blend.unwrap_or(Duration::ZERO);
+        let duration = trajectory.duration();
+        let count = (duration.as_secs_f64() / period.as_secs_f64()).ceil() as u32;
+        Ok((0..=count)
+            .map(|i| {
+                let t = (period * i).min(duration);
+                (t, trajectory.sample(t, blend), trajectory.sample_rail(t))
+            })
+            .collect())
+    }
+}
+
+impl<T: Connection> MyCobotOperator<T> {
+    /// Streams a plan from `MotionPlanner::plan`, refusing plans with violations.
+    pub fn execute_plan(
+        &mut self,
+        plan: &PlannedTrajectory,
+        options: &TrajectoryOptions,
+    ) -> Result<()> {
+        if let Some(violation) = plan.violations.first() {
+            return Err(anyhow::anyhow!(
+                "Plan has {} violations, the first being {:?}",
+                plan.violations.len(),
+                violation
+            ));
+        }
+        let options = TrajectoryOptions {
+            smoothing: None,
+            blend: None,
+            limits: None,
+            ..options.clone()
+        };
+        self.execute_trajectory(&plan.trajectory, &options)
+    }
+}
@@ -0,0 +1,108 @@
+use super::common::Mode;
+use super::io::Connection;
+use super::operator::MyCobotOperator;
+use anyhow::Result;
+use std::collections::BTreeMap;
+#[cfg(any(feature = "json", feature = "toml"))]
+use std::{fs, path::Path};
+
+/// A stored target: joint angles in degrees, or coords in mm and degrees.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum NamedPose {
+    Angles([f64; 6]),
+    Coords([f64; 6]),
+}
+
+/// Named targets such as "home" or "pick". In TOML each entry reads
+/// `home = { angles = [0, 0, 0, 0, 0, 0] }`; JSON uses the same shape.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct PoseLibrary {
+    poses: BTreeMap<String, NamedPose>,
+}
+
+impl PoseLibrary {
+    pub fn new() -> PoseLibrary {
+        PoseLibrary::default()
+    }
+    pub fn insert(&mut self, name: &str, pose: NamedPose) -> Option<NamedPose> {
+        self.poses.insert(name.to_string(), pose)
+    }
+    pub fn get(&self, name: &str) -> Option<&NamedPose> {
+        self.poses.get(name)
+    }
+    pub fn remove(&mut self, name: &str) -> Option<NamedPose> {
+        self.poses.remove(name)
+    }
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.poses.keys().map(|k| k.as_str())
+    }
+    pub fn len(&self) -> usize {
+        self.poses.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.poses.is_empty()
+    }
+    #[cfg(feature = "json")]
+    pub fn from_json_str(s: &str) -> Result<PoseLibrary> {
+        Ok(serde_json::from_str(s)?)
+    }
+    #[cfg(feature = "json")]
+    pub fn to_json_string(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+    #[cfg(feature = "toml")]
+    pub fn from_toml_str(s: &str) -> Result<PoseLibrary> {
+        Ok(toml::from_str(s)?)
+    }
+    #[cfg(feature = "toml")]
+    pub fn to_toml_string(&self) -> Result<String> {
+        Ok(toml::to_string(self)?)
+    }
+    /// Loads a `.json` or `.toml` file, chosen by extension.
+    #[cfg(any(feature = "json", feature = "toml"))]
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<PoseLibrary> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)?;
+        match path.extension().and_then(|e| e.to_str()) {
+            #[cfg(feature = "json")]
+            Some("json") => PoseLibrary::from_json_str(&text),
+            #[cfg(feature = "toml")]
+            Some("toml") => PoseLibrary::from_toml_str(&text),
+            _ => Err(anyhow::anyhow!("Unsupported pose file: {}", path.display())),
+        }
+    }
+    #[cfg(any(feature = "json", feature = "toml"))]
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let text = match path.extension().and_then(|e| e.to_str()) {
+            #[cfg(feature = "json")]
+            Some("json") => self.to_json_string()?,
+            #[cfg(feature = "toml")]
+            Some("toml") => self.to_toml_string()?,
+            _ => return Err(anyhow::anyhow!("Unsupported pose file: {}", path.display())),
+        };
+        Ok(fs::write(path, text)?)
+    }
+}
+
+impl<T: Connection> MyCobotOperator<T> {
+    /// Sends the named pose from the operator's pose library.
+    pub fn goto(&mut self, name: &str, speed: u8) -> Result<()> {
+        match self.pose_library().get(name).cloned() {
+            Some(NamedPose::Angles(degrees)) => self.send_angles(&degrees, speed),
+            Some(NamedPose::Coords(coords)) => self.send_coords(&coords, speed, Mode::Normal),
+            None => Err(anyhow::anyhow!("Unknown pose: {}", name)),
+        }
+    }
+    /// Stores the current angles under `name`.
+    pub fn save_current_pose(&mut self, name: &str) -> Result<()> {
+        let degrees = self.get_angles()?;
+        self.pose_library_mut()
+            .insert(name, NamedPose::Angles(degrees));
+        Ok(())
+    }
+}
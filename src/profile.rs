@@ -0,0 +1,60 @@
+/// Firmware-dependent encoding details, detected by `MyCobotOperator::negotiate_protocol`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProtocolProfile {
+    pub firmware_version: String,
+    pub atom_version: Option<f64>,
+    pub basic_version: Option<f64>,
+    /// Appends a CRC16 (Modbus) before the footer, as the Pro-series firmware
+    /// expects, and drops replies whose CRC does not match.
+    pub checksum: bool,
+    /// Encoder counts per servo revolution, used by the encoder-angle conversions.
+    pub encoder_resolution: u16,
+    /// Values per angle, coordinate and encoder reply; replies from arms with
+    /// fewer than six joints (4 on the Palletizer) are zero-padded to six.
+    pub joint_count: usize,
+}
+
+impl Default for ProtocolProfile {
+    fn default() -> ProtocolProfile {
+        ProtocolProfile {
+            firmware_version: String::new(),
+            atom_version: None,
+            basic_version: None,
+            checksum: false,
+            encoder_resolution: 4096,
+            joint_count: 6,
+        }
+    }
+}
+
+impl ProtocolProfile {
+    /// Resolutions of the servo generations in use, most common first.
+    pub const ENCODER_RESOLUTIONS: [u16; 2] = [4096, 2048];
+    /// Largest disagreement in degrees between encoder and joint angle
+    /// readings accepted when detecting the resolution.
+    pub const ENCODER_DETECT_TOLERANCE: f64 = 10.0;
+
+    pub fn detect(
+        firmware_version: &str,
+        atom_version: Option<f64>,
+        basic_version: Option<f64>,
+    ) -> ProtocolProfile {
+        let lower = firmware_version.to_lowercase();
+        ProtocolProfile {
+            firmware_version: firmware_version.to_string(),
+            atom_version,
+            basic_version,
+            checksum: lower.contains("pro"),
+            encoder_resolution: 4096,
+            joint_count: if lower.contains("palletizer") { 4 } else { 6 },
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RobotInfo {
+    pub robot_version: Option<f64>,
+    pub system_version: Option<f64>,
+    pub atom_version: Option<f64>,
+    pub robot_id: Option<i32>,
+}
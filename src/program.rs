@@ -0,0 +1,189 @@
+//! Plain-text motion programs, one instruction per line:
+//!
+//! ```text
+//! # pick and place
+//! loop 3
+//!     move_j 0 0 0 0 0 0 50
+//!     move_l 150 -60 120 -180 0 -90
+//!     gripper close
+//!     wait 0.5
+//!     gripper open 70
+//! end
+//! ```
+//!
+//! `move_j` takes six joint angles in degrees, `move_l` six coordinates
+//! (mm and degrees); both accept an optional speed. `gripper` takes `open`,
+//! `close` or a value 0-100, and an optional speed. `#` starts a comment.
+
+use super::common::{GripperState, Mode};
+use super::io::Connection;
+use super::operator::MyCobotOperator;
+use anyhow::{Context, Result};
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+const DEFAULT_SPEED: u8 = 50;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum GripperAction {
+    Open,
+    Close,
+    Value(u8),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Instruction {
+    MoveJ { angles: [f64; 6], speed: u8 },
+    MoveL { coords: [f64; 6], speed: u8 },
+    Wait(Duration),
+    Gripper { action: GripperAction, speed: u8 },
+    Loop { count: u32, body: Vec<Instruction> },
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Program {
+    pub instructions: Vec<Instruction>,
+}
+
+impl Program {
+    /// Parses program text; errors name the offending line.
+    pub fn parse(source: &str) -> Result<Program> {
+        // Bodies of the loops currently open, innermost last.
+        let mut stack: Vec<(u32, usize, Vec<Instruction>)> = Vec::new();
+        let mut instructions = Vec::new();
+        for (index, line) in source.lines().enumerate() {
+            let number = index + 1;
+            let line = line.split('#').next().unwrap_or("").trim();
+            let mut words = line.split_whitespace();
+            let keyword = match words.next() {
+                Some(keyword) => keyword,
+                None => continue,
+            };
+            let args: Vec<&str> = words.collect();
+            let instruction = match keyword {
+                "loop" => {
+                    let count = match args[..] {
+                        [count] => count
+                            .parse()
+                            .with_context(|| format!("line {}: invalid loop count", number))?,
+                        _ => return Err(anyhow::anyhow!("line {}: loop takes a count", number)),
+                    };
+                    stack.push((count, number, std::mem::take(&mut instructions)));
+                    continue;
+                }
+                "end" => match stack.pop() {
+                    Some((count, _, outer)) => {
+                        let body = std::mem::replace(&mut instructions, outer);
+                        Instruction::Loop { count, body }
+                    }
+                    None => return Err(anyhow::anyhow!("line {}: end without loop", number)),
+                },
+                _ => parse_instruction(keyword, &args)
+                    .with_context(|| format!("line {}: {}", number, line))?,
+            };
+            instructions.push(instruction);
+        }
+        if let Some((_, number, _)) = stack.last() {
+            return Err(anyhow::anyhow!("line {}: loop without end", number));
+        }
+        Ok(Program { instructions })
+    }
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Program> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("Cannot read {}", path.display()))?;
+        Program::parse(&source)
+    }
+}
+
+fn parse_instruction(keyword: &str, args: &[&str]) -> Result<Instruction> {
+    match keyword {
+        "move_j" => {
+            let (angles, speed) = parse_six(args)?;
+            Ok(Instruction::MoveJ { angles, speed })
+        }
+        "move_l" => {
+            let (coords, speed) = parse_six(args)?;
+            Ok(Instruction::MoveL { coords, speed })
+        }
+        "wait" => match args {
+            [secs] => {
+                let secs: f64 = secs.parse()?;
+                let wait = Duration::try_from_secs_f64(secs).map_err(|e| {
+                    anyhow::anyhow!("wait needs a non-negative number of seconds: {}", e)
+                })?;
+                Ok(Instruction::Wait(wait))
+            }
+            _ => Err(anyhow::anyhow!("wait takes seconds")),
+        },
+        "gripper" => {
+            let (action, speed) = match args {
+                [action] => (*action, DEFAULT_SPEED),
+                [action, speed] => (*action, parse_speed(speed)?),
+                _ => return Err(anyhow::anyhow!("gripper takes open, close or a value")),
+            };
+            let action = match action {
+                "open" => GripperAction::Open,
+                "close" => GripperAction::Close,
+                value => match value.parse::<u8>() {
+                    Ok(value) if value <= 100 => GripperAction::Value(value),
+                    _ => return Err(anyhow::anyhow!("gripper value must be 0-100")),
+                },
+            };
+            Ok(Instruction::Gripper { action, speed })
+        }
+        _ => Err(anyhow::anyhow!("unknown instruction {}", keyword)),
+    }
+}
+
+fn parse_six(args: &[&str]) -> Result<([f64; 6], u8)> {
+    let speed = match args.len() {
+        6 => DEFAULT_SPEED,
+        7 => parse_speed(args[6])?,
+        _ => return Err(anyhow::anyhow!("expected six values and an optional speed")),
+    };
+    let mut values = [0.0; 6];
+    for (value, arg) in values.iter_mut().zip(args) {
+        *value = arg.parse()?;
+    }
+    Ok((values, speed))
+}
+
+fn parse_speed(arg: &str) -> Result<u8> {
+    match arg.parse::<u8>() {
+        Ok(speed) if speed <= 100 => Ok(speed),
+        _ => Err(anyhow::anyhow!("speed must be 0-100")),
+    }
+}
+
+impl<T: Connection> MyCobotOperator<T> {
+    /// Runs `program`, waiting up to `move_timeout_secs` for each move to arrive.
+    pub fn run_program(&mut self, program: &Program, move_timeout_secs: f64) -> Result<()> {
+        self.run_instructions(&program.instructions, move_timeout_secs)
+    }
+    fn run_instructions(&mut self, instructions: &[Instruction], timeout: f64) -> Result<()> {
+        for instruction in instructions {
+            match instruction {
+                Instruction::MoveJ { angles, speed } => {
+                    self.sync_send_angles(angles, *speed, timeout)?;
+                }
+                Instruction::MoveL { coords, speed } => {
+                    self.sync_send_coords(coords, *speed, Mode::Linear, timeout)?;
+                }
+                Instruction::Wait(duration) => thread::sleep(*duration),
+                Instruction::Gripper { action, speed } => match action {
+                    GripperAction::Open => self.set_gripper_state(GripperState::Open, *speed)?,
+                    GripperAction::Close => self.set_gripper_state(GripperState::Close, *speed)?,
+                    GripperAction::Value(value) => self.set_gripper_value(*value, *speed)?,
+                },
+                Instruction::Loop { count, body } => {
+                    for _ in 0..*count {
+                        self.run_instructions(body, timeout)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
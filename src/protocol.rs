@@ -0,0 +1,257 @@
+//! Incremental parser for the `FE FE len genre data.. FA` frame format.
+//!
+//! Bytes can be pushed in arbitrary chunks; incomplete frames are kept until
+//! the rest arrives and bytes that cannot start a frame are discarded, so the
+//! parser resynchronizes after line noise. It never panics on any input,
+//! which makes it a suitable target for fuzzing and property tests.
+
+use super::common::Command;
+use super::utils::crc16;
+
+/// A complete frame taken from the byte stream.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RawFrame {
+    pub genre: u8,
+    /// Data between the genre byte and the footer, including any checksum.
+    pub payload: Vec<u8>,
+    /// The complete frame as it appeared on the wire.
+    pub raw: Vec<u8>,
+}
+
+impl RawFrame {
+    /// The known command for this frame's genre, if any.
+    pub fn command(&self) -> Option<Command> {
+        Command::from_u8(self.genre)
+    }
+    /// The payload without its trailing CRC16, or `None` when it doesn't end
+    /// in a valid one. Only meaningful for firmware that sends checksums.
+    pub fn checked_data(&self) -> Option<&[u8]> {
+        let n = self.payload.len().checked_sub(2)?;
+        let (data, crc) = self.payload.split_at(n);
+        if crc16(&[&[self.genre], data].concat()).to_be_bytes() == crc {
+            Some(data)
+        } else {
+            None
+        }
+    }
+}
+
+/// Smallest frame: two header bytes, length, genre and footer.
+const MIN_FRAME_LEN: usize = 5;
+
+#[derive(Clone, Debug, Default)]
+pub struct Parser {
+    buffer: Vec<u8>,
+    discarded: usize,
+}
+
+impl Parser {
+    pub fn new() -> Parser {
+        Parser::default()
+    }
+    /// Appends `data` and returns every frame it completes, in stream order.
+    pub fn push_bytes(&mut self, data: &[u8]) -> Vec<RawFrame> {
+        self.buffer.extend_from_slice(data);
+        let mut frames = Vec::new();
+        while let Some(frame) = self.next_frame() {
+            frames.push(frame);
+        }
+        frames
+    }
+    /// Takes the next complete frame from the buffered bytes, discarding any
+    /// noise before it. Returns `None` when more bytes are needed.
+    ///
+    /// A header whose frame has not fully arrived is kept, but a complete
+    /// frame found after it wins: a corrupted length byte then costs one
+    /// frame rather than stalling the stream.
+    pub fn next_frame(&mut self) -> Option<RawFrame> {
+        let mut pending = None;
+        let mut i = 0;
+        while i + MIN_FRAME_LEN <= self.buffer.len() {
+            let b = &self.buffer;
+            let len = b[i + 2] as usize;
+            let end = i + 3 + len;
+            if b[i] != Command::HEADER || b[i + 1] != Command::HEADER || len < 2 {
+                i += 1;
+                continue;
+            }
+            if end > b.len() {
+                pending.get_or_insert(i);
+                i += 1;
+                continue;
+            }
+            if b[end - 1] != Command::FOOTER {
+                i += 1;
+                continue;
+            }
+            let frame = RawFrame {
+                genre: b[i + 3],
+                payload: b[(i + 4)..(end - 1)].to_vec(),
+                raw: b[i..end].to_vec(),
+            };
+            self.discarded += i;
+            self.buffer.drain(..end);
+            return Some(frame);
+        }
+        // Keep a pending header, or the tail that may still become one.
+        let keep = pending.unwrap_or(i);
+        self.discarded += keep;
+        self.buffer.drain(..keep);
+        None
+    }
+    /// Bytes held back waiting for the rest of a frame.
+    pub fn buffered(&self) -> &[u8] {
+        &self.buffer
+    }
+    /// Total bytes skipped because they did not belong to a frame.
+    pub fn discarded(&self) -> usize {
+        self.discarded
+    }
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(genre: u8, data: &[u8]) -> Vec<u8> {
+        [
+            &[
+                Command::HEADER,
+                Command::HEADER,
+                data.len() as u8 + 2,
+                genre,
+            ][..],
+            data,
+            &[Command::FOOTER],
+        ]
+        .concat()
+    }
+
+    fn frame_with_crc(genre: u8, data: &[u8]) -> Vec<u8> {
+        let crc = crc16(&[&[genre], data].concat());
+        frame(genre, &[data, &crc.to_be_bytes()[..]].concat())
+    }
+
+    #[test]
+    fn split_frame_completes_on_last_byte() {
+        let bytes = frame(0x20, &[0x01, 0x02, 0x03, 0x04]);
+        let mut parser = Parser::new();
+        for &b in &bytes[..bytes.len() - 1] {
+            assert!(parser.push_bytes(&[b]).is_empty());
+        }
+        let frames = parser.push_bytes(&bytes[bytes.len() - 1..]);
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].genre, 0x20);
+        assert_eq!(frames[0].payload, vec![0x01, 0x02, 0x03, 0x04]);
+        assert_eq!(frames[0].raw, bytes);
+        assert!(parser.buffered().is_empty());
+        assert_eq!(parser.discarded(), 0);
+    }
+
+    #[test]
+    fn concatenated_frames_come_out_in_order() {
+        let bytes = [frame(0x01, &[7]), frame(0x20, &[]), frame(0x23, &[1, 2])].concat();
+        let frames = Parser::new().push_bytes(&bytes);
+        let genres: Vec<u8> = frames.iter().map(|f| f.genre).collect();
+        assert_eq!(genres, vec![0x01, 0x20, 0x23]);
+        assert_eq!(frames[2].payload, vec![1, 2]);
+    }
+
+    #[test]
+    fn resynchronizes_after_garbage() {
+        let noise = [0x01, Command::HEADER, 0x02, Command::FOOTER, 0x55];
+        let bytes = [
+            &noise[..],
+            &frame(0x12, &[1])[..],
+            &noise[..],
+            &frame(0x2B, &[0])[..],
+        ]
+        .concat();
+        let mut parser = Parser::new();
+        let frames = parser.push_bytes(&bytes);
+        let genres: Vec<u8> = frames.iter().map(|f| f.genre).collect();
+        assert_eq!(genres, vec![0x12, 0x2B]);
+        assert_eq!(parser.discarded(), 2 * noise.len());
+    }
+
+    #[test]
+    fn corrupted_length_costs_one_frame() {
+        let bytes = [
+            &[Command::HEADER, Command::HEADER, 0x40, 0x20][..],
+            &frame(0x17, &[1])[..],
+        ]
+        .concat();
+        let frames = Parser::new().push_bytes(&bytes);
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].genre, 0x17);
+    }
+
+    #[test]
+    fn crc_mismatch_is_detected_and_stream_continues() {
+        let good = frame_with_crc(0x20, &[0x11, 0x22]);
+        let mut bad = frame_with_crc(0x20, &[0x11, 0x22]);
+        bad[4] ^= 0xFF;
+        let bytes = [bad, good].concat();
+        let frames = Parser::new().push_bytes(&bytes);
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].checked_data(), None);
+        assert_eq!(frames[1].checked_data(), Some(&[0x11, 0x22][..]));
+    }
+
+    /// Deterministic xorshift so the property runs without extra dependencies.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+        fn below(&mut self, n: usize) -> usize {
+            (self.next() % n as u64) as usize
+        }
+        /// Random bytes, biased towards header, footer and small lengths so
+        /// that frame-like sequences show up often.
+        fn byte(&mut self) -> u8 {
+            match self.below(4) {
+                0 => Command::HEADER,
+                1 => Command::FOOTER,
+                2 => self.below(8) as u8,
+                _ => self.next() as u8,
+            }
+        }
+    }
+
+    #[test]
+    fn arbitrary_bytes_never_panic_and_yield_well_formed_frames() {
+        let mut rng = Rng(0x9E37_79B9_7F4A_7C15);
+        for _ in 0..2000 {
+            let mut parser = Parser::new();
+            let bytes: Vec<u8> = (0..rng.below(96)).map(|_| rng.byte()).collect();
+            let mut rest = &bytes[..];
+            let mut frames = Vec::new();
+            while !rest.is_empty() {
+                let (chunk, tail) = rest.split_at(1 + rng.below(rest.len()));
+                frames.extend(parser.push_bytes(chunk));
+                rest = tail;
+            }
+            for frame in &frames {
+                let raw = &frame.raw;
+                assert_eq!(raw[..2], [Command::HEADER, Command::HEADER]);
+                assert_eq!(raw[raw.len() - 1], Command::FOOTER);
+                assert_eq!(raw.len(), raw[2] as usize + 3);
+                assert_eq!(frame.genre, raw[3]);
+                assert_eq!(frame.payload, raw[4..raw.len() - 1]);
+            }
+            let consumed: usize = frames.iter().map(|f| f.raw.len()).sum();
+            assert_eq!(
+                consumed + parser.discarded() + parser.buffered().len(),
+                bytes.len()
+            );
+        }
+    }
+}
@@ -0,0 +1,41 @@
+//! Slide rail treated as a 7th axis. The robot protocol has no documented
+//! rail genre, so the rail is driven through a `RailAxis` supplied by the
+//! application, typically wrapping the rail's own controller.
+
+use super::io::Connection;
+use super::operator::MyCobotOperator;
+use anyhow::Result;
+
+/// Moves and reads a slide rail, in millimetres.
+pub trait RailAxis: Send {
+    fn set_position(&mut self, position: f64, speed: u8) -> Result<()>;
+    fn position(&mut self) -> Result<f64>;
+}
+
+impl<T: Connection> MyCobotOperator<T> {
+    /// The rail moved by `set_rail_position` and by trajectories whose
+    /// waypoints carry a rail position; `None` removes it.
+    pub fn set_rail_axis(&mut self, rail: Option<Box<dyn RailAxis>>) {
+        self.rail = rail;
+    }
+    /// Moves the slide rail to `position` millimetres. Fails when no rail
+    /// axis is set, and is refused like other motion while the e-stop is latched.
+    pub fn set_rail_position(&mut self, position: f64, speed: u8) -> Result<()> {
+        if self.estop.latched {
+            return Err(anyhow::anyhow!(
+                "Rail motion refused: emergency stop is latched"
+            ));
+        }
+        match &mut self.rail {
+            Some(rail) => rail.set_position(position, speed),
+            None => Err(anyhow::anyhow!("No rail axis set; see set_rail_axis")),
+        }
+    }
+    /// Slide rail position in millimetres.
+    pub fn get_rail_position(&mut self) -> Result<f64> {
+        match &mut self.rail {
+            Some(rail) => rail.position(),
+            None => Err(anyhow::anyhow!("No rail axis set; see set_rail_axis")),
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! Random targets for repeatability tests and demos. Poses are drawn in
+//! joint space and mapped through forward kinematics, so every pose returned
+//! is reachable, within the joint limits and inside the requested region.
+
+use super::kinematics::KinematicModel;
+use super::limits::{JointLimits, Workspace};
+use super::transform::Pose;
+use rand::Rng;
+
+#[derive(Clone, Debug)]
+pub struct PoseConstraints {
+    pub model: KinematicModel,
+    /// Defaults to the model's limits.
+    pub joint_limits: JointLimits,
+    pub workspace: Option<Workspace>,
+    /// Axis-aligned box `(min, max)` in mm the tool position must fall in.
+    pub region: Option<([f64; 3], [f64; 3])>,
+    /// Tool center point in the flange frame; `None` constrains the flange.
+    pub tool: Option<Pose>,
+    /// Joint samples drawn before giving up.
+    pub max_attempts: usize,
+}
+
+impl PoseConstraints {
+    pub fn new(model: KinematicModel) -> PoseConstraints {
+        PoseConstraints {
+            joint_limits: JointLimits::from_model(&model),
+            model,
+            workspace: None,
+            region: None,
+            tool: None,
+            max_attempts: 1000,
+        }
+    }
+    pub fn with_workspace(mut self, workspace: Workspace) -> PoseConstraints {
+        self.workspace = Some(workspace);
+        self
+    }
+    pub fn with_region(mut self, min: [f64; 3], max: [f64; 3]) -> PoseConstraints {
+        self.region = Some((min, max));
+        self
+    }
+    pub fn with_tool(mut self, tool: Pose) -> PoseConstraints {
+        self.tool = Some(tool);
+        self
+    }
+    fn accepts(&self, pose: &Pose) -> bool {
+        let in_workspace = self
+            .workspace
+            .as_ref()
+            .is_none_or(|w| w.contains(&pose.to_coords()));
+        let in_region = self.region.as_ref().is_none_or(|(min, max)| {
+            (0..3).all(|i| min[i] <= pose.position[i] && pose.position[i] <= max[i])
+        });
+        in_workspace && in_region
+    }
+}
+
+impl Default for PoseConstraints {
+    fn default() -> PoseConstraints {
+        PoseConstraints::new(KinematicModel::mycobot_280())
+    }
+}
+
+/// A random target with the joint angles that reach it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RandomPose {
+    pub angles: [f64; 6],
+    /// Tool pose as coords.
+    pub coords: [f64; 6],
+}
+
+/// Uniform joint angles within the MyCobot 280 limits.
+pub fn random_joint_angles<R: Rng + ?Sized>(rng: &mut R) -> [f64; 6] {
+    random_joint_angles_within(
+        rng,
+        &JointLimits::from_model(&KinematicModel::mycobot_280()),
+    )
+}
+
+pub fn random_joint_angles_within<R: Rng + ?Sized>(rng: &mut R, limits: &JointLimits) -> [f64; 6] {
+    let mut angles = [0.0; 6];
+    for (i, a) in angles.iter_mut().enumerate() {
+        *a = if limits.min[i] < limits.max[i] {
+            rng.gen_range(limits.min[i]..=limits.max[i])
+        } else {
+            limits.min[i]
+        };
+    }
+    angles
+}
+
+/// A random reachable pose satisfying `constraints`, `None` when none was
+/// found within `max_attempts` samples.
+pub fn random_pose<R: Rng + ?Sized>(
+    rng: &mut R,
+    constraints: &PoseConstraints,
+) -> Option<RandomPose> {
+    for _ in 0..constraints.max_attempts {
+        let angles = random_joint_angles_within(rng, &constraints.joint_limits);
+        let flange = constraints.model.forward(&angles);
+        let pose = match &constraints.tool {
+            Some(tool) => flange.compose(tool),
+            None => flange,
+        };
+        if constraints.accepts(&pose) {
+            return Some(RandomPose {
+                angles,
+                coords: pose.to_coords(),
+            });
+        }
+    }
+    None
+}
@@ -0,0 +1,236 @@
+use super::common::Command;
+use super::io::{Connection, Serial};
+use anyhow::{Error, Result};
+use std::io;
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReconnectEvent {
+    Disconnected,
+    Reconnected { attempts: usize },
+    Failed { attempts: usize },
+}
+
+type Opener<C> = Box<dyn FnMut() -> Result<C> + Send>;
+type EventCallback = Box<dyn FnMut(&ReconnectEvent) + Send>;
+
+/// Connection wrapper that re-opens the transport when the device vanishes
+/// and replays the last power and speed commands afterwards.
+pub struct AutoReconnect<C: Connection> {
+    /// `None` between dropping a dead connection and opening its
+    /// replacement, so the old port's lock is released before reopening.
+    connection: Option<C>,
+    open: Opener<C>,
+    on_event: Option<EventCallback>,
+    retry_interval: Duration,
+    max_wait: Duration,
+    power_frame: Option<Vec<u8>>,
+    speed_frame: Option<Vec<u8>>,
+    ping_frame: Vec<u8>,
+    read_timeout: Option<Duration>,
+}
+
+impl<C: Connection> AutoReconnect<C> {
+    pub fn new<F>(mut open: F) -> Result<AutoReconnect<C>>
+    where
+        F: FnMut() -> Result<C> + Send + 'static,
+    {
+        let connection = open()?;
+        Ok(AutoReconnect {
+            connection: Some(connection),
+            open: Box::new(open),
+            on_event: None,
+            retry_interval: Duration::from_millis(500),
+            max_wait: Duration::from_secs(30),
+            power_frame: None,
+            speed_frame: None,
+            ping_frame: vec![
+                Command::HEADER,
+                Command::HEADER,
+                2,
+                Command::GetRobotVersion.as_u8(),
+                Command::FOOTER,
+            ],
+            read_timeout: None,
+        })
+    }
+    pub fn retry_interval(mut self, interval: Duration) -> AutoReconnect<C> {
+        self.retry_interval = interval;
+        self
+    }
+    pub fn max_wait(mut self, max_wait: Duration) -> AutoReconnect<C> {
+        self.max_wait = max_wait;
+        self
+    }
+    /// Frame sent by `health_check`; the default asks for the robot version
+    /// without a checksum. Firmware that requires checksums needs its own frame.
+    pub fn ping_frame(mut self, frame: Vec<u8>) -> AutoReconnect<C> {
+        self.ping_frame = frame;
+        self
+    }
+    pub fn on_event<F>(mut self, callback: F) -> AutoReconnect<C>
+    where
+        F: FnMut(&ReconnectEvent) + Send + 'static,
+    {
+        self.on_event = Some(Box::new(callback));
+        self
+    }
+    fn notify(&mut self, event: ReconnectEvent) {
+        if let Some(callback) = self.on_event.as_mut() {
+            callback(&event);
+        }
+    }
+    fn remember(&mut self, command: &[u8]) {
+        match command.get(3).copied().and_then(Command::from_u8) {
+            Some(Command::PowerOn) | Some(Command::PowerOff) => {
+                self.power_frame = Some(command.to_vec())
+            }
+            Some(Command::SetSpeed) => self.speed_frame = Some(command.to_vec()),
+            _ => {}
+        }
+    }
+    fn connected(&mut self) -> Result<&mut C> {
+        self.connection
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("Not connected; the last reconnect failed"))
+    }
+    fn healthy(&mut self) -> bool {
+        self.connection.as_mut().is_some_and(|c| c.is_healthy())
+    }
+    pub fn reconnect(&mut self) -> Result<()> {
+        self.notify(ReconnectEvent::Disconnected);
+        self.connection = None;
+        let start = Instant::now();
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            match (self.open)() {
+                Ok(mut connection) => {
+                    if let Some(timeout) = self.read_timeout {
+                        connection.set_read_timeout(timeout)?;
+                    }
+                    self.connection = Some(connection);
+                    break;
+                }
+                Err(e) => {
+                    if start.elapsed() >= self.max_wait {
+                        self.notify(ReconnectEvent::Failed { attempts });
+                        return Err(e.context("Failed to reconnect"));
+                    }
+                    thread::sleep(self.retry_interval);
+                }
+            }
+        }
+        for frame in [self.power_frame.clone(), self.speed_frame.clone()]
+            .iter()
+            .flatten()
+        {
+            self.connected()?.write(frame)?;
+        }
+        self.notify(ReconnectEvent::Reconnected { attempts });
+        Ok(())
+    }
+    /// Pings the robot and reconnects when it doesn't answer. Returns the
+    /// round-trip time of the successful ping.
+    pub fn health_check(&mut self) -> Result<Duration> {
+        let frame = self.ping_frame.clone();
+        let start = Instant::now();
+        match self.connected().and_then(|c| c.write_and_read(&frame)) {
+            Ok(res) if !res.is_empty() => return Ok(start.elapsed()),
+            _ => self.reconnect()?,
+        }
+        let start = Instant::now();
+        let res = self.connected()?.write_and_read(&frame)?;
+        if res.is_empty() {
+            return Err(anyhow::anyhow!("No reply to ping after reconnecting"));
+        }
+        Ok(start.elapsed())
+    }
+    fn with_retry<R, F>(&mut self, mut f: F) -> Result<R>
+    where
+        F: FnMut(&mut C) -> Result<R>,
+    {
+        let res = match self.connection.as_mut() {
+            Some(connection) => f(connection),
+            None => Err(anyhow::anyhow!("Not connected")),
+        };
+        match res {
+            Err(e) if is_disconnect(&e) || !self.healthy() => {
+                self.reconnect()?;
+                f(self.connected()?)
+            }
+            res => res,
+        }
+    }
+}
+
+impl AutoReconnect<Serial> {
+    pub fn serial(port: &str, baudrate: u32) -> Result<AutoReconnect<Serial>> {
+        let port = port.to_string();
+        AutoReconnect::new(move || Serial::open(&port, baudrate))
+    }
+}
+
+impl<C: Connection> Connection for AutoReconnect<C> {
+    fn write(&mut self, command: &[u8]) -> Result<()> {
+        self.remember(command);
+        self.with_retry(|c| c.write(command))
+    }
+    fn read(&mut self) -> Result<Vec<u8>> {
+        let data = self.with_retry(|c| c.read())?;
+        if data.is_empty() && !self.healthy() {
+            self.reconnect()?;
+        }
+        Ok(data)
+    }
+    fn write_and_read(&mut self, command: &[u8]) -> Result<Vec<u8>> {
+        self.remember(command);
+        let data = self.with_retry(|c| c.write_and_read(command))?;
+        if data.is_empty() && !self.healthy() {
+            self.reconnect()?;
+            return self.connected()?.write_and_read(command);
+        }
+        Ok(data)
+    }
+    fn is_healthy(&mut self) -> bool {
+        self.healthy()
+    }
+    fn set_read_timeout(&mut self, timeout: Duration) -> Result<()> {
+        self.read_timeout = Some(timeout);
+        match self.connection.as_mut() {
+            Some(connection) => connection.set_read_timeout(timeout),
+            None => Ok(()),
+        }
+    }
+    fn clear_input_buffer(&mut self) -> Result<()> {
+        self.with_retry(|c| c.clear_input_buffer())
+    }
+    fn flush(&mut self) -> Result<()> {
+        self.with_retry(|c| c.flush())
+    }
+}
+
+fn is_io_disconnect(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::NotFound | io::ErrorKind::BrokenPipe | io::ErrorKind::NotConnected
+    ) || matches!(e.raw_os_error(), Some(2) | Some(5) | Some(6) | Some(19))
+}
+
+fn is_disconnect(e: &Error) -> bool {
+    if let Some(e) = e.downcast_ref::<io::Error>() {
+        return is_io_disconnect(e);
+    }
+    if let Some(e) = e.downcast_ref::<serialport::Error>() {
+        return match e.kind() {
+            serialport::ErrorKind::NoDevice => true,
+            serialport::ErrorKind::Io(kind) => matches!(
+                kind,
+                io::ErrorKind::NotFound | io::ErrorKind::BrokenPipe | io::ErrorKind::NotConnected
+            ),
+            _ => false,
+        };
+    }
+    false
+}
@@ -0,0 +1,191 @@
+//! The command surface of `MyCobotOperator` as an object-safe trait, so
+//! application code can take `&mut dyn RobotApi` and be driven by a mock or
+//! simulator in tests. Connection, configuration and helpers generic over
+//! closures stay on the concrete type.
+
+use super::common::*;
+use super::io::Connection;
+use super::motion::{AngleMoveResult, CoordMoveResult};
+use super::operator::MyCobotOperator;
+use super::transform::Pose;
+use anyhow::Result;
+
+pub trait RobotApi {
+    fn power_on(&mut self) -> Result<()>;
+    fn power_off(&mut self) -> Result<()>;
+    fn is_power_on(&mut self) -> Result<i32>;
+    fn release_all_servos(&mut self) -> Result<()>;
+    fn focus_servo(&mut self, servo_id: Angle) -> Result<()>;
+    fn release_servo(&mut self, servo_id: Angle) -> Result<()>;
+
+    fn get_angles(&mut self) -> Result<[f64; 6]>;
+    fn send_angle(&mut self, id: Angle, degree: f64, speed: u8) -> Result<()>;
+    fn send_angles(&mut self, degrees: &[f64; 6], speed: u8) -> Result<()>;
+    fn get_coords(&mut self) -> Result<[f64; 6]>;
+    fn send_coord(&mut self, id: Coord, coord: f64, speed: u8) -> Result<()>;
+    fn send_coords(&mut self, coords: &[f64; 6], speed: u8, mode: Mode) -> Result<()>;
+    fn get_pose(&mut self) -> Result<Pose>;
+    fn set_pose(&mut self, pose: &Pose, speed: u8, mode: Mode) -> Result<()>;
+    fn sync_send_angles(
+        &mut self,
+        degrees: &[f64; 6],
+        speed: u8,
+        timeout_secs: f64,
+    ) -> Result<AngleMoveResult>;
+    fn sync_send_coords(
+        &mut self,
+        coords: &[f64; 6],
+        speed: u8,
+        mode: Mode,
+        timeout_secs: f64,
+    ) -> Result<CoordMoveResult>;
+    fn is_in_angle_position(&mut self, degrees: &[f64; 6]) -> Result<i32>;
+    fn is_in_coord_position(&mut self, coords: &[f64; 6]) -> Result<i32>;
+    fn is_moving(&mut self) -> Result<i32>;
+
+    fn jog_angle(&mut self, id: Angle, direction: Direction, speed: u8) -> Result<()>;
+    fn jog_coord(&mut self, id: Coord, direction: Direction, speed: u8) -> Result<()>;
+    fn jog_stop(&mut self) -> Result<()>;
+    fn pause(&mut self) -> Result<()>;
+    fn resume(&mut self) -> Result<()>;
+    fn stop(&mut self) -> Result<()>;
+    fn emergency_stop(&mut self) -> Result<()>;
+
+    fn tool_pose(&self) -> Option<&Pose>;
+    fn set_tool_pose(&mut self, tool: Option<Pose>);
+
+    fn set_color(&mut self, r: u8, g: u8, b: u8) -> Result<()>;
+    fn set_digital_output(&mut self, pin_no: u8, pin_signal: bool) -> Result<()>;
+    fn get_digital_input(&mut self, pin_no: u8) -> Result<i32>;
+    fn set_basic_output(&mut self, pin_no: u8, pin_signal: bool) -> Result<()>;
+    fn get_basic_input(&mut self, pin_no: u8) -> Result<i32>;
+    fn get_gripper_value(&mut self) -> Result<Vec<i16>>;
+    fn set_gripper_state(&mut self, state: GripperState, speed: u8) -> Result<()>;
+    fn set_gripper_value(&mut self, value: u8, speed: u8) -> Result<()>;
+    fn is_gripper_moving(&mut self) -> Result<i32>;
+}
+
+impl<T: Connection> RobotApi for MyCobotOperator<T> {
+    fn power_on(&mut self) -> Result<()> {
+        MyCobotOperator::power_on(self)
+    }
+    fn power_off(&mut self) -> Result<()> {
+        MyCobotOperator::power_off(self)
+    }
+    fn is_power_on(&mut self) -> Result<i32> {
+        MyCobotOperator::is_power_on(self)
+    }
+    fn release_all_servos(&mut self) -> Result<()> {
+        MyCobotOperator::release_all_servos(self)
+    }
+    fn focus_servo(&mut self, servo_id: Angle) -> Result<()> {
+        MyCobotOperator::focus_servo(self, servo_id)
+    }
+    fn release_servo(&mut self, servo_id: Angle) -> Result<()> {
+        MyCobotOperator::release_servo(self, servo_id)
+    }
+    fn get_angles(&mut self) -> Result<[f64; 6]> {
+        MyCobotOperator::get_angles(self)
+    }
+    fn send_angle(&mut self, id: Angle, degree: f64, speed: u8) -> Result<()> {
+        MyCobotOperator::send_angle(self, id, degree, speed)
+    }
+    fn send_angles(&mut self, degrees: &[f64; 6], speed: u8) -> Result<()> {
+        MyCobotOperator::send_angles(self, degrees, speed)
+    }
+    fn get_coords(&mut self) -> Result<[f64; 6]> {
+        MyCobotOperator::get_coords(self)
+    }
+    fn send_coord(&mut self, id: Coord, coord: f64, speed: u8) -> Result<()> {
+        MyCobotOperator::send_coord(self, id, coord, speed)
+    }
+    fn send_coords(&mut self, coords: &[f64; 6], speed: u8, mode: Mode) -> Result<()> {
+        MyCobotOperator::send_coords(self, coords, speed, mode)
+    }
+    fn get_pose(&mut self) -> Result<Pose> {
+        MyCobotOperator::get_pose(self)
+    }
+    fn set_pose(&mut self, pose: &Pose, speed: u8, mode: Mode) -> Result<()> {
+        MyCobotOperator::set_pose(self, pose, speed, mode)
+    }
+    fn sync_send_angles(
+        &mut self,
+        degrees: &[f64; 6],
+        speed: u8,
+        timeout_secs: f64,
+    ) -> Result<AngleMoveResult> {
+        MyCobotOperator::sync_send_angles(self, degrees, speed, timeout_secs)
+    }
+    fn sync_send_coords(
+        &mut self,
+        coords: &[f64; 6],
+        speed: u8,
+        mode: Mode,
+        timeout_secs: f64,
+    ) -> Result<CoordMoveResult> {
+        MyCobotOperator::sync_send_coords(self, coords, speed, mode, timeout_secs)
+    }
+    fn is_in_angle_position(&mut self, degrees: &[f64; 6]) -> Result<i32> {
+        MyCobotOperator::is_in_angle_position(self, degrees)
+    }
+    fn is_in_coord_position(&mut self, coords: &[f64; 6]) -> Result<i32> {
+        MyCobotOperator::is_in_coord_position(self, coords)
+    }
+    fn is_moving(&mut self) -> Result<i32> {
+        MyCobotOperator::is_moving(self)
+    }
+    fn jog_angle(&mut self, id: Angle, direction: Direction, speed: u8) -> Result<()> {
+        MyCobotOperator::jog_angle(self, id, direction, speed)
+    }
+    fn jog_coord(&mut self, id: Coord, direction: Direction, speed: u8) -> Result<()> {
+        MyCobotOperator::jog_coord(self, id, direction, speed)
+    }
+    fn jog_stop(&mut self) -> Result<()> {
+        MyCobotOperator::jog_stop(self)
+    }
+    fn pause(&mut self) -> Result<()> {
+        MyCobotOperator::pause(self)
+    }
+    fn resume(&mut self) -> Result<()> {
+        MyCobotOperator::resume(self)
+    }
+    fn stop(&mut self) -> Result<()> {
+        MyCobotOperator::stop(self)
+    }
+    fn emergency_stop(&mut self) -> Result<()> {
+        MyCobotOperator::emergency_stop(self)
+    }
+    fn tool_pose(&self) -> Option<&Pose> {
+        MyCobotOperator::tool_pose(self)
+    }
+    fn set_tool_pose(&mut self, tool: Option<Pose>) {
+        MyCobotOperator::set_tool_pose(self, tool)
+    }
+    fn set_color(&mut self, r: u8, g: u8, b: u8) -> Result<()> {
+        MyCobotOperator::set_color(self, r, g, b)
+    }
+    fn set_digital_output(&mut self, pin_no: u8, pin_signal: bool) -> Result<()> {
+        MyCobotOperator::set_digital_output(self, pin_no, pin_signal)
+    }
+    fn get_digital_input(&mut self, pin_no: u8) -> Result<i32> {
+        MyCobotOperator::get_digital_intput(self, pin_no)
+    }
+    fn set_basic_output(&mut self, pin_no: u8, pin_signal: bool) -> Result<()> {
+        MyCobotOperator::set_basic_output(self, pin_no, pin_signal)
+    }
+    fn get_basic_input(&mut self, pin_no: u8) -> Result<i32> {
+        MyCobotOperator::get_basic_input(self, pin_no)
+    }
+    fn get_gripper_value(&mut self) -> Result<Vec<i16>> {
+        MyCobotOperator::get_gripper_value(self)
+    }
+    fn set_gripper_state(&mut self, state: GripperState, speed: u8) -> Result<()> {
+        MyCobotOperator::set_gripper_state(self, state, speed)
+    }
+    fn set_gripper_value(&mut self, value: u8, speed: u8) -> Result<()> {
+        MyCobotOperator::set_gripper_value(self, value, speed)
+    }
+    fn is_gripper_moving(&mut self) -> Result<i32> {
+        MyCobotOperator::is_gripper_moving(self)
+    }
+}
@@ -0,0 +1,157 @@
+//! Rhai scripts driving a shared operator, for automation authored at runtime:
+//!
+//! ```text
+//! for i in 0..3 {
+//!     move_j([0.0, 0.0, 0.0, 0.0, 0.0, 0.0], 50);
+//!     move_l([150, -60, 120, -180, 0, -90], 50);
+//!     gripper_close();
+//!     wait(0.5);
+//!     gripper(70);
+//! }
+//! print(get_angles());
+//! ```
+//!
+//! Numbers may be integers or floats. Moves wait for arrival like `run_program`.
+
+use super::common::{GripperState, Mode};
+use super::io::Connection;
+use super::operator::MyCobotOperator;
+use anyhow::Result;
+use rhai::{Array, Dynamic, Engine, EvalAltResult, FLOAT, INT};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+type ScriptResult<R> = std::result::Result<R, Box<EvalAltResult>>;
+
+fn number(value: &Dynamic) -> ScriptResult<f64> {
+    value
+        .as_float()
+        .or_else(|_| value.as_int().map(|v| v as FLOAT))
+        .map_err(|t| format!("Expected a number, got {}", t).into())
+}
+
+fn six(values: &Array) -> ScriptResult<[f64; 6]> {
+    if values.len() != 6 {
+        return Err(format!("Expected 6 values, got {}", values.len()).into());
+    }
+    let mut res = [0.0; 6];
+    for (r, v) in res.iter_mut().zip(values) {
+        *r = number(v)?;
+    }
+    Ok(res)
+}
+
+fn byte(value: INT) -> ScriptResult<u8> {
+    if (0..=100).contains(&value) {
+        Ok(value as u8)
+    } else {
+        Err(format!("{} is not within 0-100", value).into())
+    }
+}
+
+fn to_array(values: &[f64; 6]) -> Array {
+    values.iter().map(|&v| Dynamic::from_float(v)).collect()
+}
+
+/// Runs `f` on the locked operator, turning errors into script errors.
+fn with<T, R, F>(operator: &Arc<Mutex<MyCobotOperator<T>>>, f: F) -> ScriptResult<R>
+where
+    T: Connection,
+    F: FnOnce(&mut MyCobotOperator<T>) -> Result<R>,
+{
+    let mut operator = operator
+        .lock()
+        .map_err(|_| Box::<EvalAltResult>::from("Operator mutex poisoned"))?;
+    f(&mut operator).map_err(|e| e.to_string().into())
+}
+
+/// A Rhai engine with the operator API registered.
+pub struct ScriptEngine {
+    engine: Engine,
+}
+
+impl ScriptEngine {
+    /// Registers `move_j`, `move_l`, `wait`, `gripper`, `gripper_open`,
+    /// `gripper_close`, `stop`, `get_angles`, `get_coords` and `is_moving`.
+    /// Moves wait up to `move_timeout_secs` to arrive.
+    pub fn new<T>(operator: Arc<Mutex<MyCobotOperator<T>>>, move_timeout_secs: f64) -> ScriptEngine
+    where
+        T: Connection + Send + 'static,
+    {
+        let mut engine = Engine::new();
+        let op = operator.clone();
+        engine.register_fn("move_j", move |angles: Array, speed: INT| {
+            let (angles, speed) = (six(&angles)?, byte(speed)?);
+            with(&op, |o| {
+                o.sync_send_angles(&angles, speed, move_timeout_secs)
+            })
+            .map(|_| ())
+        });
+        let op = operator.clone();
+        engine.register_fn("move_l", move |coords: Array, speed: INT| {
+            let (coords, speed) = (six(&coords)?, byte(speed)?);
+            with(&op, |o| {
+                o.sync_send_coords(&coords, speed, Mode::Linear, move_timeout_secs)
+            })
+            .map(|_| ())
+        });
+        engine.register_fn("wait", |secs: FLOAT| -> ScriptResult<()> {
+            let wait = Duration::try_from_secs_f64(secs)
+                .map_err(|e| format!("wait needs a non-negative number of seconds: {}", e))?;
+            thread::sleep(wait);
+            Ok(())
+        });
+        engine.register_fn("wait", |secs: INT| -> ScriptResult<()> {
+            if secs < 0 {
+                return Err(
+                    format!("wait needs a non-negative number of seconds, got {}", secs).into(),
+                );
+            }
+            thread::sleep(Duration::from_secs(secs as u64));
+            Ok(())
+        });
+        let op = operator.clone();
+        engine.register_fn("gripper", move |value: INT| {
+            let value = byte(value)?;
+            with(&op, |o| o.set_gripper_value(value, 50))
+        });
+        let op = operator.clone();
+        engine.register_fn("gripper_open", move || {
+            with(&op, |o| o.set_gripper_state(GripperState::Open, 50))
+        });
+        let op = operator.clone();
+        engine.register_fn("gripper_close", move || {
+            with(&op, |o| o.set_gripper_state(GripperState::Close, 50))
+        });
+        let op = operator.clone();
+        engine.register_fn("stop", move || with(&op, |o| o.stop()));
+        let op = operator.clone();
+        engine.register_fn("get_angles", move || {
+            with(&op, |o| o.get_angles()).map(|a| to_array(&a))
+        });
+        let op = operator.clone();
+        engine.register_fn("get_coords", move || {
+            with(&op, |o| o.get_coords()).map(|c| to_array(&c))
+        });
+        engine.register_fn("is_moving", move || {
+            with(&operator, |o| o.is_moving()).map(|m| m == 1)
+        });
+        ScriptEngine { engine }
+    }
+    /// The underlying engine, for registering more functions or setting limits.
+    pub fn engine_mut(&mut self) -> &mut Engine {
+        &mut self.engine
+    }
+    pub fn run(&self, script: &str) -> Result<()> {
+        self.engine
+            .run(script)
+            .map_err(|e| anyhow::anyhow!("Script failed: {}", e))
+    }
+    pub fn run_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.engine
+            .run_file(path.as_ref().to_path_buf())
+            .map_err(|e| anyhow::anyhow!("{}: {}", path.as_ref().display(), e))
+    }
+}
@@ -0,0 +1,231 @@
+use super::common::Mode;
+use super::io::Connection;
+use super::kinematics::KinematicModel;
+use super::operator::MyCobotOperator;
+use super::transform::*;
+use anyhow::Result;
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Cartesian velocity in the base frame: mm/s and deg/s.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Twist {
+    pub linear: [f64; 3],
+    pub angular: [f64; 3],
+}
+
+impl Twist {
+    pub fn new(vx: f64, vy: f64, vz: f64, wx: f64, wy: f64, wz: f64) -> Twist {
+        Twist {
+            linear: [vx, vy, vz],
+            angular: [wx, wy, wz],
+        }
+    }
+    /// Pose reached after applying this twist to `coords` for `dt` seconds.
+    pub fn integrate(&self, coords: &[f64; 6], dt: f64) -> [f64; 6] {
+        let pose = Pose::from_coords(coords);
+        let w = self.angular.map(|v| (v * dt).to_radians());
+        let angle = norm(&w);
+        let rotation = if angle < 1e-12 {
+            pose.rotation
+        } else {
+            let axis = w.map(|v| v / angle);
+            mat_mul(&axis_angle_to_matrix(&axis, angle), &pose.rotation)
+        };
+        let position = add(&pose.position, &self.linear.map(|v| v * dt));
+        Pose::new(position, rotation).to_coords()
+    }
+}
+
+#[derive(Clone)]
+pub struct ServoConfig {
+    /// Streaming rate, clamped to 20-50 Hz.
+    pub rate_hz: f64,
+    pub speed: u8,
+    pub mode: Mode,
+}
+
+impl Default for ServoConfig {
+    fn default() -> ServoConfig {
+        ServoConfig {
+            rate_hz: 30.0,
+            speed: 80,
+            mode: Mode::Linear,
+        }
+    }
+}
+
+pub(crate) fn stream_period(rate_hz: f64) -> Duration {
+    Duration::from_secs_f64(1.0 / rate_hz.clamp(20.0, 50.0))
+}
+
+impl<T: Connection> MyCobotOperator<T> {
+    /// Streams `send_coords` updates integrating the twist returned by `next_twist`
+    /// (called once per cycle with the current target) until it returns `None`.
+    pub fn servo_cartesian<F>(&mut self, config: &ServoConfig, mut next_twist: F) -> Result<()>
+    where
+        F: FnMut(&[f64; 6]) -> Option<Twist>,
+    {
+        let period = stream_period(config.rate_hz);
+        let mut target = self.get_coords()?;
+        let mut last = Instant::now();
+        while let Some(twist) = next_twist(&target) {
+            let now = Instant::now();
+            let dt = now.duration_since(last).as_secs_f64();
+            last = now;
+            target = twist.integrate(&target, dt);
+            self.send_coords(&target, config.speed, config.mode)?;
+            let elapsed = now.elapsed();
+            if elapsed < period {
+                thread::sleep(period - elapsed);
+            }
+        }
+        self.stop()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct JointStreamConfig {
+    /// Streaming rate, clamped to 20-50 Hz.
+    pub rate_hz: f64,
+    pub speed: u8,
+    /// Stops the arm when no velocity command arrives within this duration.
+    pub watchdog: Duration,
+    /// Integrated targets are clamped to these limits, in degrees.
+    pub limits: [(f64, f64); 6],
+    /// Slows and then refuses motion towards singular configurations.
+    pub singularity: Option<SingularityGuard>,
+}
+
+/// Manipulability thresholds for streamed joint motion. Below `slow_below`
+/// velocities are scaled down linearly, reaching zero at `stop_below`. Motion
+/// that increases manipulability is always allowed, so the arm can back out.
+#[derive(Clone, Debug)]
+pub struct SingularityGuard {
+    pub model: KinematicModel,
+    pub slow_below: f64,
+    pub stop_below: f64,
+}
+
+impl SingularityGuard {
+    /// Factor in `0.0..=1.0` applied to a step from `from` to `to`.
+    pub fn scale(&self, from: &[f64; 6], to: &[f64; 6]) -> f64 {
+        let (before, after) = (
+            self.model.manipulability(from),
+            self.model.manipulability(to),
+        );
+        if after >= before || after >= self.slow_below {
+            return 1.0;
+        }
+        let span = self.slow_below - self.stop_below;
+        if span <= 0.0 {
+            return if after > self.stop_below { 1.0 } else { 0.0 };
+        }
+        ((after - self.stop_below) / span).clamp(0.0, 1.0)
+    }
+}
+
+impl Default for JointStreamConfig {
+    fn default() -> JointStreamConfig {
+        JointStreamConfig {
+            rate_hz: 30.0,
+            speed: 80,
+            watchdog: Duration::from_millis(200),
+            limits: KinematicModel::mycobot_280().joint_limits,
+            singularity: None,
+        }
+    }
+}
+
+/// Integrates joint velocities (deg/s) and streams `send_angles` at a fixed rate.
+pub struct JointVelocityStreamer {
+    config: JointStreamConfig,
+    velocities: [f64; 6],
+    target: [f64; 6],
+    last_command: Instant,
+    last_tick: Instant,
+}
+
+impl JointVelocityStreamer {
+    pub fn new<T: Connection>(
+        operator: &mut MyCobotOperator<T>,
+        config: JointStreamConfig,
+    ) -> Result<JointVelocityStreamer> {
+        let target = operator.get_angles()?;
+        let now = Instant::now();
+        Ok(JointVelocityStreamer {
+            config,
+            velocities: [0.0; 6],
+            target,
+            last_command: now,
+            last_tick: now,
+        })
+    }
+    pub fn target(&self) -> &[f64; 6] {
+        &self.target
+    }
+    /// Sets the commanded velocities and feeds the watchdog.
+    pub fn stream_joint_velocities(&mut self, velocities: &[f64; 6]) {
+        self.velocities = *velocities;
+        self.last_command = Instant::now();
+    }
+    /// Waits for the next cycle and sends the integrated target.
+    /// Returns `false` after stopping the arm because the watchdog expired.
+    pub fn spin<T: Connection>(&mut self, operator: &mut MyCobotOperator<T>) -> Result<bool> {
+        let period = stream_period(self.config.rate_hz);
+        let elapsed = self.last_tick.elapsed();
+        if elapsed < period {
+            thread::sleep(period - elapsed);
+        }
+        if self.last_command.elapsed() > self.config.watchdog {
+            self.velocities = [0.0; 6];
+            operator.stop()?;
+            return Ok(false);
+        }
+        let now = Instant::now();
+        let dt = now.duration_since(self.last_tick).as_secs_f64();
+        self.last_tick = now;
+        let mut next = self.target;
+        for ((target, v), (min, max)) in next
+            .iter_mut()
+            .zip(self.velocities.iter())
+            .zip(self.config.limits.iter())
+        {
+            *target = (*target + v * dt).clamp(*min, *max);
+        }
+        if let Some(guard) = &self.config.singularity {
+            let scale = guard.scale(&self.target, &next);
+            for (n, t) in next.iter_mut().zip(self.target.iter()) {
+                *n = t + (*n - t) * scale;
+            }
+        }
+        self.target = next;
+        operator.send_angles(&self.target, self.config.speed)?;
+        Ok(true)
+    }
+}
+
+impl<T: Connection> MyCobotOperator<T> {
+    /// Streams velocities received on `commands` until the sender is dropped
+    /// or the watchdog expires.
+    pub fn stream_joint_velocities(
+        &mut self,
+        config: JointStreamConfig,
+        commands: &Receiver<[f64; 6]>,
+    ) -> Result<()> {
+        let mut streamer = JointVelocityStreamer::new(self, config)?;
+        loop {
+            loop {
+                match commands.try_recv() {
+                    Ok(velocities) => streamer.stream_joint_velocities(&velocities),
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => return self.stop(),
+                }
+            }
+            if !streamer.spin(self)? {
+                return Ok(());
+            }
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use super::common::Mode;
+use super::io::Connection;
+use super::operator::MyCobotOperator;
+use anyhow::Result;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+type Job<T> = Box<dyn FnOnce(&mut MyCobotOperator<T>) + Send>;
+
+/// Stop requests that jump the job queue: the I/O thread checks the flag
+/// before every job and stops the arm first.
+#[derive(Default)]
+struct StopControl {
+    requested: AtomicBool,
+    completed: AtomicUsize,
+}
+
+/// Cheap-to-clone handle to an operator owned by a dedicated I/O thread.
+/// Requests from all clones are executed one at a time in arrival order;
+/// waits such as `sync_send_angles` sleep on the caller's thread, so they
+/// don't hold up other handles. The I/O thread exits when every handle is dropped.
+pub struct MyCobotHandle<T: Connection> {
+    sender: Sender<Job<T>>,
+    control: Arc<StopControl>,
+}
+
+impl<T: Connection> Clone for MyCobotHandle<T> {
+    fn clone(&self) -> MyCobotHandle<T> {
+        MyCobotHandle {
+            sender: self.sender.clone(),
+            control: self.control.clone(),
+        }
+    }
+}
+
+impl<T: Connection + Send + 'static> MyCobotOperator<T> {
+    pub fn into_shared(mut self) -> MyCobotHandle<T> {
+        let (sender, receiver) = mpsc::channel::<Job<T>>();
+        let control = Arc::new(StopControl::default());
+        let thread_control = control.clone();
+        thread::spawn(move || {
+            for job in receiver {
+                if thread_control.requested.swap(false, Ordering::SeqCst) {
+                    let _ = self.stop();
+                    thread_control.completed.fetch_add(1, Ordering::SeqCst);
+                }
+                job(&mut self);
+            }
+        });
+        MyCobotHandle { sender, control }
+    }
+}
+
+/// Requests a stop ahead of every queued job. Only sets a flag and queues
+/// an empty job, so it is safe to call from a signal handler's thread.
+#[derive(Clone)]
+pub struct StopTrigger {
+    control: Arc<StopControl>,
+    wake: Arc<dyn Fn() -> bool + Send + Sync>,
+}
+
+impl StopTrigger {
+    /// Returns `false` when the I/O thread has already exited.
+    pub fn trigger(&self) -> bool {
+        self.control.requested.store(true, Ordering::SeqCst);
+        (self.wake)()
+    }
+    /// Triggers and waits up to `timeout` for the stop to be sent.
+    pub fn trigger_and_wait(&self, timeout: Duration) -> bool {
+        let before = self.control.completed.load(Ordering::SeqCst);
+        if !self.trigger() {
+            return false;
+        }
+        let start = Instant::now();
+        while start.elapsed() < timeout {
+            if self.control.completed.load(Ordering::SeqCst) != before {
+                return true;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        false
+    }
+}
+
+impl<T: Connection + 'static> MyCobotHandle<T> {
+    /// Queues `f` on the I/O thread without waiting for it.
+    pub(crate) fn submit<F>(&self, f: F) -> Result<()>
+    where
+        F: FnOnce(&mut MyCobotOperator<T>) + Send + 'static,
+    {
+        self.sender
+            .send(Box::new(f))
+            .map_err(|_| anyhow::anyhow!("I/O thread stopped"))
+    }
+    /// Runs `f` on the I/O thread and waits for its result.
+    pub fn call<R, F>(&self, f: F) -> Result<R>
+    where
+        R: Send + 'static,
+        F: FnOnce(&mut MyCobotOperator<T>) -> Result<R> + Send + 'static,
+    {
+        let (reply, result) = mpsc::channel();
+        self.submit(move |op| {
+            let _ = reply.send(f(op));
+        })?;
+        result
+            .recv()
+            .map_err(|_| anyhow::anyhow!("I/O thread stopped"))?
+    }
+    /// A trigger for stopping the arm ahead of queued jobs, e.g. from a
+    /// Ctrl-C handler while another thread is mid-script.
+    pub fn stop_trigger(&self) -> StopTrigger {
+        let sender = self.sender.clone();
+        StopTrigger {
+            control: self.control.clone(),
+            wake: Arc::new(move || {
+                sender
+                    .send(Box::new(|_: &mut MyCobotOperator<T>| {}))
+                    .is_ok()
+            }),
+        }
+    }
+    /// Installs a Ctrl-C (SIGINT) handler that stops the arm ahead of any
+    /// queued jobs, waits up to a second for the stop to go out and then
+    /// exits with status 130, so an interrupted script never leaves the arm
+    /// mid-motion. Fails if the process already has a handler.
+    #[cfg(feature = "ctrlc")]
+    pub fn install_ctrlc_handler(&self) -> Result<()> {
+        let trigger = self.stop_trigger();
+        ctrlc::set_handler(move || {
+            trigger.trigger_and_wait(Duration::from_secs(1));
+            std::process::exit(130);
+        })?;
+        Ok(())
+    }
+    pub fn power_on(&self) -> Result<()> {
+        self.call(|op| op.power_on())
+    }
+    pub fn power_off(&self) -> Result<()> {
+        self.call(|op| op.power_off())
+    }
+    pub fn release_all_servos(&self) -> Result<()> {
+        self.call(|op| op.release_all_servos())
+    }
+    pub fn get_angles(&self) -> Result<[f64; 6]> {
+        self.call(|op| op.get_angles())
+    }
+    pub fn send_angles(&self, degrees: &[f64; 6], speed: u8) -> Result<()> {
+        let degrees = *degrees;
+        self.call(move |op| op.send_angles(&degrees, speed))
+    }
+    pub fn get_coords(&self) -> Result<[f64; 6]> {
+        self.call(|op| op.get_coords())
+    }
+    pub fn send_coords(&self, coords: &[f64; 6], speed: u8, mode: Mode) -> Result<()> {
+        let coords = *coords;
+        self.call(move |op| op.send_coords(&coords, speed, mode))
+    }
+    pub fn is_moving(&self) -> Result<i32> {
+        self.call(|op| op.is_moving())
+    }
+    pub fn pause(&self) -> Result<()> {
+        self.call(|op| op.pause())
+    }
+    pub fn resume(&self) -> Result<()> {
+        self.call(|op| op.resume())
+    }
+    pub fn stop(&self) -> Result<()> {
+        self.call(|op| op.stop())
+    }
+    pub fn sync_send_angles(&self, degrees: &[f64; 6], speed: u8, timeout_secs: f64) -> Result<()> {
+        let degrees = *degrees;
+        let start = Instant::now();
+        self.send_angles(&degrees, speed)?;
+        while start.elapsed().as_secs_f64() < timeout_secs {
+            if self.call(move |op| op.is_in_angle_position(&degrees))? == 1 {
+                break;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+        Ok(())
+    }
+    pub fn sync_send_coords(
+        &self,
+        coords: &[f64; 6],
+        speed: u8,
+        mode: Mode,
+        timeout_secs: f64,
+    ) -> Result<()> {
+        let coords = *coords;
+        let start = Instant::now();
+        self.send_coords(&coords, speed, mode)?;
+        while start.elapsed().as_secs_f64() < timeout_secs {
+            if self.call(move |op| op.is_in_coord_position(&coords))? == 1 {
+                break;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,89 @@
+use super::io::Connection;
+use super::operator::MyCobotOperator;
+use super::watchdog::WatchdogPolicy;
+use anyhow::Result;
+use std::ops::{Deref, DerefMut};
+
+#[derive(Clone, Debug)]
+pub struct ShutdownPolicy {
+    /// Angles to move to before the final action, e.g. a folded rest pose.
+    pub park: Option<[f64; 6]>,
+    pub speed: u8,
+    pub timeout_secs: f64,
+    pub action: WatchdogPolicy,
+}
+
+impl Default for ShutdownPolicy {
+    fn default() -> ShutdownPolicy {
+        ShutdownPolicy {
+            park: Some([0.0; 6]),
+            speed: 30,
+            timeout_secs: 15.0,
+            action: WatchdogPolicy::StopAndRelease,
+        }
+    }
+}
+
+impl<T: Connection> MyCobotOperator<T> {
+    /// Stops, parks (waiting for completion), applies the policy's action and
+    /// closes the connection by dropping the operator. A failing step does
+    /// not skip the ones after it, so the action always runs; the first error
+    /// is returned once it has.
+    pub fn shutdown(mut self, policy: &ShutdownPolicy) -> Result<()> {
+        let stopped = self.stop();
+        let parked = match &policy.park {
+            Some(park) => self
+                .sync_send_angles(park, policy.speed, policy.timeout_secs)
+                .map(|_| ()),
+            None => Ok(()),
+        };
+        let applied = policy.action.apply(&mut self);
+        stopped.and(parked).and(applied)
+    }
+}
+
+/// Owns an operator and runs its shutdown policy when dropped, including
+/// during a panic, so the arm is never left energized mid-task.
+pub struct ShutdownGuard<T: Connection> {
+    operator: Option<MyCobotOperator<T>>,
+    pub policy: ShutdownPolicy,
+}
+
+impl<T: Connection> ShutdownGuard<T> {
+    pub fn new(operator: MyCobotOperator<T>, policy: ShutdownPolicy) -> ShutdownGuard<T> {
+        ShutdownGuard {
+            operator: Some(operator),
+            policy,
+        }
+    }
+    /// Runs the shutdown now, returning its result instead of ignoring it on drop.
+    pub fn shutdown(mut self) -> Result<()> {
+        let operator = self.operator.take().unwrap();
+        operator.shutdown(&self.policy)
+    }
+    /// Disarms the guard and returns the operator untouched.
+    pub fn into_inner(mut self) -> MyCobotOperator<T> {
+        self.operator.take().unwrap()
+    }
+}
+
+impl<T: Connection> Deref for ShutdownGuard<T> {
+    type Target = MyCobotOperator<T>;
+    fn deref(&self) -> &MyCobotOperator<T> {
+        self.operator.as_ref().unwrap()
+    }
+}
+
+impl<T: Connection> DerefMut for ShutdownGuard<T> {
+    fn deref_mut(&mut self) -> &mut MyCobotOperator<T> {
+        self.operator.as_mut().unwrap()
+    }
+}
+
+impl<T: Connection> Drop for ShutdownGuard<T> {
+    fn drop(&mut self) {
+        if let Some(operator) = self.operator.take() {
+            let _ = operator.shutdown(&self.policy);
+        }
+    }
+}
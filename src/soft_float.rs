@@ -0,0 +1,105 @@
+//! Compliant hand guiding: the joints hold their targets with reduced torque,
+//! and a push that moves a joint past its deadband becomes the new target, so
+//! the arm stays where it was pushed instead of springing back or falling as
+//! it does after `release_all_servos`.
+//!
+//! Under reduced torque the loaded joints sag a little below their targets.
+//! That steady offset is estimated with a slow low-pass filter and excluded
+//! from the push detection, so gravity alone never drags the arm down.
+
+use super::io::Connection;
+use super::operator::MyCobotOperator;
+use super::servoing::stream_period;
+use anyhow::Result;
+use std::thread;
+use std::time::Instant;
+
+#[derive(Clone, Debug)]
+pub struct SoftFloatConfig {
+    /// Control rate, clamped to 20-50 Hz.
+    pub rate_hz: f64,
+    pub speed: u8,
+    /// Torque limit of each joint in percent, written to servo register
+    /// `torque_address` for the duration of the mode where the firmware
+    /// exposes it. `None` keeps full torque.
+    pub torque_limits: Option<[u8; 6]>,
+    pub torque_address: u8,
+    /// Displacement beyond the gravity sag, in degrees, taken as a push.
+    pub deadband: [f64; 6],
+    /// Time constant of the sag estimate in seconds.
+    pub sag_tau: f64,
+}
+
+impl Default for SoftFloatConfig {
+    fn default() -> SoftFloatConfig {
+        SoftFloatConfig {
+            rate_hz: 20.0,
+            speed: 50,
+            // The shoulder and elbow carry the arm and need more to hold it.
+            torque_limits: Some([30, 50, 45, 30, 30, 30]),
+            torque_address: 48,
+            deadband: [1.5; 6],
+            sag_tau: 2.0,
+        }
+    }
+}
+
+impl<T: Connection> MyCobotOperator<T> {
+    /// Runs soft float until `keep_going`, called each cycle with the current
+    /// targets, returns `false`. Torque limits are restored to 100% on exit,
+    /// including when the loop fails.
+    pub fn soft_float<F>(&mut self, config: &SoftFloatConfig, keep_going: F) -> Result<()>
+    where
+        F: FnMut(&[f64; 6]) -> bool,
+    {
+        if let Some(limits) = &config.torque_limits {
+            self.set_torque_limits(config.torque_address, limits)?;
+        }
+        let res = self.soft_float_loop(config, keep_going);
+        let restored = match config.torque_limits {
+            Some(_) => self.set_torque_limits(config.torque_address, &[100; 6]),
+            None => Ok(()),
+        };
+        res.and(restored)
+    }
+    fn set_torque_limits(&mut self, address: u8, limits: &[u8; 6]) -> Result<()> {
+        for (servo, &limit) in (1u8..).zip(limits.iter()) {
+            self.set_servo_data(servo, address, limit.min(100))?;
+        }
+        Ok(())
+    }
+    fn soft_float_loop<F>(&mut self, config: &SoftFloatConfig, mut keep_going: F) -> Result<()>
+    where
+        F: FnMut(&[f64; 6]) -> bool,
+    {
+        let period = stream_period(config.rate_hz);
+        let mut target = self.get_angles()?;
+        let mut sag = [0.0; 6];
+        let mut last = Instant::now();
+        while keep_going(&target) {
+            let tick = Instant::now();
+            let dt = tick.duration_since(last).as_secs_f64();
+            last = tick;
+            let alpha = dt / (config.sag_tau.max(1e-3) + dt);
+            let measured = self.get_angles()?;
+            let mut moved = false;
+            for j in 0..6 {
+                let offset = measured[j] - target[j];
+                if (offset - sag[j]).abs() > config.deadband[j] {
+                    target[j] = measured[j] - sag[j];
+                    moved = true;
+                } else {
+                    sag[j] += (offset - sag[j]) * alpha;
+                }
+            }
+            if moved {
+                self.send_angles(&target, config.speed)?;
+            }
+            let elapsed = tick.elapsed();
+            if elapsed < period {
+                thread::sleep(period - elapsed);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,63 @@
+use super::common::Command;
+use std::collections::HashMap;
+use std::time::Duration;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CommandStats {
+    /// Frames sent for this genre.
+    pub count: u64,
+    /// Queries that got no bytes back before the read timed out.
+    pub timeouts: u64,
+    /// Re-sends issued by verified setters.
+    pub retries: u64,
+    /// Round trips that produced a reply.
+    pub replies: u64,
+    pub total_round_trip: Duration,
+    pub min_round_trip: Option<Duration>,
+    pub max_round_trip: Option<Duration>,
+}
+
+impl CommandStats {
+    pub fn mean_round_trip(&self) -> Option<Duration> {
+        if self.replies == 0 {
+            None
+        } else {
+            Some(self.total_round_trip / self.replies as u32)
+        }
+    }
+}
+
+/// Per-genre counters collected by the operator.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OperatorStats {
+    commands: HashMap<Command, CommandStats>,
+}
+
+impl OperatorStats {
+    pub fn get(&self, genre: Command) -> Option<&CommandStats> {
+        self.commands.get(&genre)
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (&Command, &CommandStats)> {
+        self.commands.iter()
+    }
+    pub fn total_timeouts(&self) -> u64 {
+        self.commands.values().map(|s| s.timeouts).sum()
+    }
+    pub(crate) fn record_sent(&mut self, genre: Command) {
+        self.commands.entry(genre).or_default().count += 1;
+    }
+    pub(crate) fn record_round_trip(&mut self, genre: Command, elapsed: Duration, replied: bool) {
+        let stats = self.commands.entry(genre).or_default();
+        if !replied {
+            stats.timeouts += 1;
+            return;
+        }
+        stats.replies += 1;
+        stats.total_round_trip += elapsed;
+        stats.min_round_trip = Some(stats.min_round_trip.map_or(elapsed, |m| m.min(elapsed)));
+        stats.max_round_trip = Some(stats.max_round_trip.map_or(elapsed, |m| m.max(elapsed)));
+    }
+    pub(crate) fn record_retry(&mut self, genre: Command) {
+        self.commands.entry(genre).or_default().retries += 1;
+    }
+}
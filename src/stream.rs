@@ -0,0 +1,83 @@
+use super::io::Connection;
+use super::monitor::Snapshot;
+use super::operator::MyCobotOperator;
+use super::shared::MyCobotHandle;
+use anyhow::Result;
+use futures_core::Stream;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tokio::time::{Interval, MissedTickBehavior};
+
+impl<T: Connection + 'static> MyCobotHandle<T> {
+    /// Async counterpart of `call`: runs `f` on the I/O thread without
+    /// blocking the executor.
+    pub async fn call_async<R, F>(&self, f: F) -> Result<R>
+    where
+        R: Send + 'static,
+        F: FnOnce(&mut MyCobotOperator<T>) -> Result<R> + Send + 'static,
+    {
+        let (reply, result) = oneshot::channel();
+        self.submit(move |op| {
+            let _ = reply.send(f(op));
+        })?;
+        result
+            .await
+            .map_err(|_| anyhow::anyhow!("I/O thread stopped"))?
+    }
+    /// Stream of robot state read `rate_hz` times per second. Must be polled
+    /// inside a tokio runtime with the time driver enabled.
+    pub fn state_stream(&self, rate_hz: f64) -> StateStream<T> {
+        let mut interval = tokio::time::interval(Duration::from_secs_f64(1.0 / rate_hz.max(0.1)));
+        interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        StateStream {
+            handle: self.clone(),
+            interval,
+            pending: None,
+        }
+    }
+}
+
+/// Snapshots from `MyCobotHandle::state_stream`.
+///
+/// At most one read is in flight and nothing is buffered: a slow consumer
+/// skips ticks rather than queuing stale snapshots. Dropping the stream (or
+/// the future of `next()`) is cancel-safe; an in-flight read completes on the
+/// I/O thread and its result is discarded. The stream ends when the I/O
+/// thread has stopped.
+pub struct StateStream<T: Connection> {
+    handle: MyCobotHandle<T>,
+    interval: Interval,
+    pending: Option<oneshot::Receiver<Result<Snapshot>>>,
+}
+
+impl<T: Connection + 'static> Stream for StateStream<T> {
+    type Item = Result<Snapshot>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(pending) = this.pending.as_mut() {
+                let result = match Pin::new(pending).poll(cx) {
+                    Poll::Ready(result) => result,
+                    Poll::Pending => return Poll::Pending,
+                };
+                this.pending = None;
+                return Poll::Ready(result.ok());
+            }
+            if this.interval.poll_tick(cx).is_pending() {
+                return Poll::Pending;
+            }
+            let (reply, result) = oneshot::channel();
+            let submitted = this.handle.submit(move |op| {
+                let _ = reply.send(Snapshot::read(op));
+            });
+            if submitted.is_err() {
+                return Poll::Ready(None);
+            }
+            this.pending = Some(result);
+        }
+    }
+}
@@ -0,0 +1,196 @@
+//! Excitation experiments for identifying joint dynamics: one joint follows
+//! a step or a sine about its current angle while its response is sampled,
+//! giving data for tuning software velocity limits and filters.
+
+use super::common::{Angle, FreshMode};
+use super::io::Connection;
+use super::operator::MyCobotOperator;
+use anyhow::Result;
+use std::fs;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Excitation {
+    /// A single move of `amplitude` degrees at the start.
+    Step { amplitude: f64 },
+    /// `amplitude` degrees about the start angle, streamed every sample.
+    Sine { amplitude: f64, frequency_hz: f64 },
+}
+
+impl Excitation {
+    /// Offset from the start angle commanded at `t` seconds.
+    pub fn offset(&self, t: f64) -> f64 {
+        match *self {
+            Excitation::Step { amplitude } => amplitude,
+            Excitation::Sine {
+                amplitude,
+                frequency_hz,
+            } => amplitude * (2.0 * std::f64::consts::PI * frequency_hz * t).sin(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SysIdConfig {
+    pub joint: Angle,
+    pub excitation: Excitation,
+    pub duration: Duration,
+    /// Sampling rate, clamped to 1-100 Hz; the round trip of each read
+    /// bounds what is actually reached.
+    pub rate_hz: f64,
+    pub speed: u8,
+}
+
+impl SysIdConfig {
+    pub fn step(joint: Angle, amplitude: f64) -> SysIdConfig {
+        SysIdConfig {
+            joint,
+            excitation: Excitation::Step { amplitude },
+            duration: Duration::from_secs(3),
+            rate_hz: 100.0,
+            speed: 100,
+        }
+    }
+    pub fn sine(joint: Angle, amplitude: f64, frequency_hz: f64) -> SysIdConfig {
+        SysIdConfig {
+            joint,
+            excitation: Excitation::Sine {
+                amplitude,
+                frequency_hz,
+            },
+            duration: Duration::from_secs(10),
+            rate_hz: 100.0,
+            speed: 100,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SysIdSample {
+    /// Seconds since the excitation started.
+    pub time: f64,
+    pub command: f64,
+    pub response: f64,
+}
+
+/// Step response characteristics, in seconds and percent of the step.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StepMetrics {
+    /// Until the response first covers 10 % of the step.
+    pub dead_time: f64,
+    /// From 10 % to 90 % of the step.
+    pub rise_time: f64,
+    pub overshoot: f64,
+    /// Until the response stays within 5 % of the final value.
+    pub settling_time: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct SysIdRun {
+    pub joint: Angle,
+    pub start_angle: f64,
+    pub samples: Vec<SysIdSample>,
+}
+
+impl SysIdRun {
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("time,command,response\n");
+        for s in self.samples.iter() {
+            csv.push_str(&format!(
+                "{:.4},{:.2},{:.2}\n",
+                s.time, s.command, s.response
+            ));
+        }
+        csv
+    }
+    pub fn write_csv<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        Ok(fs::write(path, self.to_csv())?)
+    }
+    /// Metrics of a step run, `None` when the joint never reached 90 % of the step.
+    pub fn step_metrics(&self) -> Option<StepMetrics> {
+        let target = self.samples.first()?.command;
+        let step = target - self.start_angle;
+        if step.abs() < 1e-9 {
+            return None;
+        }
+        let progress = |s: &SysIdSample| (s.response - self.start_angle) / step;
+        let first_reaching = |fraction: f64| {
+            self.samples
+                .iter()
+                .find(|s| progress(s) >= fraction)
+                .map(|s| s.time)
+        };
+        let t10 = first_reaching(0.1)?;
+        let t90 = first_reaching(0.9)?;
+        let peak = self.samples.iter().map(progress).fold(f64::MIN, f64::max);
+        let settling_time = self
+            .samples
+            .iter()
+            .rev()
+            .find(|s| (progress(s) - 1.0).abs() > 0.05)
+            .map_or(0.0, |s| s.time);
+        Some(StepMetrics {
+            dead_time: t10,
+            rise_time: t90 - t10,
+            overshoot: ((peak - 1.0) * 100.0).max(0.0),
+            settling_time,
+        })
+    }
+}
+
+impl<T: Connection> MyCobotOperator<T> {
+    /// Runs `config.excitation` on one joint from its current angle, sampling
+    /// the commanded and measured angle, then returns the joint to where it
+    /// started. The firmware is put in interrupt mode for the run so streamed
+    /// sine targets are not queued.
+    pub fn identify_joint(&mut self, config: &SysIdConfig) -> Result<SysIdRun> {
+        let index = config.joint as usize - 1;
+        let start_angle = self.get_angles()?[index];
+        let fresh_mode = self.get_fresh_mode().ok();
+        self.set_fresh_mode(FreshMode::Interrupt)?;
+        let res = self.excite_joint(config, index, start_angle);
+        let returned = self.send_angle(config.joint, start_angle, config.speed);
+        if let Some(mode) = fresh_mode {
+            self.set_fresh_mode(mode)?;
+        }
+        let samples = res?;
+        returned?;
+        Ok(SysIdRun {
+            joint: config.joint,
+            start_angle,
+            samples,
+        })
+    }
+    fn excite_joint(
+        &mut self,
+        config: &SysIdConfig,
+        index: usize,
+        start_angle: f64,
+    ) -> Result<Vec<SysIdSample>> {
+        let period = Duration::from_secs_f64(1.0 / config.rate_hz.clamp(1.0, 100.0));
+        let streamed = matches!(config.excitation, Excitation::Sine { .. });
+        let mut samples = Vec::new();
+        let start = Instant::now();
+        let mut next = start;
+        while start.elapsed() < config.duration {
+            let command = start_angle + config.excitation.offset(start.elapsed().as_secs_f64());
+            if streamed || samples.is_empty() {
+                self.send_angle(config.joint, command, config.speed)?;
+            }
+            let response = self.get_angles()?[index];
+            samples.push(SysIdSample {
+                time: start.elapsed().as_secs_f64(),
+                command,
+                response,
+            });
+            next += period;
+            match next.checked_duration_since(Instant::now()) {
+                Some(wait) => thread::sleep(wait),
+                None => next = Instant::now(),
+            }
+        }
+        Ok(samples)
+    }
+}
@@ -0,0 +1,161 @@
+use super::common::Command;
+use super::io::Connection;
+use super::protocol::Parser;
+use super::utils::{crc16, decode_layout};
+use anyhow::Result;
+use std::io::Write;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrameDirection {
+    Sent,
+    Received,
+}
+
+/// A protocol frame observed on a tapped connection.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+    pub direction: FrameDirection,
+    pub genre: u8,
+    /// Data between the genre byte and the footer, including any checksum.
+    pub payload: Vec<u8>,
+    /// The complete frame as it appeared on the wire.
+    pub raw: Vec<u8>,
+    pub timestamp: SystemTime,
+}
+
+impl Frame {
+    /// The known command for this frame's genre, if any.
+    pub fn command(&self) -> Option<Command> {
+        Command::from_u8(self.genre)
+    }
+    /// The payload without its checksum, when it ends in a valid one.
+    pub fn data(&self) -> &[u8] {
+        let n = self.payload.len();
+        if n >= 2 {
+            let (data, crc) = self.payload.split_at(n - 2);
+            if crc16(&[&[self.genre], data].concat()).to_be_bytes() == crc {
+                return data;
+            }
+        }
+        &self.payload
+    }
+    /// Payload values decoded per the command's request or response layout.
+    pub fn decode(&self) -> Vec<i16> {
+        match (self.command(), self.direction) {
+            (Some(command), FrameDirection::Sent) => decode_layout(command.request(), self.data()),
+            (Some(command), FrameDirection::Received) => {
+                decode_layout(command.response(), self.data())
+            }
+            (None, _) => Vec::new(),
+        }
+    }
+    /// Splits a byte stream into frames, skipping bytes that don't form one.
+    pub fn parse(direction: FrameDirection, data: &[u8]) -> Vec<Frame> {
+        let timestamp = SystemTime::now();
+        Parser::new()
+            .push_bytes(data)
+            .into_iter()
+            .map(|frame| Frame {
+                direction,
+                genre: frame.genre,
+                payload: frame.payload,
+                raw: frame.raw,
+                timestamp,
+            })
+            .collect()
+    }
+}
+
+/// Connection wrapper that reports every frame written or read on a channel.
+pub struct Tap<C: Connection> {
+    inner: C,
+    sender: Sender<Frame>,
+}
+
+impl<C: Connection> Tap<C> {
+    pub fn new(inner: C) -> (Tap<C>, Receiver<Frame>) {
+        let (sender, receiver) = mpsc::channel();
+        (Tap { inner, sender }, receiver)
+    }
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+    fn report(&self, direction: FrameDirection, data: &[u8]) {
+        for frame in Frame::parse(direction, data) {
+            let _ = self.sender.send(frame);
+        }
+    }
+}
+
+impl<C: Connection> Connection for Tap<C> {
+    fn write(&mut self, command: &[u8]) -> Result<()> {
+        self.report(FrameDirection::Sent, command);
+        self.inner.write(command)
+    }
+    fn read(&mut self) -> Result<Vec<u8>> {
+        let res = self.inner.read()?;
+        self.report(FrameDirection::Received, &res);
+        Ok(res)
+    }
+    fn write_and_read(&mut self, command: &[u8]) -> Result<Vec<u8>> {
+        self.report(FrameDirection::Sent, command);
+        let res = self.inner.write_and_read(command)?;
+        self.report(FrameDirection::Received, &res);
+        Ok(res)
+    }
+    fn is_healthy(&mut self) -> bool {
+        self.inner.is_healthy()
+    }
+    fn set_read_timeout(&mut self, timeout: Duration) -> Result<()> {
+        self.inner.set_read_timeout(timeout)
+    }
+    fn clear_input_buffer(&mut self) -> Result<()> {
+        self.inner.clear_input_buffer()
+    }
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Writes frames as a pcap capture (link type USER0). Each packet is one
+/// direction byte (0 sent, 1 received) followed by the raw frame.
+pub struct PcapWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> PcapWriter<W> {
+    const LINKTYPE_USER0: u32 = 147;
+
+    pub fn new(mut writer: W) -> Result<PcapWriter<W>> {
+        writer.write_all(&0xa1b2c3d4u32.to_le_bytes())?;
+        writer.write_all(&2u16.to_le_bytes())?;
+        writer.write_all(&4u16.to_le_bytes())?;
+        writer.write_all(&0i32.to_le_bytes())?;
+        writer.write_all(&0u32.to_le_bytes())?;
+        writer.write_all(&65535u32.to_le_bytes())?;
+        writer.write_all(&Self::LINKTYPE_USER0.to_le_bytes())?;
+        Ok(PcapWriter { writer })
+    }
+    pub fn write_frame(&mut self, frame: &Frame) -> Result<()> {
+        let since = frame.timestamp.duration_since(UNIX_EPOCH)?;
+        let len = (frame.raw.len() + 1) as u32;
+        self.writer
+            .write_all(&(since.as_secs() as u32).to_le_bytes())?;
+        self.writer
+            .write_all(&since.subsec_micros().to_le_bytes())?;
+        self.writer.write_all(&len.to_le_bytes())?;
+        self.writer.write_all(&len.to_le_bytes())?;
+        let direction = match frame.direction {
+            FrameDirection::Sent => 0u8,
+            FrameDirection::Received => 1u8,
+        };
+        self.writer.write_all(&[direction])?;
+        self.writer.write_all(&frame.raw)?;
+        Ok(())
+    }
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
@@ -0,0 +1,154 @@
+use super::{jog, JogMode};
+use crate::io::Connection;
+use crate::operator::MyCobotOperator;
+use anyhow::Result;
+use gilrs::{Axis, Button, EventType, Gilrs};
+use std::thread;
+use std::time::Duration;
+
+const SPEED_HYSTERESIS: u8 = 5;
+
+#[derive(Clone, Debug)]
+pub struct GamepadConfig {
+    pub axes: [Axis; 6],
+    pub dead_zone: f32,
+    pub max_speed: u8,
+    pub estop_button: Button,
+    pub mode_button: Button,
+    pub mode: JogMode,
+}
+
+impl Default for GamepadConfig {
+    fn default() -> GamepadConfig {
+        GamepadConfig {
+            axes: [
+                Axis::LeftStickX,
+                Axis::LeftStickY,
+                Axis::RightStickY,
+                Axis::RightStickX,
+                Axis::DPadX,
+                Axis::DPadY,
+            ],
+            dead_zone: 0.15,
+            max_speed: 50,
+            estop_button: Button::East,
+            mode_button: Button::Select,
+            mode: JogMode::Coord,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct ActiveJog {
+    axis: usize,
+    increase: bool,
+    speed: u8,
+}
+
+pub struct GamepadTeleop {
+    gilrs: Gilrs,
+    config: GamepadConfig,
+    mode: JogMode,
+    active: Option<ActiveJog>,
+    stopped: bool,
+}
+
+impl GamepadTeleop {
+    pub fn new(config: GamepadConfig) -> Result<GamepadTeleop> {
+        let gilrs = Gilrs::new().map_err(|e| anyhow::anyhow!("Failed to open gamepads: {}", e))?;
+        Ok(GamepadTeleop {
+            gilrs,
+            mode: config.mode,
+            config,
+            active: None,
+            stopped: false,
+        })
+    }
+    pub fn mode(&self) -> JogMode {
+        self.mode
+    }
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+    fn scaled_speed(&self, value: f32) -> u8 {
+        let dz = self.config.dead_zone;
+        let ratio = ((value.abs() - dz) / (1.0 - dz)).clamp(0.0, 1.0);
+        ((ratio * self.config.max_speed as f32).ceil() as u8).max(1)
+    }
+    fn dominant_axis(&self) -> Option<ActiveJog> {
+        let (_, gamepad) = self.gilrs.gamepads().next()?;
+        self.config
+            .axes
+            .iter()
+            .enumerate()
+            .map(|(i, axis)| (i, gamepad.value(*axis)))
+            .filter(|(_, v)| v.abs() > self.config.dead_zone)
+            .max_by(|a, b| a.1.abs().total_cmp(&b.1.abs()))
+            .map(|(axis, v)| ActiveJog {
+                axis,
+                increase: v > 0.0,
+                speed: self.scaled_speed(v),
+            })
+    }
+    /// Processes pending gamepad input and returns `false` once the e-stop was pressed.
+    pub fn step<T: Connection>(&mut self, operator: &mut MyCobotOperator<T>) -> Result<bool> {
+        if self.stopped {
+            return Ok(false);
+        }
+        while let Some(event) = self.gilrs.next_event() {
+            match event.event {
+                EventType::ButtonPressed(button, _) if button == self.config.estop_button => {
+                    self.stopped = true;
+                    self.active = None;
+                    operator.jog_stop()?;
+                    operator.stop()?;
+                    return Ok(false);
+                }
+                EventType::ButtonPressed(button, _) if button == self.config.mode_button => {
+                    self.mode = self.mode.toggled();
+                    if self.active.take().is_some() {
+                        operator.jog_stop()?;
+                    }
+                }
+                EventType::Disconnected if self.active.is_some() => {
+                    self.active = None;
+                    operator.jog_stop()?;
+                }
+                _ => {}
+            }
+        }
+        let next = self.dominant_axis();
+        match (self.active, next) {
+            (Some(_), None) => {
+                self.active = None;
+                operator.jog_stop()?;
+            }
+            (prev, Some(next)) => {
+                let changed = match prev {
+                    Some(prev) => {
+                        prev.axis != next.axis
+                            || prev.increase != next.increase
+                            || prev.speed.abs_diff(next.speed) >= SPEED_HYSTERESIS
+                    }
+                    None => true,
+                };
+                if changed {
+                    jog(operator, self.mode, next.axis, next.increase, next.speed)?;
+                    self.active = Some(next);
+                }
+            }
+            (None, None) => {}
+        }
+        Ok(true)
+    }
+    pub fn run<T: Connection>(
+        &mut self,
+        operator: &mut MyCobotOperator<T>,
+        period: Duration,
+    ) -> Result<()> {
+        while self.step(operator)? {
+            thread::sleep(period);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,156 @@
+use super::{coord, joint, JogMode};
+use crate::io::Connection;
+use crate::operator::MyCobotOperator;
+use anyhow::Result;
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyCommand {
+    Step { axis: usize, increase: bool },
+    ToggleMode,
+    Stop,
+    Quit,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyAction {
+    Moved,
+    ModeChanged(JogMode),
+    Stopped,
+    Quit,
+    Ignored,
+}
+
+#[derive(Clone, Debug)]
+pub struct KeyBindings {
+    bindings: HashMap<char, KeyCommand>,
+}
+
+impl KeyBindings {
+    pub fn empty() -> KeyBindings {
+        KeyBindings {
+            bindings: HashMap::new(),
+        }
+    }
+    /// Fails for a `Step` whose axis is not 0-5.
+    pub fn bind(mut self, key: char, command: KeyCommand) -> Result<KeyBindings> {
+        if let KeyCommand::Step { axis, .. } = command {
+            if axis >= 6 {
+                return Err(anyhow::anyhow!(
+                    "Key {:?} steps axis {}, expected 0-5",
+                    key,
+                    axis
+                ));
+            }
+        }
+        self.bindings.insert(key, command);
+        Ok(self)
+    }
+    pub fn get(&self, key: char) -> Option<KeyCommand> {
+        self.bindings.get(&key).copied()
+    }
+}
+
+impl Default for KeyBindings {
+    /// `q/a`, `w/s`, `e/d`, `r/f`, `t/g`, `y/h` step axes 1-6 up/down,
+    /// `m` toggles joint/coordinate mode, space stops and `x` quits.
+    fn default() -> KeyBindings {
+        let pairs = ['q', 'a', 'w', 's', 'e', 'd', 'r', 'f', 't', 'g', 'y', 'h'];
+        let mut bindings = HashMap::new();
+        bindings.insert('m', KeyCommand::ToggleMode);
+        bindings.insert(' ', KeyCommand::Stop);
+        bindings.insert('x', KeyCommand::Quit);
+        for (i, key) in pairs.iter().enumerate() {
+            let command = KeyCommand::Step {
+                axis: i / 2,
+                increase: i % 2 == 0,
+            };
+            bindings.insert(*key, command);
+        }
+        KeyBindings { bindings }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct KeyboardJogController {
+    pub bindings: KeyBindings,
+    pub angle_step: f64,
+    pub position_step: f64,
+    pub rotation_step: f64,
+    pub speed: u8,
+    mode: JogMode,
+}
+
+impl Default for KeyboardJogController {
+    fn default() -> KeyboardJogController {
+        KeyboardJogController::new(KeyBindings::default())
+    }
+}
+
+impl KeyboardJogController {
+    pub fn new(bindings: KeyBindings) -> KeyboardJogController {
+        KeyboardJogController {
+            bindings,
+            angle_step: 2.0,
+            position_step: 5.0,
+            rotation_step: 2.0,
+            speed: 30,
+            mode: JogMode::Angle,
+        }
+    }
+    pub fn mode(&self) -> JogMode {
+        self.mode
+    }
+    pub fn set_mode(&mut self, mode: JogMode) {
+        self.mode = mode;
+    }
+    pub fn handle_key<T: Connection>(
+        &mut self,
+        operator: &mut MyCobotOperator<T>,
+        key: char,
+    ) -> Result<KeyAction> {
+        match self.bindings.get(key) {
+            Some(KeyCommand::Step { axis, increase }) => {
+                self.step(operator, axis, increase)?;
+                Ok(KeyAction::Moved)
+            }
+            Some(KeyCommand::ToggleMode) => {
+                operator.jog_stop()?;
+                self.mode = self.mode.toggled();
+                Ok(KeyAction::ModeChanged(self.mode))
+            }
+            Some(KeyCommand::Stop) => {
+                operator.jog_stop()?;
+                Ok(KeyAction::Stopped)
+            }
+            Some(KeyCommand::Quit) => {
+                operator.jog_stop()?;
+                Ok(KeyAction::Quit)
+            }
+            None => Ok(KeyAction::Ignored),
+        }
+    }
+    fn step<T: Connection>(
+        &self,
+        operator: &mut MyCobotOperator<T>,
+        axis: usize,
+        increase: bool,
+    ) -> Result<()> {
+        let sign = if increase { 1.0 } else { -1.0 };
+        match self.mode {
+            JogMode::Angle => {
+                operator.jog_increment(joint(axis)?, sign * self.angle_step, self.speed)
+            }
+            JogMode::Coord => {
+                let step = if axis < 3 {
+                    self.position_step
+                } else {
+                    self.rotation_step
+                };
+                let coord = coord(axis)?;
+                let coords = operator.get_coords()?;
+                operator.send_coord(coord, coords[axis] + sign * step, self.speed)
+            }
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use super::common::*;
+use super::io::Connection;
+use super::operator::MyCobotOperator;
+use anyhow::Result;
+
+#[cfg(feature = "gilrs")]
+mod gamepad;
+mod keyboard;
+
+#[cfg(feature = "gilrs")]
+pub use self::gamepad::*;
+pub use self::keyboard::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JogMode {
+    Coord,
+    Angle,
+}
+
+impl JogMode {
+    pub fn toggled(self) -> JogMode {
+        match self {
+            JogMode::Coord => JogMode::Angle,
+            JogMode::Angle => JogMode::Coord,
+        }
+    }
+}
+
+/// Joint for a 0-based `axis`; out-of-range axes are an error rather than
+/// being clamped onto the wrist.
+pub(crate) fn joint(axis: usize) -> Result<Angle> {
+    Angle::ALL
+        .get(axis)
+        .copied()
+        .ok_or_else(|| anyhow::anyhow!("Jog axis {} is out of range (0-5)", axis))
+}
+
+pub(crate) fn coord(axis: usize) -> Result<Coord> {
+    Coord::ALL
+        .get(axis)
+        .copied()
+        .ok_or_else(|| anyhow::anyhow!("Jog axis {} is out of range (0-5)", axis))
+}
+
+fn direction(increase: bool) -> Direction {
+    if increase {
+        Direction::Increase
+    } else {
+        Direction::Decrease
+    }
+}
+
+/// Jogs 0-based `axis` (J1-J6 or x-rz); fails for axes above 5.
+pub fn jog<T: Connection>(
+    operator: &mut MyCobotOperator<T>,
+    mode: JogMode,
+    axis: usize,
+    increase: bool,
+    speed: u8,
+) -> Result<()> {
+    match mode {
+        JogMode::Coord => operator.jog_coord(coord(axis)?, direction(increase), speed),
+        JogMode::Angle => operator.jog_angle(joint(axis)?, direction(increase), speed),
+    }
+}
@@ -0,0 +1,146 @@
+//! Health checks that quantify how well a unit performs.
+
+use super::common::Mode;
+use super::io::Connection;
+use super::operator::MyCobotOperator;
+use super::transform::{norm, sub, Vector3};
+use anyhow::Result;
+
+#[derive(Clone, Debug)]
+pub struct RepeatabilityOptions {
+    /// Distance in mm moved away between visits. Successive cycles leave
+    /// along +x, -x, +y, -y and +z in turn, so the target is approached
+    /// from different directions.
+    pub retreat_mm: f64,
+    pub speed: u8,
+    pub mode: Mode,
+    pub timeout_secs: f64,
+}
+
+impl Default for RepeatabilityOptions {
+    fn default() -> RepeatabilityOptions {
+        RepeatabilityOptions {
+            retreat_mm: 50.0,
+            speed: 50,
+            mode: Mode::Linear,
+            timeout_secs: 10.0,
+        }
+    }
+}
+
+const RETREAT_DIRECTIONS: [Vector3; 5] = [
+    [1.0, 0.0, 0.0],
+    [-1.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0],
+    [0.0, -1.0, 0.0],
+    [0.0, 0.0, 1.0],
+];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RepeatabilityReport {
+    pub target: [f64; 6],
+    /// Coords read back after each arrival at the target.
+    pub samples: Vec<[f64; 6]>,
+    /// Per-axis mean of the samples.
+    pub mean: [f64; 6],
+    /// Per-axis sample standard deviation (divided by n - 1, as ISO 9283
+    /// does), zero with fewer than two samples.
+    pub std_dev: [f64; 6],
+    /// Coords read at the end of cycles whose move timed out before the
+    /// robot reported it in position. Kept out of every statistic, since
+    /// the arm may not have finished the move.
+    pub timed_out: Vec<[f64; 6]>,
+}
+
+impl RepeatabilityReport {
+    fn from_samples(
+        target: [f64; 6],
+        samples: Vec<[f64; 6]>,
+        timed_out: Vec<[f64; 6]>,
+    ) -> RepeatabilityReport {
+        let n = samples.len().max(1) as f64;
+        let mut mean = [0.0; 6];
+        for s in samples.iter() {
+            for (m, v) in mean.iter_mut().zip(s.iter()) {
+                *m += v / n;
+            }
+        }
+        let mut std_dev = [0.0; 6];
+        if samples.len() >= 2 {
+            for s in samples.iter() {
+                for i in 0..6 {
+                    std_dev[i] += (s[i] - mean[i]).powi(2) / (n - 1.0);
+                }
+            }
+        }
+        RepeatabilityReport {
+            target,
+            samples,
+            mean,
+            std_dev: std_dev.map(f64::sqrt),
+            timed_out,
+        }
+    }
+    /// Cycles whose move timed out.
+    pub fn timeouts(&self) -> usize {
+        self.timed_out.len()
+    }
+    /// Distance of each sample's position from the mean position, in mm.
+    pub fn position_deviations(&self) -> Vec<f64> {
+        let mean = [self.mean[0], self.mean[1], self.mean[2]];
+        self.samples
+            .iter()
+            .map(|s| norm(&sub(&[s[0], s[1], s[2]], &mean)))
+            .collect()
+    }
+    /// Positional repeatability as in ISO 9283: the mean deviation from the
+    /// mean position plus three standard deviations of it, in mm.
+    pub fn positional_repeatability(&self) -> f64 {
+        let deviations = self.position_deviations();
+        let n = deviations.len() as f64;
+        if n < 2.0 {
+            return 0.0;
+        }
+        let mean = deviations.iter().sum::<f64>() / n;
+        let variance = deviations.iter().map(|d| (d - mean).powi(2)).sum::<f64>() / (n - 1.0);
+        mean + 3.0 * variance.sqrt()
+    }
+    /// Distance from the target to the mean position, in mm.
+    pub fn accuracy(&self) -> f64 {
+        norm(&sub(
+            &[self.mean[0], self.mean[1], self.mean[2]],
+            &[self.target[0], self.target[1], self.target[2]],
+        ))
+    }
+}
+
+/// Moves away from and back to `target` coords `cycles` times, reading the
+/// achieved coords after each arrival, and reports their spread per axis.
+/// Cycles that time out are reported separately and not counted.
+pub fn repeatability<T: Connection>(
+    operator: &mut MyCobotOperator<T>,
+    target: &[f64; 6],
+    cycles: usize,
+    options: &RepeatabilityOptions,
+) -> Result<RepeatabilityReport> {
+    let mut samples = Vec::with_capacity(cycles);
+    let mut timed_out = Vec::new();
+    for cycle in 0..cycles {
+        let direction = RETREAT_DIRECTIONS[cycle % RETREAT_DIRECTIONS.len()];
+        let mut away = *target;
+        for (a, d) in away.iter_mut().zip(direction.iter()) {
+            *a += d * options.retreat_mm;
+        }
+        operator.sync_send_coords(&away, options.speed, options.mode, options.timeout_secs)?;
+        let arrival =
+            operator.sync_send_coords(target, options.speed, options.mode, options.timeout_secs)?;
+        if arrival.reached {
+            samples.push(arrival.pose.to_coords());
+        } else {
+            timed_out.push(arrival.pose.to_coords());
+        }
+    }
+    Ok(RepeatabilityReport::from_samples(
+        *target, samples, timed_out,
+    ))
+}
@@ -0,0 +1,206 @@
+//! Servo overheating protection for robots that run all day. With a policy
+//! set, motion commands periodically read the servo temperatures: above
+//! `slow_above` their speed is scaled down, above `pause_above` the arm is
+//! paused and further motion is refused until every servo has cooled to
+//! `resume_below`.
+
+use super::common::Command;
+use super::io::Connection;
+use super::operator::MyCobotOperator;
+use anyhow::Result;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Debug)]
+pub struct ThermalPolicy {
+    /// Temperatures in °C.
+    pub slow_above: f64,
+    pub pause_above: f64,
+    pub resume_below: f64,
+    /// Speed multiplier while throttled.
+    pub slow_factor: f64,
+    /// Minimum time between temperature reads.
+    pub check_interval: Duration,
+}
+
+impl Default for ThermalPolicy {
+    fn default() -> ThermalPolicy {
+        ThermalPolicy {
+            slow_above: 55.0,
+            pause_above: 65.0,
+            resume_below: 50.0,
+            slow_factor: 0.5,
+            check_interval: Duration::from_secs(5),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ThermalState {
+    Normal,
+    Throttled,
+    Paused,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ThermalEvent {
+    pub state: ThermalState,
+    /// Hottest servo (0-based) and its temperature in °C.
+    pub joint: usize,
+    pub temperature: f64,
+}
+
+/// Returned (inside `anyhow::Error`) for motion commands sent while the arm
+/// is paused to cool down.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ThermalPause {
+    pub temperature: f64,
+}
+
+impl std::fmt::Display for ThermalPause {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Motion refused: paused until the servos cool down (hottest {:.0} °C)",
+            self.temperature
+        )
+    }
+}
+
+impl std::error::Error for ThermalPause {}
+
+type ThermalCallback = Box<dyn FnMut(ThermalEvent) + Send>;
+
+pub(crate) struct ThermalGuard {
+    policy: ThermalPolicy,
+    state: ThermalState,
+    last_check: Option<Instant>,
+    hottest: (usize, f64),
+    observers: Vec<ThermalCallback>,
+}
+
+impl ThermalGuard {
+    fn next_state(&self, temperature: f64) -> ThermalState {
+        let p = &self.policy;
+        match self.state {
+            _ if temperature >= p.pause_above => ThermalState::Paused,
+            ThermalState::Paused if temperature > p.resume_below => ThermalState::Paused,
+            ThermalState::Throttled if temperature > p.resume_below => ThermalState::Throttled,
+            _ if temperature >= p.slow_above => ThermalState::Throttled,
+            _ => ThermalState::Normal,
+        }
+    }
+}
+
+impl<T: Connection> MyCobotOperator<T> {
+    /// `None` turns the protection off, resuming a thermal pause.
+    pub fn set_thermal_policy(&mut self, policy: Option<ThermalPolicy>) -> Result<()> {
+        let observers = match self.thermal.take() {
+            Some(guard) => {
+                if guard.state == ThermalState::Paused {
+                    self.resume()?;
+                }
+                guard.observers
+            }
+            None => Vec::new(),
+        };
+        self.thermal = policy.map(|policy| ThermalGuard {
+            policy,
+            state: ThermalState::Normal,
+            last_check: None,
+            hottest: (0, 0.0),
+            observers,
+        });
+        Ok(())
+    }
+    pub fn thermal_state(&self) -> Option<ThermalState> {
+        self.thermal.as_ref().map(|g| g.state)
+    }
+    /// Called on every change of the thermal state. Needs a policy set first.
+    pub fn on_thermal_event<F>(&mut self, callback: F)
+    where
+        F: FnMut(ThermalEvent) + Send + 'static,
+    {
+        if let Some(guard) = &mut self.thermal {
+            guard.observers.push(Box::new(callback));
+        }
+    }
+    /// Servo temperatures in °C.
+    pub fn get_servo_temperatures(&mut self) -> Result<[f64; 6]> {
+        Ok(self.get_servo_temps()?.map(f64::from))
+    }
+    /// Reads the temperatures when the check interval has passed and applies
+    /// the policy, pausing or resuming the arm as needed.
+    pub fn poll_thermal(&mut self) -> Result<Option<ThermalState>> {
+        let due = match &self.thermal {
+            Some(guard) => guard
+                .last_check
+                .is_none_or(|t| t.elapsed() >= guard.policy.check_interval),
+            None => return Ok(None),
+        };
+        if !due {
+            return Ok(self.thermal_state());
+        }
+        let temperatures = self.get_servo_temperatures()?;
+        let (joint, temperature) = temperatures
+            .iter()
+            .copied()
+            .enumerate()
+            .fold((0, f64::MIN), |a, b| if b.1 > a.1 { b } else { a });
+        let (previous, next) = match &mut self.thermal {
+            Some(guard) => {
+                guard.last_check = Some(Instant::now());
+                guard.hottest = (joint, temperature);
+                let previous = guard.state;
+                guard.state = guard.next_state(temperature);
+                (previous, guard.state)
+            }
+            None => return Ok(None),
+        };
+        if previous != next {
+            if next == ThermalState::Paused {
+                self.pause()?;
+            } else if previous == ThermalState::Paused {
+                self.resume()?;
+            }
+            if let Some(guard) = &mut self.thermal {
+                let event = ThermalEvent {
+                    state: next,
+                    joint,
+                    temperature,
+                };
+                for observer in guard.observers.iter_mut() {
+                    observer(event);
+                }
+            }
+        }
+        Ok(Some(next))
+    }
+    /// Refuses every motion genre while paused, including the raw, jog and
+    /// encoder paths that don't go through `thermal_speed`. Only the state is
+    /// checked here; temperatures are re-read by `poll_thermal`.
+    pub(crate) fn check_thermal_pause(&self, genre: Command) -> Result<()> {
+        match &self.thermal {
+            Some(guard) if guard.state == ThermalState::Paused && genre.starts_motion() => {
+                Err(ThermalPause {
+                    temperature: guard.hottest.1,
+                }
+                .into())
+            }
+            _ => Ok(()),
+        }
+    }
+    /// `speed` as allowed by the thermal policy, or `ThermalPause` while paused.
+    pub(crate) fn thermal_speed(&mut self, speed: u8) -> Result<u8> {
+        match self.poll_thermal()? {
+            Some(ThermalState::Paused) => {
+                let temperature = self.thermal.as_ref().map_or(0.0, |g| g.hottest.1);
+                Err(ThermalPause { temperature }.into())
+            }
+            Some(ThermalState::Throttled) => {
+                let factor = self.thermal.as_ref().map_or(1.0, |g| g.policy.slow_factor);
+                Ok(((speed as f64 * factor).round() as u8).max(1))
+            }
+            _ => Ok(speed),
+        }
+    }
+}
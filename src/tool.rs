@@ -0,0 +1,41 @@
+use super::io::Connection;
+use super::operator::MyCobotOperator;
+use super::transform::Pose;
+
+/// Official flange accessories with their nominal tool center points.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Accessory {
+    /// Bare flange; coords refer to the flange itself.
+    Flange,
+    /// Camera flange, with the tool point on the flange face of the adapter.
+    CameraFlange,
+    /// Suction pump v2, with the tool point at the suction cup lip.
+    SuctionPumpV2,
+    /// Adaptive gripper, with the tool point between the fingertips.
+    Gripper,
+}
+
+impl Accessory {
+    /// Offset along the flange z axis in mm.
+    pub fn tool_offset(&self) -> f64 {
+        match self {
+            Accessory::Flange => 0.0,
+            Accessory::CameraFlange => 10.0,
+            Accessory::SuctionPumpV2 => 62.0,
+            Accessory::Gripper => 100.0,
+        }
+    }
+    pub fn tool_pose(&self) -> Pose {
+        Pose::from_translation([0.0, 0.0, self.tool_offset()])
+    }
+}
+
+impl<T: Connection> MyCobotOperator<T> {
+    /// Applies the accessory's tool transform to subsequent coord commands.
+    pub fn set_accessory(&mut self, accessory: Accessory) {
+        self.set_tool_pose(match accessory {
+            Accessory::Flange => None,
+            _ => Some(accessory.tool_pose()),
+        });
+    }
+}
@@ -0,0 +1,454 @@
+//! Rigid transform helpers following the firmware's pose convention:
+//! positions in millimeters and `rx`/`ry`/`rz` in degrees, applied as `R = Rz * Ry * Rx`.
+
+pub type Vector3 = [f64; 3];
+pub type Matrix3 = [[f64; 3]; 3];
+/// Homogeneous transform, row-major.
+pub type Transform = [[f64; 4]; 4];
+
+pub const IDENTITY3: Matrix3 = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+pub fn mat_mul(a: &Matrix3, b: &Matrix3) -> Matrix3 {
+    let mut m = [[0.0; 3]; 3];
+    for (i, row) in m.iter_mut().enumerate() {
+        for (j, v) in row.iter_mut().enumerate() {
+            *v = (0..3).map(|k| a[i][k] * b[k][j]).sum();
+        }
+    }
+    m
+}
+
+pub fn mat_vec(a: &Matrix3, v: &Vector3) -> Vector3 {
+    [
+        a[0][0] * v[0] + a[0][1] * v[1] + a[0][2] * v[2],
+        a[1][0] * v[0] + a[1][1] * v[1] + a[1][2] * v[2],
+        a[2][0] * v[0] + a[2][1] * v[1] + a[2][2] * v[2],
+    ]
+}
+
+pub fn transpose(a: &Matrix3) -> Matrix3 {
+    let mut m = [[0.0; 3]; 3];
+    for (i, row) in m.iter_mut().enumerate() {
+        for (j, v) in row.iter_mut().enumerate() {
+            *v = a[j][i];
+        }
+    }
+    m
+}
+
+pub fn skew(v: &Vector3) -> Matrix3 {
+    [[0.0, -v[2], v[1]], [v[2], 0.0, -v[0]], [-v[1], v[0], 0.0]]
+}
+
+pub fn add(a: &Vector3, b: &Vector3) -> Vector3 {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+pub fn sub(a: &Vector3, b: &Vector3) -> Vector3 {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+pub fn dot(a: &Vector3, b: &Vector3) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+pub fn cross(a: &Vector3, b: &Vector3) -> Vector3 {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+pub fn norm(v: &Vector3) -> f64 {
+    dot(v, v).sqrt()
+}
+
+/// Rotation matrix from firmware Euler angles in degrees.
+pub fn euler_to_matrix(rx: f64, ry: f64, rz: f64) -> Matrix3 {
+    let (sx, cx) = rx.to_radians().sin_cos();
+    let (sy, cy) = ry.to_radians().sin_cos();
+    let (sz, cz) = rz.to_radians().sin_cos();
+    [
+        [cz * cy, cz * sy * sx - sz * cx, cz * sy * cx + sz * sx],
+        [sz * cy, sz * sy * sx + cz * cx, sz * sy * cx - cz * sx],
+        [-sy, cy * sx, cy * cx],
+    ]
+}
+
+/// Firmware Euler angles `[rx, ry, rz]` in degrees from a rotation matrix.
+pub fn matrix_to_euler(r: &Matrix3) -> [f64; 3] {
+    let ry = (-r[2][0]).clamp(-1.0, 1.0).asin();
+    let (rx, rz) = if ry.cos().abs() > 1e-9 {
+        (r[2][1].atan2(r[2][2]), r[1][0].atan2(r[0][0]))
+    } else {
+        ((-r[1][2]).atan2(r[1][1]), 0.0)
+    };
+    [rx.to_degrees(), ry.to_degrees(), rz.to_degrees()]
+}
+
+/// Axis sequence of extrinsic Euler angles (rotations about the fixed base
+/// axes, applied in the listed order). `Xyz` is the firmware convention.
+/// An intrinsic sequence equals the reversed extrinsic one, e.g. intrinsic
+/// Z-Y'-X'' (yaw-pitch-roll) is `Xyz` with the angles reversed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EulerOrder {
+    Xyz,
+    Xzy,
+    Yxz,
+    Yzx,
+    Zxy,
+    Zyx,
+}
+
+impl EulerOrder {
+    pub const ALL: &'static [EulerOrder] = &[
+        EulerOrder::Xyz,
+        EulerOrder::Xzy,
+        EulerOrder::Yxz,
+        EulerOrder::Yzx,
+        EulerOrder::Zxy,
+        EulerOrder::Zyx,
+    ];
+
+    fn axes(self) -> [usize; 3] {
+        match self {
+            EulerOrder::Xyz => [0, 1, 2],
+            EulerOrder::Xzy => [0, 2, 1],
+            EulerOrder::Yxz => [1, 0, 2],
+            EulerOrder::Yzx => [1, 2, 0],
+            EulerOrder::Zxy => [2, 0, 1],
+            EulerOrder::Zyx => [2, 1, 0],
+        }
+    }
+}
+
+fn axis_rotation(axis: usize, degree: f64) -> Matrix3 {
+    let mut unit = [0.0; 3];
+    unit[axis] = 1.0;
+    axis_angle_to_matrix(&unit, degree.to_radians())
+}
+
+/// Rotation matrix from extrinsic Euler angles in degrees, one per axis of `order`.
+pub fn euler_to_matrix_with(order: EulerOrder, angles: &[f64; 3]) -> Matrix3 {
+    let [i, j, k] = order.axes();
+    mat_mul(
+        &axis_rotation(k, angles[2]),
+        &mat_mul(&axis_rotation(j, angles[1]), &axis_rotation(i, angles[0])),
+    )
+}
+
+/// Extrinsic Euler angles in degrees for `order`. At gimbal lock the first
+/// angle is set to zero.
+pub fn matrix_to_euler_with(order: EulerOrder, r: &Matrix3) -> [f64; 3] {
+    // Decompose R = Rk(c) * Rj(b) * Ri(a) as the intrinsic sequence k, j, i.
+    let [i, j, k] = order.axes();
+    let sign = if [[0, 1, 2], [1, 2, 0], [2, 0, 1]].contains(&[k, j, i]) {
+        1.0
+    } else {
+        -1.0
+    };
+    let b = (sign * r[k][i]).clamp(-1.0, 1.0).asin();
+    let (c, a) = if b.cos().abs() > 1e-9 {
+        (
+            (-sign * r[j][i]).atan2(r[i][i]),
+            (-sign * r[k][j]).atan2(r[k][k]),
+        )
+    } else {
+        ((sign * r[i][j]).atan2(r[j][j]), 0.0)
+    };
+    [a.to_degrees(), b.to_degrees(), c.to_degrees()]
+}
+
+/// Rotation matrix from a unit axis and an angle in radians.
+pub fn axis_angle_to_matrix(axis: &Vector3, angle: f64) -> Matrix3 {
+    let k = skew(axis);
+    let k2 = mat_mul(&k, &k);
+    let (s, c) = angle.sin_cos();
+    let mut r = IDENTITY3;
+    for i in 0..3 {
+        for j in 0..3 {
+            r[i][j] += s * k[i][j] + (1.0 - c) * k2[i][j];
+        }
+    }
+    r
+}
+
+/// Angle in radians, in `[0, π]`, and unit axis of a rotation matrix.
+pub fn matrix_to_axis_angle(r: &Matrix3) -> (f64, Vector3) {
+    let q = Quaternion::from_matrix(r);
+    // q and -q are the same rotation; w >= 0 picks the one with angle <= π.
+    let (w, v) = if q.w < 0.0 {
+        (-q.w, [-q.x, -q.y, -q.z])
+    } else {
+        (q.w, [q.x, q.y, q.z])
+    };
+    let s = norm(&v);
+    if s < 1e-12 {
+        return (0.0, [0.0, 0.0, 1.0]);
+    }
+    let angle = 2.0 * s.atan2(w);
+    (angle, [v[0] / s, v[1] / s, v[2] / s])
+}
+
+/// Rotation matrix from an OpenCV-style rotation vector in radians.
+pub fn rotation_vector_to_matrix(rvec: &Vector3) -> Matrix3 {
+    let angle = norm(rvec);
+    if angle < 1e-12 {
+        IDENTITY3
+    } else {
+        axis_angle_to_matrix(&[rvec[0] / angle, rvec[1] / angle, rvec[2] / angle], angle)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quaternion {
+    pub w: f64,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Quaternion {
+    pub fn identity() -> Quaternion {
+        Quaternion {
+            w: 1.0,
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        }
+    }
+    pub fn new(w: f64, x: f64, y: f64, z: f64) -> Quaternion {
+        Quaternion { w, x, y, z }
+    }
+    pub fn from_axis_angle(axis: &Vector3, angle: f64) -> Quaternion {
+        let (s, c) = (angle / 2.0).sin_cos();
+        let n = norm(axis);
+        Quaternion::new(c, s * axis[0] / n, s * axis[1] / n, s * axis[2] / n)
+    }
+    pub fn from_euler(rx: f64, ry: f64, rz: f64) -> Quaternion {
+        let qx = Quaternion::from_axis_angle(&[1.0, 0.0, 0.0], rx.to_radians());
+        let qy = Quaternion::from_axis_angle(&[0.0, 1.0, 0.0], ry.to_radians());
+        let qz = Quaternion::from_axis_angle(&[0.0, 0.0, 1.0], rz.to_radians());
+        qz.mul(&qy).mul(&qx)
+    }
+    pub fn to_euler(&self) -> [f64; 3] {
+        matrix_to_euler(&self.to_matrix())
+    }
+    pub fn from_matrix(r: &Matrix3) -> Quaternion {
+        let trace = r[0][0] + r[1][1] + r[2][2];
+        let q = if trace > 0.0 {
+            let s = (trace + 1.0).sqrt() * 2.0;
+            Quaternion::new(
+                s / 4.0,
+                (r[2][1] - r[1][2]) / s,
+                (r[0][2] - r[2][0]) / s,
+                (r[1][0] - r[0][1]) / s,
+            )
+        } else if r[0][0] > r[1][1] && r[0][0] > r[2][2] {
+            let s = (1.0 + r[0][0] - r[1][1] - r[2][2]).sqrt() * 2.0;
+            Quaternion::new(
+                (r[2][1] - r[1][2]) / s,
+                s / 4.0,
+                (r[0][1] + r[1][0]) / s,
+                (r[0][2] + r[2][0]) / s,
+            )
+        } else if r[1][1] > r[2][2] {
+            let s = (1.0 + r[1][1] - r[0][0] - r[2][2]).sqrt() * 2.0;
+            Quaternion::new(
+                (r[0][2] - r[2][0]) / s,
+                (r[0][1] + r[1][0]) / s,
+                s / 4.0,
+                (r[1][2] + r[2][1]) / s,
+            )
+        } else {
+            let s = (1.0 + r[2][2] - r[0][0] - r[1][1]).sqrt() * 2.0;
+            Quaternion::new(
+                (r[1][0] - r[0][1]) / s,
+                (r[0][2] + r[2][0]) / s,
+                (r[1][2] + r[2][1]) / s,
+                s / 4.0,
+            )
+        };
+        q.normalized()
+    }
+    pub fn to_matrix(&self) -> Matrix3 {
+        let Quaternion { w, x, y, z } = self.normalized();
+        [
+            [
+                1.0 - 2.0 * (y * y + z * z),
+                2.0 * (x * y - w * z),
+                2.0 * (x * z + w * y),
+            ],
+            [
+                2.0 * (x * y + w * z),
+                1.0 - 2.0 * (x * x + z * z),
+                2.0 * (y * z - w * x),
+            ],
+            [
+                2.0 * (x * z - w * y),
+                2.0 * (y * z + w * x),
+                1.0 - 2.0 * (x * x + y * y),
+            ],
+        ]
+    }
+    pub fn norm(&self) -> f64 {
+        (self.w * self.w + self.x * self.x + self.y * self.y + self.z * self.z).sqrt()
+    }
+    pub fn normalized(&self) -> Quaternion {
+        let n = self.norm();
+        Quaternion::new(self.w / n, self.x / n, self.y / n, self.z / n)
+    }
+    pub fn conjugate(&self) -> Quaternion {
+        Quaternion::new(self.w, -self.x, -self.y, -self.z)
+    }
+    pub fn mul(&self, o: &Quaternion) -> Quaternion {
+        Quaternion::new(
+            self.w * o.w - self.x * o.x - self.y * o.y - self.z * o.z,
+            self.w * o.x + self.x * o.w + self.y * o.z - self.z * o.y,
+            self.w * o.y - self.x * o.z + self.y * o.w + self.z * o.x,
+            self.w * o.z + self.x * o.y - self.y * o.x + self.z * o.w,
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pose {
+    pub position: Vector3,
+    pub rotation: Matrix3,
+}
+
+impl Default for Pose {
+    fn default() -> Pose {
+        Pose::identity()
+    }
+}
+
+impl Pose {
+    pub fn identity() -> Pose {
+        Pose {
+            position: [0.0; 3],
+            rotation: IDENTITY3,
+        }
+    }
+    pub fn new(position: Vector3, rotation: Matrix3) -> Pose {
+        Pose { position, rotation }
+    }
+    pub fn from_translation(position: Vector3) -> Pose {
+        Pose::new(position, IDENTITY3)
+    }
+    /// Builds a pose from `[x, y, z, rx, ry, rz]` as returned by `get_coords`.
+    pub fn from_coords(coords: &[f64; 6]) -> Pose {
+        Pose {
+            position: [coords[0], coords[1], coords[2]],
+            rotation: euler_to_matrix(coords[3], coords[4], coords[5]),
+        }
+    }
+    pub fn to_coords(&self) -> [f64; 6] {
+        let [rx, ry, rz] = matrix_to_euler(&self.rotation);
+        [
+            self.position[0],
+            self.position[1],
+            self.position[2],
+            rx,
+            ry,
+            rz,
+        ]
+    }
+    /// Euler angles of the rotation in the given convention.
+    pub fn euler(&self, order: EulerOrder) -> [f64; 3] {
+        matrix_to_euler_with(order, &self.rotation)
+    }
+    pub fn from_euler(position: Vector3, order: EulerOrder, angles: &[f64; 3]) -> Pose {
+        Pose::new(position, euler_to_matrix_with(order, angles))
+    }
+    /// Builds a pose from an OpenCV-style rotation vector (rad) and translation (mm).
+    pub fn from_rotation_vector(rvec: &Vector3, tvec: &Vector3) -> Pose {
+        Pose::new(*tvec, rotation_vector_to_matrix(rvec))
+    }
+    pub fn from_quaternion(position: Vector3, q: &Quaternion) -> Pose {
+        Pose::new(position, q.to_matrix())
+    }
+    pub fn quaternion(&self) -> Quaternion {
+        Quaternion::from_matrix(&self.rotation)
+    }
+    pub fn from_transform(t: &Transform) -> Pose {
+        Pose {
+            position: [t[0][3], t[1][3], t[2][3]],
+            rotation: [
+                [t[0][0], t[0][1], t[0][2]],
+                [t[1][0], t[1][1], t[1][2]],
+                [t[2][0], t[2][1], t[2][2]],
+            ],
+        }
+    }
+    pub fn to_transform(&self) -> Transform {
+        let (r, p) = (&self.rotation, &self.position);
+        [
+            [r[0][0], r[0][1], r[0][2], p[0]],
+            [r[1][0], r[1][1], r[1][2], p[1]],
+            [r[2][0], r[2][1], r[2][2], p[2]],
+            [0.0, 0.0, 0.0, 1.0],
+        ]
+    }
+    /// Returns `self * other`, i.e. `other` expressed in the frame of `self`.
+    pub fn compose(&self, other: &Pose) -> Pose {
+        Pose {
+            position: self.transform_point(&other.position),
+            rotation: mat_mul(&self.rotation, &other.rotation),
+        }
+    }
+    pub fn inverse(&self) -> Pose {
+        let rotation = transpose(&self.rotation);
+        let p = mat_vec(&rotation, &self.position);
+        Pose {
+            position: [-p[0], -p[1], -p[2]],
+            rotation,
+        }
+    }
+    pub fn transform_point(&self, point: &Vector3) -> Vector3 {
+        add(&mat_vec(&self.rotation, point), &self.position)
+    }
+    /// Pose a fraction `s` of the way to `other`, moving along a straight line
+    /// and rotating about a fixed axis.
+    pub fn interpolate(&self, other: &Pose, s: f64) -> Pose {
+        let relative = mat_mul(&transpose(&self.rotation), &other.rotation);
+        let (angle, axis) = matrix_to_axis_angle(&relative);
+        let delta = sub(&other.position, &self.position);
+        Pose {
+            position: add(&self.position, &delta.map(|v| v * s)),
+            rotation: mat_mul(&self.rotation, &axis_angle_to_matrix(&axis, angle * s)),
+        }
+    }
+    /// Pose `standoff` mm from `target` with its z axis pointing at it, on the
+    /// line from `self` towards `target`. The x axis stays as close to that of
+    /// `self` as possible, so the wrist rolls no more than needed. `None` when
+    /// `self` is already at `target`.
+    pub fn look_at(&self, target: &Vector3, standoff: f64) -> Option<Pose> {
+        let line = sub(target, &self.position);
+        let length = norm(&line);
+        if length < 1e-9 {
+            return None;
+        }
+        let z = line.map(|v| v / length);
+        let column = |r: &Matrix3, j: usize| [r[0][j], r[1][j], r[2][j]];
+        let mut x = [0.0; 3];
+        for hint in [column(&self.rotation, 0), column(&self.rotation, 1)].iter() {
+            let projected = sub(hint, &z.map(|v| v * dot(hint, &z)));
+            let n = norm(&projected);
+            if n > 1e-6 {
+                x = projected.map(|v| v / n);
+                break;
+            }
+        }
+        let y = cross(&z, &x);
+        let rotation = [[x[0], y[0], z[0]], [x[1], y[1], z[1]], [x[2], y[2], z[2]]];
+        Some(Pose::new(sub(target, &z.map(|v| v * standoff)), rotation))
+    }
+    /// Translation distance in mm and rotation angle in degrees between two poses.
+    pub fn distance(&self, other: &Pose) -> (f64, f64) {
+        let diff = self.inverse().compose(other);
+        let (angle, _) = matrix_to_axis_angle(&diff.rotation);
+        (norm(&diff.position), angle.to_degrees())
+    }
+}
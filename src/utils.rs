@@ -1,53 +1,7 @@
 use super::common::*;
-use anyhow::{Error, Result};
+use anyhow::Result;
 use byteorder::{BigEndian, ByteOrder};
 use num_traits::FromPrimitive;
-use std::convert::TryInto;
-
-pub fn angle_to_int(degree: f64) -> i16 {
-    (degree * 100.0) as i16
-}
-
-pub fn coord_to_int(coord: f64) -> i16 {
-    (coord * 10.0) as i16
-}
-
-pub fn int_to_angle(val: i16) -> f64 {
-    (val as f64) / 100.0
-}
-
-pub fn int_to_coord(val: i16) -> f64 {
-    (val as f64) / 10.0
-}
-
-pub fn coords_to_int_vec(coords: &[f64; 6]) -> Vec<i16> {
-    coords
-        .iter()
-        .enumerate()
-        .map(|(i, coord)| {
-            if i < 3 {
-                coord_to_int(*coord)
-            } else {
-                angle_to_int(*coord)
-            }
-        })
-        .collect()
-}
-
-pub fn int_vec_to_coords(vals: &[i16]) -> Result<[f64; 6]> {
-    vals.iter()
-        .enumerate()
-        .map(|(i, v)| {
-            if i < 3 {
-                int_to_coord(*v)
-            } else {
-                int_to_angle(*v)
-            }
-        })
-        .collect::<Vec<_>>()[..]
-        .try_into()
-        .map_err(Error::msg)
-}
 
 pub fn encode_int16(data: i16) -> [u8; 2] {
     let mut buf = [0u8; 2];
@@ -56,8 +10,7 @@ pub fn encode_int16(data: i16) -> [u8; 2] {
 }
 
 pub fn encode_int16_vec(data: &[i16]) -> Vec<u8> {
-    let mut buf = Vec::<u8>::new();
-    buf.resize(data.len() * 2, 0);
+    let mut buf = vec![0u8; data.len() * 2];
     for i in 0..data.len() {
         BigEndian::write_i16(&mut buf[(2 * i)..(2 * i + 2)], data[i]);
     }
@@ -80,6 +33,107 @@ pub fn decode_int16_vec(data: &[u8]) -> Vec<i16> {
     res
 }
 
+fn decode_fields(fields: &[Field], data: &[u8]) -> Vec<i16> {
+    let mut pos = 0;
+    fields
+        .iter()
+        .filter_map(|field| {
+            let value = match field {
+                Field::U8 => Some(data[pos] as i16),
+                Field::I8 => Some(decode_int8(&data[pos..]) as i16),
+                Field::I16 => Some(decode_int16(&data[pos..])),
+                Field::Pad => None,
+            };
+            pos += field.size();
+            value
+        })
+        .collect()
+}
+
+/// Decodes an angle, coordinate or encoder reply from an arm with fewer than
+/// six joints: one value per joint, optionally followed by a status byte,
+/// zero-padded to the six values the rest of the API expects.
+pub fn decode_joint_values(data: &[u8], joint_count: usize) -> Vec<i16> {
+    let len = joint_count * 2;
+    if data.len() != len && data.len() != len + 1 {
+        return Vec::new();
+    }
+    let mut values = decode_int16_vec(&data[..len]);
+    values.resize(values.len().max(6), 0);
+    values
+}
+
+/// Decodes a reply payload as declared by `layout`. Payloads whose length
+/// doesn't match the layout decode to nothing rather than being guessed at.
+pub fn decode_layout(layout: Layout, data: &[u8]) -> Vec<i16> {
+    match layout {
+        Layout::Fields(fields) if data.len() == fields_len(fields) => decode_fields(fields, data),
+        Layout::OneOf(options) => options
+            .iter()
+            .find(|fields| fields_len(fields) == data.len())
+            .map_or_else(Vec::new, |fields| decode_fields(fields, data)),
+        _ => Vec::new(),
+    }
+}
+
+fn value_count(fields: &[Field]) -> usize {
+    fields.iter().filter(|f| **f != Field::Pad).count()
+}
+
+/// Encodes request arguments as declared by `layout`. `Raw` takes one byte per argument.
+pub fn encode_layout(layout: Layout, args: &[i32]) -> Result<Vec<u8>> {
+    let fields: Vec<Field> = match layout {
+        Layout::Fields(fields) => fields.to_vec(),
+        Layout::Raw => vec![Field::U8; args.len()],
+        Layout::OneOf(options) => options
+            .iter()
+            .find(|fields| value_count(fields) == args.len())
+            .ok_or_else(|| anyhow::anyhow!("No layout takes {} arguments", args.len()))?
+            .to_vec(),
+    };
+    if value_count(&fields) != args.len() {
+        return Err(anyhow::anyhow!(
+            "Expected {} arguments, got {}",
+            value_count(&fields),
+            args.len()
+        ));
+    }
+    let mut buf = Vec::with_capacity(fields_len(&fields));
+    let mut args = args.iter();
+    for field in &fields {
+        let (min, max) = match field {
+            Field::U8 => (u8::MIN as i32, u8::MAX as i32),
+            Field::I8 => (i8::MIN as i32, i8::MAX as i32),
+            Field::I16 => (i16::MIN as i32, i16::MAX as i32),
+            Field::Pad => {
+                buf.push(0);
+                continue;
+            }
+        };
+        let arg = *args.next().unwrap();
+        if arg < min || arg > max {
+            return Err(anyhow::anyhow!("{} is out of range for {:?}", arg, field));
+        }
+        match field {
+            Field::I16 => buf.extend_from_slice(&encode_int16(arg as i16)),
+            _ => buf.push(arg as u8),
+        }
+    }
+    Ok(buf)
+}
+
+pub fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0xFFFFu16, |crc, &byte| {
+        (0..8).fold(crc ^ byte as u16, |crc, _| {
+            if crc & 1 != 0 {
+                (crc >> 1) ^ 0xA001
+            } else {
+                crc >> 1
+            }
+        })
+    })
+}
+
 const MINANGLE: f64 = -190.0;
 const MAXANGLE: f64 = 190.0;
 
@@ -0,0 +1,166 @@
+//! Streams robot state as newline-delimited JSON, one object per snapshot:
+//! `{"timestamp": 1700000000.25, "angles": [...], "coords": [...], "moving": false}`
+//! with the timestamp in seconds since the Unix epoch. Any TCP client (or a
+//! recorded file fed to `replay`) can drive a 3D view from it.
+
+use super::monitor::{Snapshot, StateMonitor};
+use anyhow::Result;
+use std::convert::TryInto;
+use std::io::{BufRead, ErrorKind, Write};
+use std::net::{SocketAddr, TcpListener, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const ACCEPT_POLL: Duration = Duration::from_millis(50);
+
+type Sinks = Arc<Mutex<Vec<Box<dyn Write + Send>>>>;
+
+pub fn snapshot_to_json(snapshot: &Snapshot) -> String {
+    let timestamp = snapshot
+        .timestamp
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |d| d.as_secs_f64());
+    serde_json::json!({
+        "timestamp": timestamp,
+        "angles": snapshot.angles,
+        "coords": snapshot.coords,
+        "moving": snapshot.moving,
+    })
+    .to_string()
+}
+
+pub fn snapshot_from_json(line: &str) -> Result<Snapshot> {
+    let value: serde_json::Value = serde_json::from_str(line)?;
+    let six = |key: &str| -> Result<[f64; 6]> {
+        let values = value[key]
+            .as_array()
+            .ok_or_else(|| anyhow::anyhow!("Missing {}", key))?
+            .iter()
+            .map(|v| v.as_f64().ok_or_else(|| anyhow::anyhow!("Invalid {}", key)))
+            .collect::<Result<Vec<_>>>()?;
+        values[..].try_into().map_err(anyhow::Error::msg)
+    };
+    let timestamp = value["timestamp"].as_f64().unwrap_or(0.0);
+    Ok(Snapshot {
+        angles: six("angles")?,
+        coords: six("coords")?,
+        moving: value["moving"].as_bool().unwrap_or(false),
+        timestamp: UNIX_EPOCH + Duration::from_secs_f64(timestamp.max(0.0)),
+    })
+}
+
+/// Fans snapshots out to TCP clients and any added writers (e.g. a recording file).
+/// Sinks that fail to accept a write are dropped.
+pub struct VizPublisher {
+    sinks: Sinks,
+    local_addr: Option<SocketAddr>,
+    shutdown: Arc<AtomicBool>,
+    listener: Option<JoinHandle<()>>,
+}
+
+impl VizPublisher {
+    pub fn new() -> VizPublisher {
+        VizPublisher {
+            sinks: Arc::new(Mutex::new(Vec::new())),
+            local_addr: None,
+            shutdown: Arc::new(AtomicBool::new(false)),
+            listener: None,
+        }
+    }
+    /// Listens on `addr` and streams to every client that connects.
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> Result<VizPublisher> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        let mut publisher = VizPublisher::new();
+        publisher.local_addr = Some(listener.local_addr()?);
+        let sinks = publisher.sinks.clone();
+        let shutdown = publisher.shutdown.clone();
+        publisher.listener = Some(thread::spawn(move || {
+            while !shutdown.load(Ordering::SeqCst) {
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        if stream.set_nonblocking(false).is_ok() {
+                            if let Ok(mut sinks) = sinks.lock() {
+                                sinks.push(Box::new(stream));
+                            }
+                        }
+                    }
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => thread::sleep(ACCEPT_POLL),
+                    Err(_) => thread::sleep(ACCEPT_POLL),
+                }
+            }
+        }));
+        Ok(publisher)
+    }
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.local_addr
+    }
+    pub fn add_writer<W: Write + Send + 'static>(&self, writer: W) {
+        if let Ok(mut sinks) = self.sinks.lock() {
+            sinks.push(Box::new(writer));
+        }
+    }
+    pub fn publish(&self, snapshot: &Snapshot) {
+        publish_to(&self.sinks, snapshot);
+    }
+    /// Publishes every state change seen by `monitor`.
+    pub fn attach(&self, monitor: &mut StateMonitor) {
+        let sinks = self.sinks.clone();
+        monitor.on_state_change(move |snapshot, _| publish_to(&sinks, snapshot));
+    }
+}
+
+impl Default for VizPublisher {
+    fn default() -> VizPublisher {
+        VizPublisher::new()
+    }
+}
+
+impl Drop for VizPublisher {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        if let Some(listener) = self.listener.take() {
+            let _ = listener.join();
+        }
+    }
+}
+
+fn publish_to(sinks: &Sinks, snapshot: &Snapshot) {
+    let line = snapshot_to_json(snapshot) + "\n";
+    if let Ok(mut sinks) = sinks.lock() {
+        sinks.retain_mut(|sink| {
+            sink.write_all(line.as_bytes())
+                .and_then(|_| sink.flush())
+                .is_ok()
+        });
+    }
+}
+
+/// Reads a recording back, calling `f` for each snapshot. With `realtime`
+/// the original spacing between snapshots is reproduced.
+pub fn replay<R, F>(reader: R, realtime: bool, mut f: F) -> Result<usize>
+where
+    R: BufRead,
+    F: FnMut(&Snapshot),
+{
+    let mut previous: Option<SystemTime> = None;
+    let mut count = 0;
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let snapshot = snapshot_from_json(&line)?;
+        if realtime {
+            if let Some(gap) = previous.and_then(|p| snapshot.timestamp.duration_since(p).ok()) {
+                thread::sleep(gap);
+            }
+            previous = Some(snapshot.timestamp);
+        }
+        f(&snapshot);
+        count += 1;
+    }
+    Ok(count)
+}
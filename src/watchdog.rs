@@ -0,0 +1,108 @@
+use super::io::Connection;
+use super::operator::MyCobotOperator;
+use anyhow::Result;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WatchdogPolicy {
+    Stop,
+    StopAndRelease,
+    StopAndPowerOff,
+}
+
+impl WatchdogPolicy {
+    pub fn apply<T: Connection>(&self, operator: &mut MyCobotOperator<T>) -> Result<()> {
+        operator.stop()?;
+        match self {
+            WatchdogPolicy::Stop => Ok(()),
+            WatchdogPolicy::StopAndRelease => operator.release_all_servos(),
+            WatchdogPolicy::StopAndPowerOff => operator.power_off(),
+        }
+    }
+}
+
+/// Deadman timer: runs the trip action once if `feed` is not called within the timeout.
+/// Once tripped it stays tripped until `rearm` is called.
+pub struct Watchdog {
+    last_feed: Arc<Mutex<Instant>>,
+    tripped: Arc<AtomicBool>,
+    shutdown: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Watchdog {
+    pub fn spawn<F>(timeout: Duration, mut on_trip: F) -> Watchdog
+    where
+        F: FnMut() -> Result<()> + Send + 'static,
+    {
+        let last_feed = Arc::new(Mutex::new(Instant::now()));
+        let tripped = Arc::new(AtomicBool::new(false));
+        let (shutdown, receiver) = mpsc::channel::<()>();
+        let thread = {
+            let last_feed = last_feed.clone();
+            let tripped = tripped.clone();
+            let poll = (timeout / 4).max(Duration::from_millis(1));
+            thread::spawn(move || {
+                while let Err(RecvTimeoutError::Timeout) = receiver.recv_timeout(poll) {
+                    if tripped.load(Ordering::SeqCst) {
+                        continue;
+                    }
+                    let starved = last_feed
+                        .lock()
+                        .map(|t| t.elapsed() > timeout)
+                        .unwrap_or(true);
+                    if starved {
+                        tripped.store(true, Ordering::SeqCst);
+                        let _ = on_trip();
+                    }
+                }
+            })
+        };
+        Watchdog {
+            last_feed,
+            tripped,
+            shutdown: Some(shutdown),
+            thread: Some(thread),
+        }
+    }
+    pub fn for_operator<T>(
+        operator: Arc<Mutex<MyCobotOperator<T>>>,
+        timeout: Duration,
+        policy: WatchdogPolicy,
+    ) -> Watchdog
+    where
+        T: Connection + Send + 'static,
+    {
+        Watchdog::spawn(timeout, move || {
+            let mut operator = operator
+                .lock()
+                .map_err(|_| anyhow::anyhow!("Operator mutex poisoned"))?;
+            policy.apply(&mut operator)
+        })
+    }
+    pub fn feed(&self) {
+        if let Ok(mut last_feed) = self.last_feed.lock() {
+            *last_feed = Instant::now();
+        }
+    }
+    pub fn has_tripped(&self) -> bool {
+        self.tripped.load(Ordering::SeqCst)
+    }
+    pub fn rearm(&self) {
+        self.feed();
+        self.tripped.store(false, Ordering::SeqCst);
+    }
+}
+
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        self.shutdown.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
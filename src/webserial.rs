@@ -0,0 +1,232 @@
+//! `Connection` over the browser WebSerial API for `wasm32` builds.
+//!
+//! Browsers cannot block on a serial read, so `Connection::read` and the
+//! reply half of `write_and_read` only return bytes that have already
+//! arrived. Motion commands work through the normal operator API; queries
+//! that need a reply should use the `*_async` methods on
+//! `MyCobotOperator<WebSerial>`, which wait for the matching frame.
+
+use super::common::Command;
+use super::conversion::{int_vec_to_angles, int_vec_to_coords};
+use super::io::Connection;
+use super::operator::MyCobotOperator;
+use super::tap::{Frame, FrameDirection};
+use super::utils::encode_layout;
+use anyhow::{Error, Result};
+use js_sys::{Function, Object, Promise, Reflect, Uint8Array};
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::convert::TryInto;
+use std::rc::Rc;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+
+const REPLY_TIMEOUT_MS: u32 = 200;
+
+fn js_error(context: &str, value: JsValue) -> anyhow::Error {
+    anyhow::anyhow!("{}: {:?}", context, value)
+}
+
+fn get(target: &JsValue, name: &str) -> Result<JsValue> {
+    Reflect::get(target, &JsValue::from_str(name)).map_err(|e| js_error(name, e))
+}
+
+fn call(target: &JsValue, name: &str, args: &[JsValue]) -> Result<JsValue> {
+    let function: Function = get(target, name)?
+        .dyn_into()
+        .map_err(|e| js_error(name, e))?;
+    let args: js_sys::Array = args.iter().collect();
+    function.apply(target, &args).map_err(|e| js_error(name, e))
+}
+
+async fn await_promise(value: JsValue, context: &str) -> Result<JsValue> {
+    let promise: Promise = value.dyn_into().map_err(|e| js_error(context, e))?;
+    JsFuture::from(promise)
+        .await
+        .map_err(|e| js_error(context, e))
+}
+
+async fn sleep_ms(ms: i32) {
+    let promise = Promise::new(&mut |resolve, _| {
+        if let Ok(set_timeout) = get(&js_sys::global(), "setTimeout") {
+            if let Ok(set_timeout) = set_timeout.dyn_into::<Function>() {
+                let _ = set_timeout.call2(&JsValue::NULL, &resolve, &JsValue::from(ms));
+            }
+        }
+    });
+    let _ = JsFuture::from(promise).await;
+}
+
+/// An open WebSerial port with a background reader filling a receive buffer.
+pub struct WebSerial {
+    port: JsValue,
+    writer: JsValue,
+    buffer: Rc<RefCell<VecDeque<u8>>>,
+    healthy: Rc<Cell<bool>>,
+}
+
+impl WebSerial {
+    /// Shows the browser's port picker and opens the chosen port. Must be
+    /// called from a user gesture such as a click handler.
+    pub async fn request(baudrate: u32) -> Result<WebSerial> {
+        let serial = get(&get(&js_sys::global(), "navigator")?, "serial")?;
+        if serial.is_undefined() {
+            return Err(anyhow::anyhow!(
+                "WebSerial is not supported by this browser"
+            ));
+        }
+        let port = await_promise(call(&serial, "requestPort", &[])?, "requestPort").await?;
+        WebSerial::open(port, baudrate).await
+    }
+    /// Opens a `SerialPort` object obtained elsewhere, e.g. from `navigator.serial.getPorts()`.
+    pub async fn open(port: JsValue, baudrate: u32) -> Result<WebSerial> {
+        let options = Object::new();
+        Reflect::set(&options, &"baudRate".into(), &JsValue::from(baudrate))
+            .map_err(|e| js_error("baudRate", e))?;
+        await_promise(call(&port, "open", &[options.into()])?, "open").await?;
+        let writer = call(&get(&port, "writable")?, "getWriter", &[])?;
+        let reader = call(&get(&port, "readable")?, "getReader", &[])?;
+        let serial = WebSerial {
+            port,
+            writer,
+            buffer: Rc::new(RefCell::new(VecDeque::new())),
+            healthy: Rc::new(Cell::new(true)),
+        };
+        let buffer = serial.buffer.clone();
+        let healthy = serial.healthy.clone();
+        spawn_local(async move {
+            loop {
+                let chunk = match call(&reader, "read", &[]) {
+                    Ok(promise) => await_promise(promise, "read").await,
+                    Err(e) => Err(e),
+                };
+                let chunk = match chunk {
+                    Ok(chunk) => chunk,
+                    Err(_) => break,
+                };
+                if get(&chunk, "done").map_or(true, |d| d.is_truthy()) {
+                    break;
+                }
+                if let Ok(value) = get(&chunk, "value") {
+                    buffer.borrow_mut().extend(Uint8Array::new(&value).to_vec());
+                }
+            }
+            healthy.set(false);
+        });
+        Ok(serial)
+    }
+    /// The underlying JavaScript `SerialPort`.
+    pub fn port(&self) -> &JsValue {
+        &self.port
+    }
+    /// Waits up to `timeout_ms` for a complete frame of `genre` and removes
+    /// everything buffered up to and including it.
+    pub async fn wait_for_frame(&self, genre: u8, timeout_ms: u32) -> Option<Vec<u8>> {
+        const STEP_MS: u32 = 5;
+        let mut waited = 0;
+        loop {
+            if let Some(raw) = self.take_frame(genre) {
+                return Some(raw);
+            }
+            if waited >= timeout_ms || !self.healthy.get() {
+                return None;
+            }
+            sleep_ms(STEP_MS as i32).await;
+            waited += STEP_MS;
+        }
+    }
+    fn take_frame(&self, genre: u8) -> Option<Vec<u8>> {
+        let mut buffer = self.buffer.borrow_mut();
+        let data: Vec<u8> = buffer.iter().copied().collect();
+        let frame = Frame::parse(FrameDirection::Received, &data)
+            .into_iter()
+            .find(|f| f.genre == genre)?;
+        let end = data
+            .windows(frame.raw.len())
+            .position(|w| w == &frame.raw[..])?
+            + frame.raw.len();
+        buffer.drain(..end);
+        Some(frame.raw)
+    }
+}
+
+impl Connection for WebSerial {
+    fn write(&mut self, command: &[u8]) -> Result<()> {
+        if !self.healthy.get() {
+            return Err(anyhow::anyhow!("WebSerial port is closed"));
+        }
+        let promise: Promise = call(&self.writer, "write", &[Uint8Array::from(command).into()])?
+            .dyn_into()
+            .map_err(|e| js_error("write", e))?;
+        let healthy = self.healthy.clone();
+        let on_error = Closure::once(move |_: JsValue| healthy.set(false));
+        let _ = promise.catch(&on_error);
+        on_error.forget();
+        Ok(())
+    }
+    fn read(&mut self) -> Result<Vec<u8>> {
+        Ok(self.buffer.borrow_mut().drain(..).collect())
+    }
+    fn write_and_read(&mut self, command: &[u8]) -> Result<Vec<u8>> {
+        self.write(command)?;
+        self.read()
+    }
+    fn is_healthy(&mut self) -> bool {
+        self.healthy.get()
+    }
+    fn clear_input_buffer(&mut self) -> Result<()> {
+        self.buffer.borrow_mut().clear();
+        Ok(())
+    }
+}
+
+impl MyCobotOperator<WebSerial> {
+    /// Async counterpart of `call` that waits up to `timeout_ms` for the reply.
+    pub async fn call_async(
+        &mut self,
+        genre: Command,
+        args: &[i32],
+        timeout_ms: u32,
+    ) -> Result<Vec<i16>> {
+        let command_data = encode_layout(genre.request(), args)?;
+        if genre.response().is_empty() {
+            self.write_command(genre, &command_data)?;
+            return Ok(Vec::new());
+        }
+        // Drop stale bytes so an earlier unanswered reply isn't mistaken for this one.
+        self.connection_mut().clear_input_buffer()?;
+        self.write_command(genre, &command_data)?;
+        match self
+            .connection()
+            .wait_for_frame(genre.as_u8(), timeout_ms)
+            .await
+        {
+            Some(raw) => Ok(self.decode_reply(genre, &raw)),
+            None => Err(anyhow::anyhow!(
+                "No reply to {} within {} ms",
+                genre,
+                timeout_ms
+            )),
+        }
+    }
+    pub async fn get_angles_async(&mut self) -> Result<[f64; 6]> {
+        let res = self
+            .call_async(Command::GetAngles, &[], REPLY_TIMEOUT_MS)
+            .await?;
+        let angles: [i16; 6] = res[..].try_into().map_err(Error::msg)?;
+        Ok(int_vec_to_angles(&angles))
+    }
+    pub async fn get_coords_async(&mut self) -> Result<[f64; 6]> {
+        let res = self
+            .call_async(Command::GetCoords, &[], REPLY_TIMEOUT_MS)
+            .await?;
+        Ok(self.flange_to_tool(&int_vec_to_coords(&res)?))
+    }
+    pub async fn is_moving_async(&mut self) -> Result<bool> {
+        let res = self
+            .call_async(Command::IsMoving, &[], REPLY_TIMEOUT_MS)
+            .await?;
+        Ok(res.first() == Some(&1))
+    }
+}